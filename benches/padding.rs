@@ -0,0 +1,62 @@
+//! Compare building the last PKCS #7 chunk through a `Vec` with filling an array directly
+//!
+//! Run with `cargo bench --bench padding`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use aesculap::padding::{Padding, Pkcs7Padding};
+
+const ITERATIONS: u32 = 1_000_000;
+
+/// The previous implementation, which allocated twice for the last chunk
+fn pad_via_vec(bytes: &[u8]) -> Vec<[u8; 16]> {
+    let mut chunks: Vec<[u8; 16]> = bytes
+        .chunks_exact(16)
+        .map(|c| c.try_into().unwrap())
+        .collect();
+
+    let remainder = bytes.chunks_exact(16).remainder();
+    let missing_bytes = 16 - remainder.len();
+
+    let last_chunk: [u8; 16] = remainder
+        .iter()
+        .chain(vec![missing_bytes as u8; missing_bytes].iter())
+        .copied()
+        .collect::<Vec<u8>>()
+        .try_into()
+        .unwrap();
+    chunks.push(last_chunk);
+
+    chunks
+}
+
+fn bench(name: &str, input: &[u8], f: impl Fn(&[u8]) -> Vec<[u8; 16]>) {
+    // warm up
+    for _ in 0..ITERATIONS / 10 {
+        black_box(f(black_box(input)));
+    }
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f(black_box(input)));
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{name:<28} {:>8.2} ns/iter",
+        elapsed.as_nanos() as f64 / ITERATIONS as f64
+    );
+}
+
+fn main() {
+    for input in [&b"I use Rust btw"[..], b"felis eget nunc lobortis mattis"] {
+        let len = input.len();
+        assert_eq!(pad_via_vec(input), Pkcs7Padding.pad(input).unwrap());
+
+        bench(&format!("Vec ({len} bytes)"), input, pad_via_vec);
+        bench(&format!("array ({len} bytes)"), input, |b| {
+            Pkcs7Padding.pad(b).unwrap()
+        });
+    }
+}
@@ -0,0 +1,54 @@
+//! Compare adding a `u128` round key with xoring a precomputed round key [Block]
+//!
+//! Run with `cargo bench --bench round_keys`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use aesculap::block::Block;
+use aesculap::encryption::encrypt_block;
+use aesculap::key::{AES128Key, RoundKeyProvider};
+
+const ITERATIONS: u32 = 1_000_000;
+
+fn bench(name: &str, mut f: impl FnMut(&mut Block)) {
+    let mut block = Block::from_bytes(*b"I use Rust btw!!");
+
+    // warm up
+    for _ in 0..ITERATIONS / 10 {
+        f(black_box(&mut block));
+    }
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f(black_box(&mut block));
+    }
+    let elapsed = start.elapsed();
+
+    black_box(block);
+    println!(
+        "{name:<28} {:>8.2} ns/iter",
+        elapsed.as_nanos() as f64 / ITERATIONS as f64
+    );
+}
+
+fn main() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let round_keys = key.round_keys();
+    let round_key_blocks = key.round_key_blocks();
+
+    bench("add_round_key (u128)", |block| {
+        for round_key in round_keys {
+            block.add_round_key(round_key);
+        }
+    });
+    bench("^= round key (Block)", |block| {
+        for round_key in round_key_blocks {
+            *block ^= round_key;
+        }
+    });
+
+    bench("encrypt_block (AES-128)", |block| {
+        encrypt_block(block, &key)
+    });
+}
@@ -0,0 +1,44 @@
+//! Compare the portable shift rows variants
+//!
+//! Run with `cargo bench --bench shift_rows`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use aesculap::block::Block;
+
+const ITERATIONS: u32 = 10_000_000;
+
+fn bench(name: &str, shift_rows: fn(&mut Block)) {
+    let mut block = Block::from_bytes(*b"I use Rust btw!!");
+
+    // warm up
+    for _ in 0..ITERATIONS / 10 {
+        shift_rows(black_box(&mut block));
+    }
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        shift_rows(black_box(&mut block));
+    }
+    let elapsed = start.elapsed();
+
+    black_box(block);
+    println!(
+        "{name:<28} {:>8.2} ns/iter",
+        elapsed.as_nanos() as f64 / ITERATIONS as f64
+    );
+}
+
+fn main() {
+    bench("shift_rows (transpose)", Block::shift_rows);
+    bench("shift_rows (permutation)", Block::shift_rows_permutation);
+    bench("shift_rows (table)", Block::shift_rows_table);
+
+    bench("shift_rows_inv (transpose)", Block::shift_rows_inv);
+    bench(
+        "shift_rows_inv (permutation)",
+        Block::shift_rows_inv_permutation,
+    );
+    bench("shift_rows_inv (table)", Block::shift_rows_inv_table);
+}
@@ -0,0 +1,163 @@
+//! AES-GCM
+//!
+//! Galois/Counter Mode as specified in [NIST SP 800-38D](https://csrc.nist.gov/publications/detail/sp/800-38d/final).
+//! Unlike [AES-GCM-SIV](super::encrypt_gcm_siv), reusing a nonce with the same key
+//! is catastrophic: it reveals the XOR of the plaintexts and allows forging tags.
+
+use std::fmt;
+
+use crate::block::Block;
+use crate::encryption::encrypt_block;
+use crate::key::Key;
+use crate::util;
+
+use super::ghash::Ghash;
+
+/// Size of the authentication tag (in bytes)
+const TAG_SIZE: usize = 16;
+
+/// Errors that can occur during AES-GCM decryption
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GcmError {
+    /// The ciphertext is too short to contain a tag
+    InvalidLength,
+
+    /// The tag does not match, so the ciphertext or the associated data was tampered with
+    AuthenticationFailed,
+}
+
+impl fmt::Display for GcmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GcmError::InvalidLength => write!(f, "Ciphertext is too short to contain a tag"),
+            GcmError::AuthenticationFailed => write!(f, "Authentication failed"),
+        }
+    }
+}
+
+impl std::error::Error for GcmError {}
+
+/// Encrypt and authenticate a byte slice using AES-GCM
+///
+/// # Parameters
+/// - `key`: [Key] used for encryption
+/// - `nonce`: 96 bit nonce, must be unique per message
+/// - `aad`: associated data that is authenticated but not encrypted (may be empty)
+/// - `plaintext`: byte slice to encrypt
+///
+/// # Return value
+/// The ciphertext followed by the 16 byte tag.
+pub fn encrypt_gcm<const R: usize, K>(
+    key: &K,
+    nonce: &[u8; 12],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Vec<u8>
+where
+    K: Key<R>,
+{
+    log::trace!("AES-GCM encryption");
+
+    let mut ciphertext = ctr(key, nonce, plaintext);
+    let tag = compute_tag(key, nonce, aad, &ciphertext);
+    ciphertext.extend_from_slice(&tag);
+
+    ciphertext
+}
+
+/// Verify and decrypt a byte slice using AES-GCM
+///
+/// # Parameters
+/// - `key`: [Key] that was used for encryption
+/// - `nonce`: 96 bit nonce that was used for encryption
+/// - `aad`: associated data that was authenticated
+/// - `ciphertext`: ciphertext followed by the 16 byte tag
+///
+/// # Return value
+/// The tag is checked (in constant time) before anything is decrypted,
+/// so no plaintext is produced for a tampered message.
+pub fn decrypt_gcm<const R: usize, K>(
+    key: &K,
+    nonce: &[u8; 12],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, GcmError>
+where
+    K: Key<R>,
+{
+    log::trace!("AES-GCM decryption");
+
+    if ciphertext.len() < TAG_SIZE {
+        return Err(GcmError::InvalidLength);
+    }
+
+    let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - TAG_SIZE);
+
+    let expected_tag = compute_tag(key, nonce, aad, ciphertext);
+    if !util::ct_eq(&expected_tag, tag) {
+        log::error!("{}", GcmError::AuthenticationFailed);
+        return Err(GcmError::AuthenticationFailed);
+    }
+
+    Ok(ctr(key, nonce, ciphertext))
+}
+
+/// Pre-counter block `J0` for a 96 bit nonce
+fn pre_counter_block(nonce: &[u8; 12]) -> [u8; 16] {
+    let mut j0 = [0; 16];
+    j0[..12].copy_from_slice(nonce);
+    j0[15] = 1;
+    j0
+}
+
+/// Compute the tag from GHASH over the associated data, the ciphertext and their lengths
+fn compute_tag<const R: usize, K>(
+    key: &K,
+    nonce: &[u8; 12],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> [u8; TAG_SIZE]
+where
+    K: Key<R>,
+{
+    let mut h = Block::from(0);
+    encrypt_block(&mut h, key);
+
+    let mut length_block = [0; 16];
+    length_block[..8].copy_from_slice(&(aad.len() as u64 * 8).to_be_bytes());
+    length_block[8..].copy_from_slice(&(ciphertext.len() as u64 * 8).to_be_bytes());
+
+    let mut ghash = Ghash::new(h.dump_bytes());
+    ghash.update_padded(aad);
+    ghash.update_padded(ciphertext);
+    ghash.update_padded(&length_block);
+
+    let mut tag = Block::from_bytes(pre_counter_block(nonce));
+    encrypt_block(&mut tag, key);
+    tag ^= Block::from_bytes(ghash.finalize());
+
+    tag.dump_bytes()
+}
+
+/// Counter mode starting at `J0 + 1`
+///
+/// The last 32 bits of the counter block are incremented as big-endian integer and wrap around.
+fn ctr<const R: usize, K>(key: &K, nonce: &[u8; 12], bytes: &[u8]) -> Vec<u8>
+where
+    K: Key<R>,
+{
+    let mut counter = pre_counter_block(nonce);
+
+    let mut output = Vec::with_capacity(bytes.len());
+    for chunk in bytes.chunks(16) {
+        let count = u32::from_be_bytes(counter[12..].try_into().unwrap()).wrapping_add(1);
+        counter[12..].copy_from_slice(&count.to_be_bytes());
+
+        let mut keystream = Block::from_bytes(counter);
+        encrypt_block(&mut keystream, key);
+
+        output.extend(chunk.iter().zip(keystream.dump_bytes()).map(|(b, k)| b ^ k));
+    }
+
+    output
+}
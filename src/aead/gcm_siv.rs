@@ -0,0 +1,266 @@
+//! AES-GCM-SIV
+//!
+//! Nonce-misuse-resistant authenticated encryption as specified in [RFC 8452](https://www.rfc-editor.org/rfc/rfc8452).
+//! Reusing a nonce does not break confidentiality,
+//! it only reveals whether two messages (with the same associated data) are identical.
+
+use std::fmt;
+
+use crate::block::Block;
+use crate::encryption::encrypt_block;
+use crate::key::{AES128Key, AES256Key, Key};
+use crate::util;
+
+use super::polyval::Polyval;
+
+/// Size of the authentication tag (in bytes)
+const TAG_SIZE: usize = 16;
+
+/// A key that can be used as key-generating key for AES-GCM-SIV
+///
+/// RFC 8452 only specifies [AES-128](AES128Key) and [AES-256](AES256Key).
+pub trait GcmSivKey: Sized {
+    /// Derive the per-nonce message authentication key and message encryption key
+    fn derive_keys(&self, nonce: &[u8; 12]) -> ([u8; 16], Self);
+
+    /// Encrypt a single [Block]
+    fn encrypt(&self, block: &mut Block);
+}
+
+impl GcmSivKey for AES128Key {
+    fn derive_keys(&self, nonce: &[u8; 12]) -> ([u8; 16], Self) {
+        let material: [u8; 32] = derive_key_material(self, nonce);
+
+        (
+            material[..16].try_into().unwrap(),
+            AES128Key::from_bytes(material[16..].try_into().unwrap()),
+        )
+    }
+
+    fn encrypt(&self, block: &mut Block) {
+        encrypt_block(block, self);
+    }
+}
+
+impl GcmSivKey for AES256Key {
+    fn derive_keys(&self, nonce: &[u8; 12]) -> ([u8; 16], Self) {
+        let material: [u8; 48] = derive_key_material(self, nonce);
+
+        (
+            material[..16].try_into().unwrap(),
+            AES256Key::from_bytes(material[16..].try_into().unwrap()),
+        )
+    }
+
+    fn encrypt(&self, block: &mut Block) {
+        encrypt_block(block, self);
+    }
+}
+
+/// Errors that can occur during AES-GCM-SIV decryption
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GcmSivError {
+    /// The ciphertext is too short to contain a tag
+    InvalidLength,
+
+    /// The tag does not match, so the ciphertext or the associated data was tampered with
+    AuthenticationFailed,
+}
+
+impl fmt::Display for GcmSivError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GcmSivError::InvalidLength => write!(f, "Ciphertext is too short to contain a tag"),
+            GcmSivError::AuthenticationFailed => write!(f, "Authentication failed"),
+        }
+    }
+}
+
+impl std::error::Error for GcmSivError {}
+
+/// Encrypt and authenticate a byte slice using AES-GCM-SIV
+///
+/// # Parameters
+/// - `key`: key-generating [GcmSivKey]
+/// - `nonce`: 96 bit nonce, should be unique per message
+/// - `aad`: associated data that is authenticated but not encrypted
+/// - `plaintext`: byte slice to encrypt
+///
+/// # Return value
+/// The ciphertext followed by the 16 byte tag.
+pub fn encrypt_gcm_siv<K>(key: &K, nonce: &[u8; 12], aad: &[u8], plaintext: &[u8]) -> Vec<u8>
+where
+    K: GcmSivKey,
+{
+    log::trace!("AES-GCM-SIV encryption");
+
+    let (auth_key, enc_key) = key.derive_keys(nonce);
+    let tag = compute_tag(&auth_key, &enc_key, nonce, aad, plaintext);
+
+    let mut ciphertext = ctr(&enc_key, tag, plaintext);
+    ciphertext.extend_from_slice(&tag);
+
+    ciphertext
+}
+
+/// Verify and decrypt a byte slice using AES-GCM-SIV
+///
+/// # Parameters
+/// - `key`: key-generating [GcmSivKey]
+/// - `nonce`: 96 bit nonce that was used for encryption
+/// - `aad`: associated data that was authenticated
+/// - `ciphertext`: ciphertext followed by the 16 byte tag
+///
+/// # Return value
+/// The plaintext is only returned if the tag is valid.
+/// The tag comparison is done in constant time.
+pub fn decrypt_gcm_siv<K>(
+    key: &K,
+    nonce: &[u8; 12],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, GcmSivError>
+where
+    K: GcmSivKey,
+{
+    log::trace!("AES-GCM-SIV decryption");
+
+    if ciphertext.len() < TAG_SIZE {
+        return Err(GcmSivError::InvalidLength);
+    }
+
+    let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - TAG_SIZE);
+    let tag: [u8; TAG_SIZE] = tag.try_into().unwrap();
+
+    let (auth_key, enc_key) = key.derive_keys(nonce);
+    let plaintext = ctr(&enc_key, tag, ciphertext);
+
+    let expected_tag = compute_tag(&auth_key, &enc_key, nonce, aad, &plaintext);
+    if !util::ct_eq(&expected_tag, &tag) {
+        log::error!("{}", GcmSivError::AuthenticationFailed);
+        return Err(GcmSivError::AuthenticationFailed);
+    }
+
+    Ok(plaintext)
+}
+
+/// Derive key material by encrypting the nonce prefixed with a little-endian counter
+///
+/// Only the first 8 bytes of each encrypted [Block] are used.
+fn derive_key_material<const R: usize, const N: usize, K>(key: &K, nonce: &[u8; 12]) -> [u8; N]
+where
+    K: Key<R>,
+{
+    let mut material = [0; N];
+
+    for (i, chunk) in material.chunks_exact_mut(8).enumerate() {
+        let mut bytes = [0; 16];
+        bytes[..4].copy_from_slice(&(i as u32).to_le_bytes());
+        bytes[4..].copy_from_slice(nonce);
+
+        let mut block = Block::from_bytes(bytes);
+        encrypt_block(&mut block, key);
+        chunk.copy_from_slice(&block.dump_bytes()[..8]);
+    }
+
+    material
+}
+
+/// Compute the tag from POLYVAL over the associated data, the plaintext and their lengths
+fn compute_tag<K>(
+    auth_key: &[u8; 16],
+    enc_key: &K,
+    nonce: &[u8; 12],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> [u8; TAG_SIZE]
+where
+    K: GcmSivKey,
+{
+    let mut length_block = [0; 16];
+    length_block[..8].copy_from_slice(&(aad.len() as u64 * 8).to_le_bytes());
+    length_block[8..].copy_from_slice(&(plaintext.len() as u64 * 8).to_le_bytes());
+
+    let mut polyval = Polyval::new(*auth_key);
+    polyval.update_padded(aad);
+    polyval.update_padded(plaintext);
+    polyval.update_padded(&length_block);
+
+    let mut s = polyval.finalize();
+    for (s, n) in s.iter_mut().zip(nonce) {
+        *s ^= n;
+    }
+    s[15] &= 0x7f;
+
+    let mut tag = Block::from_bytes(s);
+    enc_key.encrypt(&mut tag);
+
+    tag.dump_bytes()
+}
+
+/// Counter mode keyed by the tag
+///
+/// The first 32 bits of the counter block are incremented as little-endian integer and wrap around.
+fn ctr<K>(key: &K, tag: [u8; TAG_SIZE], bytes: &[u8]) -> Vec<u8>
+where
+    K: GcmSivKey,
+{
+    let mut counter = tag;
+    counter[15] |= 0x80;
+
+    let mut output = Vec::with_capacity(bytes.len());
+    for chunk in bytes.chunks(16) {
+        let mut keystream = Block::from_bytes(counter);
+        key.encrypt(&mut keystream);
+
+        output.extend(chunk.iter().zip(keystream.dump_bytes()).map(|(b, k)| b ^ k));
+
+        let count = u32::from_le_bytes(counter[..4].try_into().unwrap()).wrapping_add(1);
+        counter[..4].copy_from_slice(&count.to_le_bytes());
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_derivation() {
+        // see https://www.rfc-editor.org/rfc/rfc8452#appendix-C.1
+        let key = AES128Key::from_bytes(0x01000000000000000000000000000000_u128.to_be_bytes());
+        let nonce = [0x03, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+        let (auth_key, enc_key) = key.derive_keys(&nonce);
+
+        let expected_auth_key = 0xd9b360279694941ac5dbc6987ada7377_u128.to_be_bytes();
+        let expected_enc_key =
+            AES128Key::from_bytes(0x4004a0dcd862f2a57360219d2d44ef6c_u128.to_be_bytes());
+
+        assert_eq!(auth_key, expected_auth_key);
+        assert_eq!(
+            enc_key.generate_round_keys(),
+            expected_enc_key.generate_round_keys()
+        );
+    }
+
+    #[test]
+    fn counter_wraps_in_first_word() {
+        let key = AES128Key::from_bytes([0; 16]);
+
+        let mut tag = [0; TAG_SIZE];
+        tag[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        tag[4] = 0x42;
+
+        let keystream = ctr(&key, tag, &[0; 32]);
+
+        let mut wrapped = tag;
+        wrapped[..4].copy_from_slice(&[0; 4]);
+        wrapped[15] |= 0x80;
+        let mut expected = Block::from_bytes(wrapped);
+        encrypt_block(&mut expected, &key);
+
+        assert_eq!(keystream[16..], expected.dump_bytes());
+    }
+}
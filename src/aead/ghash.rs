@@ -0,0 +1,77 @@
+//! GHASH universal hash
+//!
+//! GHASH is the authentication part of GCM, a polynomial hash over GF(2^128).
+//! For reference, see [NIST SP 800-38D](https://csrc.nist.gov/publications/detail/sp/800-38d/final).
+
+/// Reduction constant of the GHASH field
+pub(super) const R: u128 = 0xe1 << 120;
+
+/// Multiply two elements of the GHASH field
+///
+/// The loop does not branch on the operands to avoid timing leaks.
+pub(super) fn gf_mul(x: u128, y: u128) -> u128 {
+    let mut z = 0;
+    let mut v = y;
+
+    for i in 0..128 {
+        let bit = (x >> (127 - i)) & 1;
+        z ^= v & 0u128.wrapping_sub(bit);
+
+        let carry = v & 1;
+        v = (v >> 1) ^ (R & 0u128.wrapping_sub(carry));
+    }
+
+    z
+}
+
+/// Incremental GHASH computation
+pub struct Ghash {
+    h: u128,
+    s: u128,
+}
+
+impl Ghash {
+    /// Constructor that takes the hash subkey
+    pub fn new(h: [u8; 16]) -> Self {
+        Self {
+            h: u128::from_be_bytes(h),
+            s: 0,
+        }
+    }
+
+    /// Absorb bytes, the last partial chunk is filled with zeroes
+    pub fn update_padded(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(16) {
+            let mut x = [0; 16];
+            x[..chunk.len()].copy_from_slice(chunk);
+
+            self.s = gf_mul(self.s ^ u128::from_be_bytes(x), self.h);
+        }
+    }
+
+    /// Get the resulting hash
+    pub fn finalize(self) -> [u8; 16] {
+        self.s.to_be_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ghash_test_case_2() {
+        // see the GCM specification (McGrew, Viega), Test Case 2
+        let h = 0x66e94bd4ef8a2c3b884cfa59ca342b2e_u128.to_be_bytes();
+        let c = 0x0388dace60b6a392f328c2b971b2fe78_u128.to_be_bytes();
+        let lengths = 0x00000000000000000000000000000080_u128.to_be_bytes();
+
+        let mut ghash = Ghash::new(h);
+        ghash.update_padded(&c);
+        ghash.update_padded(&lengths);
+
+        let expected = 0xf38cbb1ad69223dcc3457ae5b6b0f885_u128.to_be_bytes();
+
+        assert_eq!(ghash.finalize(), expected);
+    }
+}
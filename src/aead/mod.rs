@@ -0,0 +1,23 @@
+//! Authenticated encryption module
+//!
+//! This module provides authenticated encryption with associated data (AEAD).
+//! Besides keeping the plaintext confidential, these modes detect any tampering
+//! with the ciphertext or the associated data.
+//!
+//! Implemented modes:
+//! - [AES-GCM](encrypt_gcm)
+//! - [AES-GCM-SIV](encrypt_gcm_siv) (nonce-misuse-resistant)
+//!
+//! [seal] and [open] wrap AES-GCM-SIV with a random nonce and are the recommended entry point.
+
+mod gcm;
+mod gcm_siv;
+mod ghash;
+mod polyval;
+mod seal;
+
+pub use gcm::{decrypt_gcm, encrypt_gcm, GcmError};
+pub use gcm_siv::{decrypt_gcm_siv, encrypt_gcm_siv, GcmSivError, GcmSivKey};
+#[cfg(feature = "rand")]
+pub use seal::seal;
+pub use seal::{open, NONCE_SIZE};
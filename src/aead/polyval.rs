@@ -0,0 +1,64 @@
+//! POLYVAL universal hash
+//!
+//! POLYVAL is the little-endian counterpart of GHASH over GF(2^128).
+//! It is computed via GHASH arithmetic as described in
+//! [RFC 8452, Appendix A](https://www.rfc-editor.org/rfc/rfc8452#appendix-A).
+
+use super::ghash::{gf_mul, R};
+
+/// Multiply an element of the GHASH field by `x`
+fn mul_x(v: u128) -> u128 {
+    (v >> 1) ^ (R & 0u128.wrapping_sub(v & 1))
+}
+
+/// Incremental POLYVAL computation
+pub struct Polyval {
+    h: u128,
+    s: u128,
+}
+
+impl Polyval {
+    /// Constructor that takes the hash key
+    pub fn new(h: [u8; 16]) -> Self {
+        Self {
+            h: mul_x(u128::from_le_bytes(h)),
+            s: 0,
+        }
+    }
+
+    /// Absorb bytes, the last partial chunk is filled with zeroes
+    pub fn update_padded(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(16) {
+            let mut x = [0; 16];
+            x[..chunk.len()].copy_from_slice(chunk);
+
+            self.s = gf_mul(self.s ^ u128::from_le_bytes(x), self.h);
+        }
+    }
+
+    /// Get the resulting hash
+    pub fn finalize(self) -> [u8; 16] {
+        self.s.to_le_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polyval_rfc8452() {
+        // see https://www.rfc-editor.org/rfc/rfc8452#appendix-A
+        let h = 0x25629347589242761d31f826ba4b757b_u128.to_be_bytes();
+        let x1 = 0x4f4f95668c83dfb6401762bb2d01a262_u128.to_be_bytes();
+        let x2 = 0xd1a24ddd2721d006bbe45f20d3c9f362_u128.to_be_bytes();
+
+        let mut polyval = Polyval::new(h);
+        polyval.update_padded(&x1);
+        polyval.update_padded(&x2);
+
+        let expected = 0xf7a3b47b846119fae5b7866cf5e5b77e_u128.to_be_bytes();
+
+        assert_eq!(polyval.finalize(), expected);
+    }
+}
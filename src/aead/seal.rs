@@ -0,0 +1,64 @@
+//! seal/open
+//!
+//! The recommended way to encrypt a message if there is no format to be compatible with.
+//! [seal] picks a random nonce for [AES-GCM-SIV](encrypt_gcm_siv) and prepends it to the ciphertext,
+//! so the sealed message is self-contained: `nonce (12 bytes) || ciphertext || tag (16 bytes)`.
+//! Since AES-GCM-SIV is nonce-misuse-resistant, even a repeated random nonce doesn't break confidentiality.
+
+#[cfg(feature = "rand")]
+use super::gcm_siv::encrypt_gcm_siv;
+use super::gcm_siv::{decrypt_gcm_siv, GcmSivError, GcmSivKey};
+
+/// Size of the nonce in front of a sealed message (in bytes)
+pub const NONCE_SIZE: usize = 12;
+
+/// Encrypt and authenticate a byte slice with a random nonce
+///
+/// # Parameters
+/// - `key`: [GcmSivKey], i.e. an [AES-128](crate::key::AES128Key) or [AES-256](crate::key::AES256Key) key
+/// - `plaintext`: byte slice to encrypt
+/// - `aad`: associated data that is authenticated but not encrypted (may be empty), it is not part of the sealed message
+///
+/// # Return value
+/// The nonce, the ciphertext and the 16 byte tag, which can be passed to [open].
+#[cfg(feature = "rand")]
+pub fn seal<K>(key: &K, plaintext: &[u8], aad: &[u8]) -> Vec<u8>
+where
+    K: GcmSivKey,
+{
+    use rand::Rng;
+
+    log::trace!("Seal message");
+
+    let nonce: [u8; NONCE_SIZE] = rand::thread_rng().gen();
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend(encrypt_gcm_siv(key, &nonce, aad, plaintext));
+
+    sealed
+}
+
+/// Verify and decrypt a message of [seal]
+///
+/// # Parameters
+/// - `key`: [GcmSivKey] that was used for sealing
+/// - `sealed`: the nonce, the ciphertext and the tag
+/// - `aad`: associated data that was authenticated
+///
+/// # Return value
+/// The plaintext is only returned if the tag is valid.
+/// A message that is too short for nonce and tag fails with [InvalidLength](GcmSivError::InvalidLength).
+pub fn open<K>(key: &K, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, GcmSivError>
+where
+    K: GcmSivKey,
+{
+    log::trace!("Open sealed message");
+
+    if sealed.len() < NONCE_SIZE {
+        log::error!("{}", GcmSivError::InvalidLength);
+        return Err(GcmSivError::InvalidLength);
+    }
+
+    let (nonce, ciphertext) = sealed.split_at(NONCE_SIZE);
+    decrypt_gcm_siv(key, nonce.try_into().unwrap(), aad, ciphertext)
+}
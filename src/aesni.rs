@@ -0,0 +1,134 @@
+//! AES-NI module
+//!
+//! This module provides the rounds of AES with the AES-NI instructions of x86_64 CPUs.
+//! [encrypt_block](crate::encryption::encrypt_block) and [decrypt_block](crate::decryption::decrypt_block)
+//! use them instead of the software implementation if the CPU supports them.
+//!
+//! For reference, see the [Intel white paper](https://www.intel.com/content/dam/doc/white-paper/advanced-encryption-standard-new-instructions-set-paper.pdf).
+
+use std::arch::x86_64::*;
+use std::sync::OnceLock;
+
+use crate::block::Block;
+use crate::Backend;
+
+/// Check the CPU on the first call and remember the result
+pub fn backend() -> Backend {
+    static BACKEND: OnceLock<Backend> = OnceLock::new();
+
+    *BACKEND.get_or_init(|| {
+        if is_x86_feature_detected!("aes") {
+            log::trace!("Use AES-NI");
+            Backend::AesNi
+        } else {
+            log::trace!("CPU does not support AES-NI, use the software implementation");
+            Backend::Software
+        }
+    })
+}
+
+/// Encrypt a [Block] with the given round keys
+///
+/// # Safety
+/// The CPU must support AES-NI, see [backend].
+#[target_feature(enable = "aes")]
+pub unsafe fn encrypt_block<const R: usize>(block: &mut Block, round_keys: &[Block; R]) {
+    let mut state = _mm_xor_si128(load(block), load(&round_keys[0]));
+
+    for round_key in &round_keys[1..R - 1] {
+        state = _mm_aesenc_si128(state, load(round_key));
+    }
+
+    state = _mm_aesenclast_si128(state, load(&round_keys[R - 1]));
+    store(state, block);
+}
+
+/// Decrypt a [Block] with the given round keys
+///
+/// `aesdec` implements the equivalent inverse cipher,
+/// so the inner round keys have to go through inverse mix columns first.
+///
+/// # Safety
+/// The CPU must support AES-NI, see [backend].
+#[target_feature(enable = "aes")]
+pub unsafe fn decrypt_block<const R: usize>(block: &mut Block, round_keys: &[Block; R]) {
+    let mut state = _mm_xor_si128(load(block), load(&round_keys[R - 1]));
+
+    for round_key in round_keys[1..R - 1].iter().rev() {
+        state = _mm_aesdec_si128(state, _mm_aesimc_si128(load(round_key)));
+    }
+
+    state = _mm_aesdeclast_si128(state, load(&round_keys[0]));
+    store(state, block);
+}
+
+fn load(block: &Block) -> __m128i {
+    let bytes = block.dump_bytes();
+
+    // SAFETY: `bytes` has 16 bytes and the load doesn't need to be aligned
+    unsafe { _mm_loadu_si128(bytes.as_ptr().cast()) }
+}
+
+fn store(state: __m128i, block: &mut Block) {
+    let mut bytes = [0; 16];
+
+    // SAFETY: `bytes` has 16 bytes and the store doesn't need to be aligned
+    unsafe { _mm_storeu_si128(bytes.as_mut_ptr().cast(), state) };
+
+    *block = Block::from_bytes(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decryption::decrypt_block_software;
+    use crate::encryption::encrypt_block_software;
+    use crate::key::{AES128Key, AES192Key, AES256Key, Key};
+
+    fn check<const R: usize, K: Key<R>>(key: &K, ciphertext: u128) {
+        let plaintext = Block::from(0x00112233445566778899aabbccddeeff);
+        let round_keys = key.round_key_blocks();
+
+        let mut software = plaintext;
+        encrypt_block_software(&mut software, round_keys);
+        assert_eq!(software, Block::from(ciphertext));
+
+        let mut hardware = plaintext;
+        // SAFETY: only called if the CPU supports AES-NI
+        unsafe { encrypt_block(&mut hardware, &round_keys) };
+        assert_eq!(hardware, software);
+
+        decrypt_block_software(&mut software, round_keys);
+        // SAFETY: only called if the CPU supports AES-NI
+        unsafe { decrypt_block(&mut hardware, &round_keys) };
+        assert_eq!(software, plaintext);
+        assert_eq!(hardware, plaintext);
+    }
+
+    #[test]
+    fn software_and_aes_ni_agree() {
+        if backend() != Backend::AesNi {
+            eprintln!("CPU does not support AES-NI, skipping");
+            return;
+        }
+
+        // see FIPS 197, Appendix C
+        let key_bytes = std::array::from_fn(|i| i as u8);
+        check(
+            &AES128Key::from_bytes(key_bytes),
+            0x69c4e0d86a7b0430d8cdb78070b4c55a,
+        );
+
+        let key_bytes = std::array::from_fn(|i| i as u8);
+        check(
+            &AES192Key::from_bytes(key_bytes),
+            0xdda97ca4864cdfe06eaf70a0ec0d7191,
+        );
+
+        let key_bytes = std::array::from_fn(|i| i as u8);
+        check(
+            &AES256Key::from_bytes(key_bytes),
+            0x8ea2b7ca516745bfeafc49904b496089,
+        );
+    }
+}
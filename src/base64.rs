@@ -0,0 +1,338 @@
+//! Base64 module
+//!
+//! This module provides the standard Base64 encoding (with padding) that is used to pass keys and IVs as text.
+//! [EncodingWriter] and [DecodingReader] do the same for data of any size, wrapped into lines.
+//!
+//! For reference, see [RFC 4648](https://www.rfc-editor.org/rfc/rfc4648#section-4).
+
+use std::io::{self, Read, Write};
+
+/// Digits of the standard alphabet
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode bytes as Base64
+pub fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Decode Base64
+///
+/// The padding is required, whitespace is not allowed and the unused bits before the padding have to be zero,
+/// so every byte sequence has exactly one encoding.
+pub fn decode(s: &str) -> Result<Vec<u8>, &'static str> {
+    if !s.len().is_multiple_of(4) {
+        return Err("Base64 must consist of groups of 4 characters");
+    }
+
+    let groups = s.as_bytes().chunks_exact(4);
+    let last = groups.len().saturating_sub(1);
+
+    let mut decoded = Vec::with_capacity(s.len() / 4 * 3);
+    for (i, group) in groups.enumerate() {
+        let padding = group.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && i != last) {
+            return Err("Invalid Base64 padding");
+        }
+
+        let mut value = 0u32;
+        for c in &group[..4 - padding] {
+            let digit = ALPHABET
+                .iter()
+                .position(|a| a == c)
+                .ok_or("Invalid Base64 character")?;
+            value = value << 6 | digit as u32;
+        }
+        value <<= 6 * padding;
+
+        let bytes = &value.to_be_bytes()[1..];
+        let len = 3 - padding;
+        if bytes[len..].iter().any(|&b| b != 0) {
+            return Err("Invalid Base64 padding");
+        }
+        decoded.extend_from_slice(&bytes[..len]);
+    }
+
+    Ok(decoded)
+}
+
+/// Writer adapter that encodes everything written to it as Base64
+///
+/// The encoding is broken into lines of at most `line_length` characters.
+/// [EncodingWriter::finish] has to be called at the end to write the last group with its padding.
+pub struct EncodingWriter<W> {
+    writer: W,
+    line_length: usize,
+    column: usize,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> EncodingWriter<W> {
+    /// Constructor that takes the inner writer and the maximal length of a line
+    pub fn new(writer: W, line_length: usize) -> Self {
+        assert!(
+            line_length > 0 && line_length.is_multiple_of(4),
+            "The line length must be a positive multiple of 4"
+        );
+
+        Self {
+            writer,
+            line_length,
+            column: 0,
+            pending: Vec::with_capacity(3),
+        }
+    }
+
+    /// Encode the remaining bytes, end the last line, flush the inner writer and return it
+    pub fn finish(mut self) -> io::Result<W> {
+        let pending = std::mem::take(&mut self.pending);
+        self.write_encoded(&pending)?;
+        if self.column > 0 {
+            self.writer.write_all(b"\n")?;
+        }
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    /// Encode bytes and break the encoding into lines
+    fn write_encoded(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let encoded = encode(bytes);
+        let mut encoded = encoded.as_bytes();
+
+        while !encoded.is_empty() {
+            if self.column == self.line_length {
+                self.writer.write_all(b"\n")?;
+                self.column = 0;
+            }
+
+            let (line, rest) = encoded.split_at(encoded.len().min(self.line_length - self.column));
+            self.writer.write_all(line)?;
+            self.column += line.len();
+            encoded = rest;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for EncodingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+
+        let complete = self.pending.len() / 3 * 3;
+        let bytes: Vec<u8> = self.pending.drain(..complete).collect();
+        self.write_encoded(&bytes)?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // an incomplete group can't be encoded before the rest of it is known
+        self.writer.flush()
+    }
+}
+
+/// Number of characters that are requested from the inner reader at once
+const READ_SIZE: usize = 4096;
+
+/// Reader adapter that decodes Base64 while it is read
+///
+/// Whitespace (e.g. line breaks) is skipped, everything else has to be valid Base64 like for [decode].
+/// Reading fails with [InvalidData](io::ErrorKind::InvalidData) if it isn't.
+pub struct DecodingReader<R> {
+    reader: R,
+    pending: Vec<u8>,
+    decoded: Vec<u8>,
+    position: usize,
+    padded: bool,
+    done: bool,
+}
+
+impl<R: Read> DecodingReader<R> {
+    /// Constructor that takes the inner reader
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            pending: Vec::new(),
+            decoded: Vec::new(),
+            position: 0,
+            padded: false,
+            done: false,
+        }
+    }
+
+    /// Read the next chunk and decode all complete groups of it
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0; READ_SIZE];
+        let n = loop {
+            match self.reader.read(&mut chunk) {
+                Ok(n) => break n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        };
+
+        if n == 0 {
+            self.done = true;
+            if !self.pending.is_empty() {
+                return Err(invalid_data(
+                    "Base64 must consist of groups of 4 characters",
+                ));
+            }
+            return Ok(());
+        }
+
+        self.pending
+            .extend(chunk[..n].iter().filter(|c| !c.is_ascii_whitespace()));
+
+        let complete = self.pending.len() / 4 * 4;
+        if complete == 0 {
+            return Ok(());
+        }
+        if self.padded {
+            return Err(invalid_data("Invalid Base64 padding"));
+        }
+
+        let groups: Vec<u8> = self.pending.drain(..complete).collect();
+        let groups =
+            std::str::from_utf8(&groups).map_err(|_| invalid_data("Invalid Base64 character"))?;
+        self.padded = groups.ends_with('=');
+        self.decoded = decode(groups).map_err(invalid_data)?;
+        self.position = 0;
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.decoded.len() && !self.done {
+            self.fill()?;
+        }
+
+        let n = buf.len().min(self.decoded.len() - self.position);
+        buf[..n].copy_from_slice(&self.decoded[self.position..][..n]);
+        self.position += n;
+
+        Ok(n)
+    }
+}
+
+fn invalid_data(err: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc4648_examples() {
+        // see https://www.rfc-editor.org/rfc/rfc4648#section-10
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+
+        for (bytes, encoded) in vectors {
+            assert_eq!(encode(bytes.as_bytes()), encoded);
+            assert_eq!(decode(encoded).unwrap(), bytes.as_bytes());
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes: Vec<u8> = (0..=255).collect();
+
+        for len in 0..bytes.len() {
+            assert_eq!(decode(&encode(&bytes[..len])).unwrap(), &bytes[..len]);
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        for encoded in [
+            "Zg",
+            "Zg=",
+            "Zm9vY",
+            "Z===",
+            "====",
+            "Zh==",
+            "Zm9=",
+            "Zg==Zg==",
+            "Zm9-",
+            "Zm9v YmFy",
+            "Zm9v\n",
+        ] {
+            assert!(decode(encoded).is_err(), "{encoded:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn streaming_round_trip() {
+        let bytes: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+
+        for step in [1, 2, 3, 7, 64, 1000] {
+            let mut writer = EncodingWriter::new(Vec::new(), 64);
+            for chunk in bytes.chunks(step) {
+                writer.write_all(chunk).unwrap();
+            }
+            let armored = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+            assert!(armored.ends_with('\n'));
+            assert!(armored.lines().all(|line| line.len() <= 64));
+            assert_eq!(armored.replace('\n', ""), encode(&bytes));
+
+            let mut decoded = Vec::new();
+            DecodingReader::new(armored.as_bytes())
+                .read_to_end(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded, bytes);
+        }
+    }
+
+    #[test]
+    fn streaming_malformed() {
+        for encoded in [
+            &b"Zg"[..],
+            b"Zm9vY",
+            b"Zg==Zg==",
+            b"Zg==\nZm9v",
+            b"Zm9-",
+            b"Zm9\xff",
+        ] {
+            let err = DecodingReader::new(encoded)
+                .read_to_end(&mut Vec::new())
+                .unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{encoded:?}");
+        }
+
+        let mut decoded = Vec::new();
+        DecodingReader::new(" Zm9v\r\nYmFy\t\n".as_bytes())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, b"foobar");
+    }
+}
@@ -0,0 +1,143 @@
+//! BLAKE2b module
+//!
+//! This module provides the BLAKE2b message digest that Argon2 is built on.
+//!
+//! For reference, see [RFC 7693](https://www.rfc-editor.org/rfc/rfc7693).
+
+/// Initialization vector (the same as the initial hash value of SHA-512)
+const IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+/// Message word schedule of the rounds
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// Size of a block (in bytes)
+const BLOCK_SIZE: usize = 128;
+
+/// Compute the (unkeyed) BLAKE2b digest of a byte slice with a size of `out_len` bytes
+///
+/// # Panics
+/// Panics if `out_len` is not between 1 and 64.
+pub fn blake2b(bytes: &[u8], out_len: usize) -> Vec<u8> {
+    assert!(
+        (1..=64).contains(&out_len),
+        "BLAKE2b digests have 1 to 64 bytes"
+    );
+
+    let mut state = IV;
+    state[0] ^= 0x01010000 ^ out_len as u64;
+
+    // the empty message is processed as a single block of zeroes
+    let block_count = bytes.len().div_ceil(BLOCK_SIZE).max(1);
+    for i in 0..block_count {
+        let chunk = &bytes[i * BLOCK_SIZE..bytes.len().min((i + 1) * BLOCK_SIZE)];
+
+        let mut block = [0; BLOCK_SIZE];
+        block[..chunk.len()].copy_from_slice(chunk);
+
+        let counter = (i * BLOCK_SIZE + chunk.len()) as u128;
+        compress(&mut state, &block, counter, i == block_count - 1);
+    }
+
+    let digest: Vec<u8> = state.iter().flat_map(|word| word.to_le_bytes()).collect();
+    digest[..out_len].to_vec()
+}
+
+/// Process a single 1024 bit block
+///
+/// `counter` is the number of bytes processed so far, including this block.
+fn compress(state: &mut [u64; 8], block: &[u8; BLOCK_SIZE], counter: u128, last: bool) {
+    let m: [u64; 16] =
+        std::array::from_fn(|i| u64::from_le_bytes(block[i * 8..i * 8 + 8].try_into().unwrap()));
+
+    let mut v = [0; 16];
+    v[..8].copy_from_slice(state);
+    v[8..].copy_from_slice(&IV);
+    v[12] ^= counter as u64;
+    v[13] ^= (counter >> 64) as u64;
+    if last {
+        v[14] = !v[14];
+    }
+
+    for round in 0..12 {
+        let s = &SIGMA[round % 10];
+
+        mix(&mut v, [0, 4, 8, 12], m[s[0]], m[s[1]]);
+        mix(&mut v, [1, 5, 9, 13], m[s[2]], m[s[3]]);
+        mix(&mut v, [2, 6, 10, 14], m[s[4]], m[s[5]]);
+        mix(&mut v, [3, 7, 11, 15], m[s[6]], m[s[7]]);
+        mix(&mut v, [0, 5, 10, 15], m[s[8]], m[s[9]]);
+        mix(&mut v, [1, 6, 11, 12], m[s[10]], m[s[11]]);
+        mix(&mut v, [2, 7, 8, 13], m[s[12]], m[s[13]]);
+        mix(&mut v, [3, 4, 9, 14], m[s[14]], m[s[15]]);
+    }
+
+    for (i, s) in state.iter_mut().enumerate() {
+        *s ^= v[i] ^ v[i + 8];
+    }
+}
+
+/// Mixing function `G`
+fn mix(v: &mut [u64; 16], [a, b, c, d]: [usize; 4], x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex;
+
+    #[test]
+    fn rfc7693_example() {
+        // see https://www.rfc-editor.org/rfc/rfc7693#appendix-A
+        assert_eq!(
+            blake2b(b"abc", 64),
+            hex::decode("ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923").unwrap()
+        );
+    }
+
+    #[test]
+    fn block_boundaries() {
+        let bytes: Vec<u8> = (0..=255).collect();
+
+        let vectors = [
+            (0, 64, "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"),
+            (127, 64, "b6292669ccd38d5f01caae96ba272c76a879a45743afa0725d83b9ebb26665b731f1848c52f11972b6644f554c064fa90780dbbbf3a89d4fc31f67df3e5857ef"),
+            (128, 64, "2319e3789c47e2daa5fe807f61bec2a1a6537fa03f19ff32e87eecbfd64b7e0e8ccff439ac333b040f19b0c4ddd11a61e24ac1fe0f10a039806c5dcc0da3d115"),
+            (129, 40, "512770d521e6b5e299b481f1db41f93bd56a8efe9fed9c8a6ad9c4aa55a1c7b2a10d4a6931972476"),
+        ];
+
+        for (len, out_len, expected) in vectors {
+            assert_eq!(
+                blake2b(&bytes[..len], out_len),
+                hex::decode(expected).unwrap()
+            );
+        }
+    }
+}
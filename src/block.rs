@@ -5,13 +5,33 @@
 
 use std::ops;
 
-use crate::lookups::{gmul::*, sbox::*};
-use crate::padding::Padding;
+use crate::lookups::gmul::*;
+use crate::padding::{Padding, PaddingError};
 use crate::util;
 
 /// Size of the payload of a [Block] (in bytes)
 pub const BLOCK_SIZE: usize = 16;
 
+/// Number of [Block]s in a ciphertext of `len` bytes
+///
+/// # Return value
+/// Fails if `len` is not a multiple of [BLOCK_SIZE].
+pub fn block_count(len: usize) -> Result<usize, &'static str> {
+    if !len.is_multiple_of(BLOCK_SIZE) {
+        return Err("Number of bytes not divisible by 16");
+    }
+
+    Ok(len / BLOCK_SIZE)
+}
+
+/// Source index of every byte after [shift rows](Block::shift_rows)
+const SHIFT_ROWS_TABLE: [usize; BLOCK_SIZE] =
+    [0, 5, 10, 15, 4, 9, 14, 3, 8, 13, 2, 7, 12, 1, 6, 11];
+
+/// Source index of every byte after [shift rows (inverse)](Block::shift_rows_inv)
+const SHIFT_ROWS_INV_TABLE: [usize; BLOCK_SIZE] =
+    [0, 13, 10, 7, 4, 1, 14, 11, 8, 5, 2, 15, 12, 9, 6, 3];
+
 /// The AES block abstraction
 ///
 /// Internally a block is just 4x4 bytes.
@@ -37,26 +57,46 @@ impl Block {
 
     /// Constructor that takes a continuous 16 byte array
     pub fn from_bytes(bytes: [u8; BLOCK_SIZE]) -> Self {
-        let state: [[u8; 4]; 4] = bytes
-            .chunks_exact(4)
-            .map(|c| c.try_into().unwrap())
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap();
+        Self::from_array_ref(&bytes)
+    }
+
+    /// Constructor that takes a reference to a continuous 16 byte array
+    ///
+    /// The bytes are copied into the [Block], which does not borrow from `bytes`.
+    /// Nothing is allocated, so this is the cheapest way to build a block from a slice:
+    /// `<&[u8; 16]>::try_from(slice)` only checks the length.
+    pub fn from_array_ref(bytes: &[u8; BLOCK_SIZE]) -> Self {
+        let state = std::array::from_fn(|c| std::array::from_fn(|r| bytes[c * 4 + r]));
 
         Self { state }
     }
 
     /// Load a set of [Block]s from a byte slice and a [Padding] mode
-    pub fn load<P>(bytes: &[u8], padding: &P) -> Vec<Self>
+    pub fn load<P>(bytes: &[u8], padding: &P) -> Result<Vec<Self>, PaddingError>
+    where
+        P: Padding<16>,
+    {
+        Ok(Self::load_iter(bytes, padding)?.collect())
+    }
+
+    /// Lazily load [Block]s from a byte slice and a [Padding] mode
+    ///
+    /// Full chunks are yielded one by one as they are needed.
+    /// Only the trailing remainder (which may be empty) is handed to the [Padding].
+    /// It is padded up front, so a [PaddingError] is returned before any block is yielded.
+    pub fn load_iter<'a, P>(
+        bytes: &'a [u8],
+        padding: &P,
+    ) -> Result<impl Iterator<Item = Self> + 'a, PaddingError>
     where
         P: Padding<16>,
     {
-        padding
-            .pad(bytes)
-            .into_iter()
-            .map(Self::from_bytes)
-            .collect()
+        let chunks = bytes.chunks_exact(BLOCK_SIZE);
+        let last_chunks = padding.pad(chunks.remainder())?;
+
+        Ok(chunks
+            .map(|c| Self::from_array_ref(c.try_into().unwrap()))
+            .chain(last_chunks.into_iter().map(Self::from_bytes)))
     }
 
     /// Dump the inner bytes from the [Block] as continuous byte array
@@ -74,25 +114,35 @@ impl Block {
         dump
     }
 
+    /// Apply a transformation and return the block
+    ///
+    /// This allows chaining the steps, e.g. `block.apply(Block::sub_bytes).apply(Block::shift_rows)`.
+    pub fn apply(mut self, f: impl FnOnce(&mut Block)) -> Self {
+        f(&mut self);
+        self
+    }
+
     /// Substitute bytes
     ///
-    /// Substitutes every single byte using the AES [SBOX].
+    /// Substitutes every single byte using the AES [SBOX](crate::lookups::sbox::SBOX).
+    /// With the `constant-time` feature, the S-box is computed without table lookups.
     ///
     /// For reference, see the [Wikipedia article](https://en.wikipedia.org/wiki/Advanced_Encryption_Standard#The_SubBytes_step).
     pub fn sub_bytes(&mut self) {
         for col in &mut self.state {
-            *col = util::apply_sbox(*col, SBOX);
+            *col = util::sub_bytes(*col);
         }
     }
 
     /// Substitute bytes (inverse)
     ///
-    /// Substitutes every single byte using the AES [INVERSE_SBOX].
+    /// Substitutes every single byte using the AES [INVERSE_SBOX](crate::lookups::sbox::INVERSE_SBOX).
+    /// With the `constant-time` feature, the S-box is computed without table lookups.
     ///
     /// For reference, see the [Wikipedia article](https://en.wikipedia.org/wiki/Advanced_Encryption_Standard#The_SubBytes_step).
     pub fn sub_bytes_inv(&mut self) {
         for col in &mut self.state {
-            *col = util::apply_sbox(*col, INVERSE_SBOX);
+            *col = util::sub_bytes_inv(*col);
         }
     }
 
@@ -124,6 +174,56 @@ impl Block {
         self.state = util::transpose_array2d(&transposed);
     }
 
+    /// [Shift rows](Self::shift_rows) by moving every byte to its target position directly
+    ///
+    /// Only public to compare the variants in `benches/shift_rows.rs`.
+    #[doc(hidden)]
+    pub fn shift_rows_permutation(&mut self) {
+        let s = self.state;
+        for (c, col) in self.state.iter_mut().enumerate() {
+            for (r, byte) in col.iter_mut().enumerate() {
+                *byte = s[(c + r) % 4][r];
+            }
+        }
+    }
+
+    /// [Shift rows (inverse)](Self::shift_rows_inv) by moving every byte to its target position directly
+    ///
+    /// Only public to compare the variants in `benches/shift_rows.rs`.
+    #[doc(hidden)]
+    pub fn shift_rows_inv_permutation(&mut self) {
+        let s = self.state;
+        for (c, col) in self.state.iter_mut().enumerate() {
+            for (r, byte) in col.iter_mut().enumerate() {
+                *byte = s[(c + 4 - r) % 4][r];
+            }
+        }
+    }
+
+    /// [Shift rows](Self::shift_rows) using a precomputed byte index table
+    ///
+    /// Only public to compare the variants in `benches/shift_rows.rs`.
+    #[doc(hidden)]
+    pub fn shift_rows_table(&mut self) {
+        self.permute(SHIFT_ROWS_TABLE);
+    }
+
+    /// [Shift rows (inverse)](Self::shift_rows_inv) using a precomputed byte index table
+    ///
+    /// Only public to compare the variants in `benches/shift_rows.rs`.
+    #[doc(hidden)]
+    pub fn shift_rows_inv_table(&mut self) {
+        self.permute(SHIFT_ROWS_INV_TABLE);
+    }
+
+    /// Rearrange the bytes so that byte `i` is taken from position `table[i]`
+    fn permute(&mut self, table: [usize; BLOCK_SIZE]) {
+        let s = self.state;
+        for (byte, i) in self.state.iter_mut().flatten().zip(table) {
+            *byte = s[i / 4][i % 4];
+        }
+    }
+
     /// Mix columns
     ///
     /// Combine the four bytes of each column using an invertible linear transformation.
@@ -136,8 +236,8 @@ impl Block {
             let col = &mut self.state[c];
             let copy = copy[c];
 
-            for r in 0..4 {
-                col[r] = match r {
+            for (r, byte) in col.iter_mut().enumerate() {
+                *byte = match r {
                     0 => GMUL2[copy[0] as usize] ^ GMUL3[copy[1] as usize] ^ copy[2] ^ copy[3],
                     1 => copy[0] ^ GMUL2[copy[1] as usize] ^ GMUL3[copy[2] as usize] ^ copy[3],
                     2 => copy[0] ^ copy[1] ^ GMUL2[copy[2] as usize] ^ GMUL3[copy[3] as usize],
@@ -160,8 +260,8 @@ impl Block {
             let col = &mut self.state[c];
             let copy = copy[c];
 
-            for r in 0..4 {
-                col[r] = match r {
+            for (r, byte) in col.iter_mut().enumerate() {
+                *byte = match r {
                     0 => {
                         GMUL14[copy[0] as usize]
                             ^ GMUL11[copy[1] as usize]
@@ -204,9 +304,46 @@ impl Block {
     }
 }
 
+impl ops::BitXor for Block {
+    type Output = Block;
+
+    fn bitxor(mut self, rhs: Self) -> Self::Output {
+        for (i, col) in self.state.iter_mut().enumerate() {
+            for (j, byte) in col.iter_mut().enumerate() {
+                *byte ^= rhs.state[i][j];
+            }
+        }
+
+        self
+    }
+}
+
+impl ops::BitXorAssign for Block {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        for (i, col) in self.state.iter_mut().enumerate() {
+            for (j, byte) in col.iter_mut().enumerate() {
+                *byte ^= rhs.state[i][j];
+            }
+        }
+    }
+}
+
+impl From<[u8; 16]> for Block {
+    fn from(value: [u8; 16]) -> Self {
+        Block::from_bytes(value)
+    }
+}
+
+impl From<u128> for Block {
+    fn from(value: u128) -> Self {
+        Block::from_bytes(value.to_be_bytes())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::padding::{Pkcs7Padding, ZeroPadding};
 
     #[test]
     fn sub_bytes_step() {
@@ -272,12 +409,19 @@ mod tests {
             [0xc, 0x1, 0x6, 0xb],
         ];
 
-        let mut block = Block::new(state);
-        block.shift_rows();
-
         let expected_block = Block::new(shifted_state);
 
-        assert_eq!(block, expected_block);
+        let variants: [fn(&mut Block); 3] = [
+            Block::shift_rows,
+            Block::shift_rows_permutation,
+            Block::shift_rows_table,
+        ];
+        for shift_rows in variants {
+            let mut block = Block::new(state);
+            shift_rows(&mut block);
+
+            assert_eq!(block, expected_block);
+        }
     }
 
     #[test]
@@ -296,12 +440,19 @@ mod tests {
             [0xc, 0x1, 0x6, 0xb],
         ];
 
-        let mut block = Block::new(shifted_state);
-        block.shift_rows_inv();
-
         let expected_block = Block::new(state);
 
-        assert_eq!(block, expected_block);
+        let variants: [fn(&mut Block); 3] = [
+            Block::shift_rows_inv,
+            Block::shift_rows_inv_permutation,
+            Block::shift_rows_inv_table,
+        ];
+        for shift_rows_inv in variants {
+            let mut block = Block::new(shifted_state);
+            shift_rows_inv(&mut block);
+
+            assert_eq!(block, expected_block);
+        }
     }
 
     #[test]
@@ -379,40 +530,92 @@ mod tests {
 
         assert_eq!(block, expected_block);
     }
-}
-
-impl ops::BitXor for Block {
-    type Output = Block;
 
-    fn bitxor(mut self, rhs: Self) -> Self::Output {
-        for (i, col) in self.state.iter_mut().enumerate() {
-            for (j, byte) in col.iter_mut().enumerate() {
-                *byte ^= rhs.state[i][j];
-            }
+    #[test]
+    fn load_iter_matches_whole_padding() {
+        let bytes: Vec<u8> = (0..=255).collect();
+
+        for len in [0, 1, 14, 15, 16, 17, 32, 100, 256] {
+            let bytes = &bytes[..len];
+
+            let expected: Vec<Block> = Padding::<16>::pad(&Pkcs7Padding, bytes)
+                .unwrap()
+                .into_iter()
+                .map(Block::from_bytes)
+                .collect();
+            assert_eq!(
+                Block::load_iter(bytes, &Pkcs7Padding)
+                    .unwrap()
+                    .collect::<Vec<_>>(),
+                expected
+            );
+
+            let expected: Vec<Block> = Padding::<16>::pad(&ZeroPadding, bytes)
+                .unwrap()
+                .into_iter()
+                .map(Block::from_bytes)
+                .collect();
+            assert_eq!(
+                Block::load_iter(bytes, &ZeroPadding)
+                    .unwrap()
+                    .collect::<Vec<_>>(),
+                expected
+            );
         }
+    }
 
-        self
+    #[test]
+    fn block_count_aligned() {
+        assert_eq!(block_count(0), Ok(0));
+        assert_eq!(block_count(16), Ok(1));
+        assert_eq!(block_count(160), Ok(10));
     }
-}
 
-impl ops::BitXorAssign for Block {
-    fn bitxor_assign(&mut self, rhs: Self) {
-        for (i, col) in self.state.iter_mut().enumerate() {
-            for (j, byte) in col.iter_mut().enumerate() {
-                *byte ^= rhs.state[i][j];
-            }
-        }
+    #[test]
+    fn block_count_misaligned() {
+        assert!(block_count(1).is_err());
+        assert!(block_count(15).is_err());
+        assert!(block_count(161).is_err());
     }
-}
 
-impl From<[u8; 16]> for Block {
-    fn from(value: [u8; 16]) -> Self {
-        Block::from_bytes(value)
+    #[test]
+    fn first_round_with_apply() {
+        // see FIPS 197, Appendix B
+        let start = Block::from(0x193de3bea0f4e22b9ac68d2ae9f84808);
+
+        let round = start
+            .apply(Block::sub_bytes)
+            .apply(Block::shift_rows)
+            .apply(Block::mix_columns)
+            .apply(|b| b.add_round_key(0xa0fafe1788542cb123a339392a6c7605));
+
+        assert_eq!(round, Block::from(0xa49c7ff2689f352b6b5bea43026a5049));
+
+        assert_eq!(
+            start.apply(Block::sub_bytes),
+            Block::from(0xd42711aee0bf98f1b8b45de51e415230)
+        );
     }
-}
 
-impl From<u128> for Block {
-    fn from(value: u128) -> Self {
-        Block::from_bytes(value.to_be_bytes())
+    #[test]
+    fn xor_matches_add_round_key() {
+        let round_key = 0x727c01c8464937ff7e70569d1d1433fb;
+        let block = Block::from(0x193de3bea0f4e22b9ac68d2ae9f84808);
+
+        let mut xored = block;
+        xored ^= Block::from(round_key);
+
+        assert_eq!(xored, block.apply(|b| b.add_round_key(round_key)));
+    }
+
+    #[test]
+    fn from_array_ref_matches_from_bytes() {
+        let bytes: Vec<u8> = (0..32).collect();
+
+        for chunk in bytes.chunks_exact(BLOCK_SIZE) {
+            let array: &[u8; BLOCK_SIZE] = chunk.try_into().unwrap();
+            assert_eq!(Block::from_array_ref(array), Block::from_bytes(*array));
+            assert_eq!(Block::from_array_ref(array).dump_bytes(), *array);
+        }
     }
 }
@@ -0,0 +1,61 @@
+//! Cipher module
+//!
+//! This module provides a [Cipher] that runs the key schedule only once
+//! and keeps the round keys for any number of blocks.
+
+use crate::block::Block;
+use crate::decryption::decrypt_block_with;
+use crate::encryption::encrypt_block_with;
+use crate::key::{Key, RoundKeyProvider, Subkey};
+use crate::PER_BLOCK_LOG_TARGET;
+
+/// AES with precomputed round keys
+///
+/// [encrypt_block](crate::encryption::encrypt_block) asks the [Key] for its round keys on every call,
+/// which runs the whole key schedule again.
+/// A [Cipher] asks only once, when it is constructed.
+///
+/// It is a [RoundKeyProvider] itself, so it can be passed to every function that takes a [Key].
+#[derive(Clone)]
+pub struct Cipher<const R: usize> {
+    round_keys: [Block; R],
+}
+
+impl<const R: usize> Cipher<R> {
+    /// Constructor that derives the round keys of a [Key]
+    pub fn new<K>(key: &K) -> Self
+    where
+        K: Key<R>,
+    {
+        log::trace!("Create cipher");
+
+        Self {
+            round_keys: key.round_key_blocks(),
+        }
+    }
+
+    /// Encrypt a [Block]
+    pub fn encrypt_block(&self, block: &mut Block) {
+        log::trace!(target: PER_BLOCK_LOG_TARGET, "Encrypt a block");
+
+        encrypt_block_with(block, &self.round_keys);
+    }
+
+    /// Decrypt a [Block]
+    pub fn decrypt_block(&self, block: &mut Block) {
+        log::trace!(target: PER_BLOCK_LOG_TARGET, "Decrypt a block");
+
+        decrypt_block_with(block, &self.round_keys);
+    }
+}
+
+impl<const R: usize> RoundKeyProvider<R> for Cipher<R> {
+    fn round_keys(&self) -> [Subkey; R] {
+        self.round_keys
+            .map(|round_key| Subkey::from_be_bytes(round_key.dump_bytes()))
+    }
+
+    fn round_key_blocks(&self) -> [Block; R] {
+        self.round_keys
+    }
+}
@@ -0,0 +1,121 @@
+//! Ciphertext module
+//!
+//! This module provides the [Ciphertext] type,
+//! a byte buffer that is known to consist of whole [Block]s.
+
+use crate::block::{block_count, Block, BLOCK_SIZE};
+
+/// Block-aligned ciphertext
+///
+/// The alignment is checked once on construction,
+/// so the bytes can be processed block by block without checking the length again.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Ciphertext(Vec<u8>);
+
+impl Ciphertext {
+    /// Constructor that takes the ciphertext bytes
+    ///
+    /// # Return value
+    /// Fails if the number of bytes is not a multiple of `16`.
+    pub fn new(bytes: Vec<u8>) -> Result<Self, &'static str> {
+        if let Err(err) = block_count(bytes.len()) {
+            log::error!("{}", err);
+            return Err(err);
+        }
+
+        Ok(Self(bytes))
+    }
+
+    /// Number of [Block]s
+    pub fn len_blocks(&self) -> usize {
+        self.0.len() / BLOCK_SIZE
+    }
+
+    /// Get the inner bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Unwrap the inner bytes
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl TryFrom<Vec<u8>> for Ciphertext {
+    type Error = &'static str;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::new(bytes)
+    }
+}
+
+impl IntoIterator for Ciphertext {
+    type Item = Block;
+    type IntoIter = Blocks;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Blocks {
+            bytes: self.0,
+            pos: 0,
+        }
+    }
+}
+
+/// Owning iterator over the [Block]s of a [Ciphertext]
+#[derive(Debug)]
+pub struct Blocks {
+    bytes: Vec<u8>,
+    pos: usize,
+}
+
+impl Iterator for Blocks {
+    type Item = Block;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.bytes.get(self.pos..self.pos + BLOCK_SIZE)?;
+        self.pos += BLOCK_SIZE;
+
+        Some(Block::from_array_ref(chunk.try_into().unwrap()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.bytes.len() - self.pos) / BLOCK_SIZE;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Blocks {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn construction_checks_alignment() {
+        assert!(Ciphertext::new(vec![]).is_ok());
+        assert!(Ciphertext::new(vec![0; 32]).is_ok());
+
+        assert!(Ciphertext::new(vec![0; 15]).is_err());
+        assert!(Ciphertext::try_from(vec![0; 17]).is_err());
+    }
+
+    #[test]
+    fn iterate_blocks() {
+        let bytes: Vec<u8> = (0..48).collect();
+        let ciphertext = Ciphertext::new(bytes.clone()).unwrap();
+        assert_eq!(ciphertext.len_blocks(), 3);
+        assert_eq!(ciphertext.as_bytes(), bytes);
+
+        let blocks = ciphertext.into_iter();
+        assert_eq!(blocks.len(), 3);
+
+        let expected: Vec<Block> = bytes
+            .chunks_exact(16)
+            .map(|c| Block::from_bytes(c.try_into().unwrap()))
+            .collect();
+        assert_eq!(blocks.collect::<Vec<_>>(), expected);
+
+        assert_eq!(Ciphertext::new(vec![]).unwrap().into_iter().next(), None);
+    }
+}
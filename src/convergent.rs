@@ -0,0 +1,109 @@
+//! Convergent encryption module
+//!
+//! In convergent encryption the key is derived from the plaintext itself,
+//! so identical plaintexts always produce identical ciphertexts.
+//! This allows deduplicating encrypted data without being able to read it.
+//!
+//! The price is privacy: anyone who can guess a plaintext can confirm
+//! that a ciphertext contains it, and equal files are recognizable as such.
+//! Small or predictable plaintexts (e.g. forms that differ only in a few fields)
+//! can even be recovered by trying all candidates.
+//! The master key limits these attacks to holders of that key.
+
+use crate::block::Block;
+use crate::decryption::{decrypt_block, decrypt_bytes_cbc};
+use crate::encryption::{encrypt_block, encrypt_bytes_cbc};
+use crate::iv::InitializationVector;
+use crate::key::{AES256Key, Key};
+use crate::padding::Pkcs7Padding;
+use crate::sha256::sha256;
+use crate::util;
+
+/// Content key wrapped with the master key
+///
+/// Identical plaintexts have identical tags, so it can serve as deduplication ID.
+pub type ConvergentTag = [u8; 32];
+
+/// Encrypt a byte slice with a key derived from its SHA-256 hash
+///
+/// The plaintext is encrypted with the hash as [AES-256 key](AES256Key) in [CBC](crate::EncryptionMode) mode.
+/// The IV is fixed, which is fine because every key is only used for one plaintext.
+///
+/// # Parameters
+/// - `plaintext`: byte slice to encrypt
+/// - `master_key`: [Key] used to wrap the content key
+///
+/// # Return value
+/// The [ConvergentTag] that is needed for decryption and the ciphertext.
+pub fn encrypt_convergent<const R: usize, K>(
+    plaintext: &[u8],
+    master_key: &K,
+) -> (ConvergentTag, Vec<u8>)
+where
+    K: Key<R>,
+{
+    log::trace!("Convergent encryption");
+
+    let content_key = sha256(plaintext);
+    let ciphertext = encrypt_bytes_cbc(
+        plaintext,
+        &AES256Key::from_bytes(content_key),
+        &Pkcs7Padding,
+        InitializationVector::from(0),
+    );
+
+    let mut tag = content_key;
+    for chunk in tag.chunks_exact_mut(16) {
+        let mut block = Block::from_bytes(chunk.try_into().unwrap());
+        encrypt_block(&mut block, master_key);
+        chunk.copy_from_slice(&block.dump_bytes());
+    }
+
+    (tag, ciphertext)
+}
+
+/// Decrypt a byte slice that was encrypted with [encrypt_convergent]
+///
+/// # Parameters
+/// - `tag`: [ConvergentTag] returned by the encryption
+/// - `ciphertext`: byte slice to decrypt
+/// - `master_key`: [Key] that was used to wrap the content key
+///
+/// # Return value
+/// The decryption fails if the hash of the decrypted bytes doesn't match the content key,
+/// i.e. if the tag, the ciphertext or the master key is wrong.
+pub fn decrypt_convergent<const R: usize, K>(
+    tag: &ConvergentTag,
+    ciphertext: &[u8],
+    master_key: &K,
+) -> Result<Vec<u8>, &'static str>
+where
+    K: Key<R>,
+{
+    log::trace!("Convergent decryption");
+
+    let mut content_key = *tag;
+    for chunk in content_key.chunks_exact_mut(16) {
+        let mut block = Block::from_bytes(chunk.try_into().unwrap());
+        decrypt_block(&mut block, master_key);
+        chunk.copy_from_slice(&block.dump_bytes());
+    }
+
+    let err = "Plaintext does not match the convergent tag";
+
+    // a wrong content key usually shows up as an invalid padding first
+    let decrypted = decrypt_bytes_cbc(
+        ciphertext,
+        &AES256Key::from_bytes(content_key),
+        Some(Pkcs7Padding),
+        InitializationVector::from(0),
+    );
+
+    match decrypted {
+        Ok(plaintext) if util::ct_eq(&sha256(&plaintext), &content_key) => Ok(plaintext),
+        _ => {
+            log::error!("{}", err);
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Constant-time S-box module
+//!
+//! This module computes the AES S-box instead of looking it up in [SBOX](crate::lookups::sbox::SBOX).
+//! A table lookup with a secret index leaves traces in the CPU cache that an attacker may measure.
+//! Here every byte goes through the same sequence of operations without branches or secret indices:
+//! the multiplicative inverse in GF(2^8) (as `x^254`) followed by the affine transformation.
+//!
+//! For reference, see [FIPS 197, Section 5.1.1](https://csrc.nist.gov/publications/detail/fips/197/final).
+
+/// Substitute a byte like [SBOX](crate::lookups::sbox::SBOX)
+pub fn sub_byte(byte: u8) -> u8 {
+    let inverse = invert(byte);
+
+    inverse
+        ^ inverse.rotate_left(1)
+        ^ inverse.rotate_left(2)
+        ^ inverse.rotate_left(3)
+        ^ inverse.rotate_left(4)
+        ^ 0x63
+}
+
+/// Substitute a byte like [INVERSE_SBOX](crate::lookups::sbox::INVERSE_SBOX)
+pub fn sub_byte_inv(byte: u8) -> u8 {
+    invert(byte.rotate_left(1) ^ byte.rotate_left(3) ^ byte.rotate_left(6) ^ 0x05)
+}
+
+/// Multiplicative inverse in GF(2^8), `0` is mapped to `0`
+fn invert(x: u8) -> u8 {
+    // x^254 = x^-1, with an addition chain that doesn't depend on x
+    let x2 = mul(x, x);
+    let x3 = mul(x2, x);
+    let x6 = mul(x3, x3);
+    let x12 = mul(x6, x6);
+    let x15 = mul(x12, x3);
+    let x30 = mul(x15, x15);
+    let x60 = mul(x30, x30);
+    let x120 = mul(x60, x60);
+    let x240 = mul(x120, x120);
+    let x252 = mul(x240, x12);
+
+    mul(x252, x2)
+}
+
+/// Multiplication in GF(2^8) with the AES polynomial
+///
+/// Unlike with the [gmul](crate::lookups::gmul) tables, the bits of the factors only select masks.
+fn mul(mut a: u8, b: u8) -> u8 {
+    let mut product = 0;
+
+    for i in 0..8 {
+        product ^= a & 0u8.wrapping_sub((b >> i) & 1);
+        a = (a << 1) ^ (0x1b & 0u8.wrapping_sub(a >> 7));
+    }
+
+    product
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookups::sbox::{INVERSE_SBOX, SBOX};
+
+    #[test]
+    fn matches_lookup_tables() {
+        for byte in 0..=255 {
+            assert_eq!(sub_byte(byte), SBOX[byte as usize], "S-box of {byte:#04x}");
+            assert_eq!(
+                sub_byte_inv(byte),
+                INVERSE_SBOX[byte as usize],
+                "Inverse S-box of {byte:#04x}"
+            );
+        }
+    }
+}
@@ -2,39 +2,91 @@
 //!
 //! This module provides functions to decrypt [Block]s and byte slices.
 
-use crate::block::Block;
+use std::fmt;
+
+use crate::block::{block_count, Block};
+use crate::cipher::Cipher;
+use crate::encryption::{ctr, encrypt_block, ofb, record_iv, CtsError};
 use crate::iv::InitializationVector;
 use crate::key::Key;
-use crate::padding::{Padding, ZeroPadding};
-use crate::EncryptionMode;
+use crate::padding::{NoPadding, Padding, PaddingError, Pkcs7Padding};
+use crate::parallel;
+use crate::{EncryptionMode, PER_BLOCK_LOG_TARGET};
 
 /// Decrypt a [Block] using a [Key] type
 pub fn decrypt_block<const R: usize, K>(block: &mut Block, key: &K)
 where
     K: Key<R>,
 {
-    log::trace!("Decrypt a block");
+    log::trace!(target: PER_BLOCK_LOG_TARGET, "Decrypt a block");
 
-    let round_keys = key.round_keys();
+    let round_keys = key.round_key_blocks();
     debug_assert_eq!(round_keys.len(), R);
 
+    decrypt_block_with(block, &round_keys);
+}
+
+/// Decrypt a [Block] with round keys that were already derived
+pub(crate) fn decrypt_block_with<const R: usize>(block: &mut Block, round_keys: &[Block; R]) {
+    #[cfg(all(feature = "aes-ni", target_arch = "x86_64"))]
+    if crate::aesni::backend() == crate::Backend::AesNi {
+        // SAFETY: the CPU supports AES-NI
+        unsafe { crate::aesni::decrypt_block(block, round_keys) };
+        return;
+    }
+
+    decrypt_block_software(block, *round_keys);
+}
+
+/// Decrypt a [Block] with the software implementation of the rounds
+pub(crate) fn decrypt_block_software<const R: usize>(block: &mut Block, round_keys: [Block; R]) {
     for (i, round_key) in round_keys.into_iter().rev().enumerate() {
         if i == 0 {
-            block.add_round_key(round_key);
+            *block ^= round_key;
             continue;
         }
 
         if i <= R - 2 {
             block.shift_rows_inv();
             block.sub_bytes_inv();
-            block.add_round_key(round_key);
+            *block ^= round_key;
             block.mix_columns_inv();
             continue;
         }
 
         block.shift_rows_inv();
         block.sub_bytes_inv();
-        block.add_round_key(round_key);
+        *block ^= round_key;
+    }
+}
+
+/// Errors of [decrypt_bytes]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DecryptError {
+    /// The number of encrypted bytes is not a multiple of `16`
+    NotBlockAligned,
+
+    /// The decrypted bytes don't end with a valid padding
+    ///
+    /// Usually the key, the IV or the padding mode is wrong, or the ciphertext is corrupted.
+    InvalidPadding(PaddingError),
+}
+
+impl fmt::Display for DecryptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecryptError::NotBlockAligned => write!(f, "Number of bytes not divisible by 16"),
+            DecryptError::InvalidPadding(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for DecryptError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecryptError::NotBlockAligned => None,
+            DecryptError::InvalidPadding(err) => Some(err),
+        }
     }
 }
 
@@ -43,45 +95,153 @@ where
 /// # Parameters
 /// - `bytes`: byte slice to decrypt
 /// - `key`: [Key] used for decryption
-/// - `padding`: how the decrypted bytes should be unpadded
+/// - `padding`: how the decrypted bytes should be unpadded (ignored in the stream modes [CTR](EncryptionMode::CTR), [CFB](EncryptionMode::CFB) and [OFB](EncryptionMode::OFB))
 /// - `mode`: [EncryptionMode] that was used for encryption
 ///
 /// # Return value
-/// The decryption may fail if the number of encrypted bytes is not a multiple of `16`.
+/// The decryption fails with [DecryptError::NotBlockAligned] if the number of encrypted bytes is not a multiple of `16`
+/// (except in the stream modes [CTR](EncryptionMode::CTR), [CFB](EncryptionMode::CFB) and [OFB](EncryptionMode::OFB))
+/// or with [DecryptError::InvalidPadding] if the decrypted bytes don't end with a valid padding.
 pub fn decrypt_bytes<const R: usize, K, P>(
     bytes: &[u8],
     key: &K,
     padding: Option<P>,
     mode: EncryptionMode,
-) -> Result<Vec<u8>, &'static str>
+) -> Result<Vec<u8>, DecryptError>
 where
     K: Key<R>,
     P: Padding<16>,
 {
     log::trace!("Decrypt bytes");
 
-    if bytes.len() % 16 != 0 {
-        let err = "Number of bytes not divisible by 16";
+    // run the key schedule only once
+    let key = &Cipher::new(key);
+
+    match mode {
+        EncryptionMode::CTR(counter) => return Ok(ctr(bytes, key, counter)),
+        EncryptionMode::CFB(iv) => return Ok(cfb(bytes, key, iv)),
+        EncryptionMode::OFB(iv) => return Ok(ofb(bytes, key, iv)),
+        _ => (),
+    }
+
+    if let Err(err) = block_count(bytes.len()) {
         log::error!("{}", err);
-        return Err(err);
+        return Err(DecryptError::NotBlockAligned);
     }
 
-    let mut blocks = Block::load(bytes, &ZeroPadding);
+    let mut blocks = Block::load(bytes, &NoPadding).unwrap();
 
     match mode {
         EncryptionMode::ECB => ecb(&mut blocks, key),
         EncryptionMode::CBC(iv) => cbc(&mut blocks, key, iv),
+        EncryptionMode::PCBC(iv) => pcbc(&mut blocks, key, iv),
+        EncryptionMode::CTR(_) | EncryptionMode::CFB(_) | EncryptionMode::OFB(_) => {
+            unreachable!("Stream modes do not work on padded blocks")
+        }
     }
 
     let padded_bytes: Vec<[u8; 16]> = blocks.into_iter().map(|b| b.dump_bytes()).collect();
 
     if let Some(padding) = padding {
-        Ok(padding.unpad(&padded_bytes))
+        padding.unpad(&padded_bytes).map_err(|err| {
+            log::error!("{}", err);
+            DecryptError::InvalidPadding(err)
+        })
     } else {
         Ok(padded_bytes.into_iter().flatten().collect())
     }
 }
 
+/// Decrypt a byte slice in [CBC](EncryptionMode) mode
+///
+/// Same as [decrypt_bytes] with [EncryptionMode::CBC],
+/// but the IV is passed directly, which is convenient when decrypting many messages.
+pub fn decrypt_bytes_cbc<const R: usize, K, P>(
+    bytes: &[u8],
+    key: &K,
+    padding: Option<P>,
+    iv: InitializationVector,
+) -> Result<Vec<u8>, DecryptError>
+where
+    K: Key<R>,
+    P: Padding<16>,
+{
+    decrypt_bytes(bytes, key, padding, EncryptionMode::CBC(iv))
+}
+
+/// Decrypt a byte slice that was encrypted with [encrypt_bytes_cts](crate::encryption::encrypt_bytes_cts)
+///
+/// # Return value
+/// Fails with [CtsError::TooShort] if the input is not longer than one block.
+pub fn decrypt_bytes_cts<const R: usize, K>(
+    bytes: &[u8],
+    key: &K,
+    iv: InitializationVector,
+) -> Result<Vec<u8>, CtsError>
+where
+    K: Key<R>,
+{
+    log::trace!("Decrypt bytes with ciphertext stealing");
+
+    if bytes.len() <= 16 {
+        log::error!("{}", CtsError::TooShort);
+        return Err(CtsError::TooShort);
+    }
+
+    let n = bytes.len().div_ceil(16);
+    let (head, tail) = bytes.split_at((n - 2) * 16);
+    let (last_block, stolen) = tail.split_at(16);
+
+    // the last full block hides the tail of the second-to-last ciphertext block
+    let mut block = Block::from_array_ref(last_block.try_into().unwrap());
+    decrypt_block(&mut block, key);
+    let decrypted = block.dump_bytes();
+
+    let mut second_to_last = decrypted;
+    second_to_last[..stolen.len()].copy_from_slice(stolen);
+
+    // the blocks are complete and not unpadded, so this can't fail
+    let mut plaintext = decrypt_bytes_cbc(
+        &[head, &second_to_last].concat(),
+        key,
+        None::<NoPadding>,
+        iv,
+    )
+    .unwrap();
+    plaintext.extend(decrypted.iter().zip(stolen).map(|(d, c)| d ^ c));
+
+    Ok(plaintext)
+}
+
+/// Decrypt a set of records that were encrypted with [encrypt_records](crate::encryption::encrypt_records)
+///
+/// # Parameters
+/// - `records`: byte slices to decrypt
+/// - `key`: [Key] used for decryption
+/// - `tweak_base`: tweak of the first record that was used for encryption
+///
+/// # Return value
+/// The decryption fails if any of the records can't be decrypted.
+pub fn decrypt_records<const R: usize, K>(
+    records: &[&[u8]],
+    key: &K,
+    tweak_base: u128,
+) -> Result<Vec<Vec<u8>>, DecryptError>
+where
+    K: Key<R>,
+{
+    log::trace!("Decrypt records");
+
+    records
+        .iter()
+        .enumerate()
+        .map(|(i, record)| {
+            let iv = record_iv(key, tweak_base, i);
+            decrypt_bytes(record, key, Some(Pkcs7Padding), EncryptionMode::CBC(iv))
+        })
+        .collect()
+}
+
 /// Implementation of [ECB](EncryptionMode) decryption
 fn ecb<const R: usize, K>(blocks: &mut [Block], key: &K)
 where
@@ -89,9 +249,21 @@ where
 {
     log::trace!("ECB decryption");
 
-    for block in blocks {
-        decrypt_block(block, key);
+    let n = blocks.len();
+    if cfg!(feature = "parallel") && n >= parallel::THRESHOLD {
+        let round_keys = key.round_key_blocks();
+        parallel::for_each_chunk(blocks, |_, chunk| {
+            for block in chunk {
+                decrypt_block_with(block, &round_keys);
+            }
+        });
+    } else {
+        for block in blocks {
+            decrypt_block(block, key);
+        }
     }
+
+    log::debug!("Decrypted {n} blocks");
 }
 
 /// Implementation of [CBC](EncryptionMode) decryption
@@ -101,6 +273,7 @@ where
 {
     log::trace!("CBC decryption");
 
+    let n = blocks.len();
     let mut prev: Block = iv.into();
     for block in blocks {
         let copy = *block;
@@ -108,4 +281,58 @@ where
         *block ^= prev;
         prev = copy;
     }
+
+    log::debug!("Decrypted {n} blocks");
+}
+
+/// Implementation of [PCBC](EncryptionMode) decryption
+///
+/// Every decrypted block feeds into the next one,
+/// so an error in one ciphertext block propagates to the end of the message.
+fn pcbc<const R: usize, K>(blocks: &mut [Block], key: &K, iv: InitializationVector)
+where
+    K: Key<R>,
+{
+    log::trace!("PCBC decryption");
+
+    let n = blocks.len();
+    let mut prev: Block = iv.into();
+    for block in blocks {
+        let ciphertext = *block;
+        decrypt_block(block, key);
+        *block ^= prev;
+        prev = ciphertext ^ *block;
+    }
+
+    log::debug!("Decrypted {n} blocks");
+}
+
+/// Implementation of [CFB](EncryptionMode) decryption
+///
+/// Unlike CBC, this needs the forward cipher: the keystream is the encryption
+/// of the previous ciphertext block.
+fn cfb<const R: usize, K>(bytes: &[u8], key: &K, iv: InitializationVector) -> Vec<u8>
+where
+    K: Key<R>,
+{
+    log::trace!("CFB decryption");
+
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut prev: Block = iv.into();
+    let mut n = 0;
+    for chunk in bytes.chunks(16) {
+        encrypt_block(&mut prev, key);
+        let keystream = prev.dump_bytes();
+
+        output.extend(chunk.iter().zip(keystream).map(|(b, k)| b ^ k));
+        if let Ok(chunk) = chunk.try_into() {
+            prev = Block::from_bytes(chunk);
+        }
+
+        n += 1;
+    }
+
+    log::debug!("Decrypted {n} blocks");
+
+    output
 }
@@ -2,25 +2,46 @@
 //!
 //! This module provides functions to encrypt [Block]s and bytes slices.
 
+use std::fmt;
+
 use crate::block::Block;
+use crate::cipher::Cipher;
 use crate::iv::InitializationVector;
 use crate::key::Key;
-use crate::padding::Padding;
-use crate::EncryptionMode;
+use crate::padding::{NoPadding, Padding, PaddingError, Pkcs7Padding};
+use crate::parallel;
+use crate::{EncryptionMode, PER_BLOCK_LOG_TARGET};
 
 /// Encrypt a [Block] using a [Key] type
 pub fn encrypt_block<const R: usize, K>(block: &mut Block, key: &K)
 where
     K: Key<R>,
 {
-    log::trace!("Encrypt a block");
+    log::trace!(target: PER_BLOCK_LOG_TARGET, "Encrypt a block");
 
-    let round_keys = key.round_keys();
+    let round_keys = key.round_key_blocks();
     debug_assert_eq!(round_keys.len(), R);
 
+    encrypt_block_with(block, &round_keys);
+}
+
+/// Encrypt a [Block] with round keys that were already derived
+pub(crate) fn encrypt_block_with<const R: usize>(block: &mut Block, round_keys: &[Block; R]) {
+    #[cfg(all(feature = "aes-ni", target_arch = "x86_64"))]
+    if crate::aesni::backend() == crate::Backend::AesNi {
+        // SAFETY: the CPU supports AES-NI
+        unsafe { crate::aesni::encrypt_block(block, round_keys) };
+        return;
+    }
+
+    encrypt_block_software(block, *round_keys);
+}
+
+/// Encrypt a [Block] with the software implementation of the rounds
+pub(crate) fn encrypt_block_software<const R: usize>(block: &mut Block, round_keys: [Block; R]) {
     for (i, round_key) in round_keys.into_iter().enumerate() {
         if i == 0 {
-            block.add_round_key(round_key);
+            *block ^= round_key;
             continue;
         }
 
@@ -28,13 +49,13 @@ where
             block.sub_bytes();
             block.shift_rows();
             block.mix_columns();
-            block.add_round_key(round_key);
+            *block ^= round_key;
             continue;
         }
 
         block.sub_bytes();
         block.shift_rows();
-        block.add_round_key(round_key);
+        *block ^= round_key;
     }
 }
 
@@ -43,28 +64,310 @@ where
 /// # Parameters
 /// - `bytes`: byte slice to encrypt
 /// - `key`: [Key] used for encryption
-/// - `padding`: how the decrypted bytes should be padded
+/// - `padding`: how the decrypted bytes should be padded (ignored in the stream modes [CTR](EncryptionMode::CTR), [CFB](EncryptionMode::CFB) and [OFB](EncryptionMode::OFB))
 /// - `mode`: [EncryptionMode] that is used for encryption
+///
+/// # Panics
+/// Panics if the bytes can't be padded, e.g. with [NoPadding] and a number of bytes that is not a multiple of `16`.
+/// Use [try_encrypt_bytes] to handle this case.
 pub fn encrypt_bytes<const R: usize, K, P>(
     bytes: &[u8],
     key: &K,
     padding: &P,
     mode: EncryptionMode,
 ) -> Vec<u8>
+where
+    K: Key<R>,
+    P: Padding<16>,
+{
+    try_encrypt_bytes(bytes, key, padding, mode).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Encrypt a byte slice using a [Key] type, failing if it can't be padded
+///
+/// Same as [encrypt_bytes], but returns the [PaddingError]
+/// e.g. for [NoPadding] and a number of bytes that is not a multiple of `16`.
+pub fn try_encrypt_bytes<const R: usize, K, P>(
+    bytes: &[u8],
+    key: &K,
+    padding: &P,
+    mode: EncryptionMode,
+) -> Result<Vec<u8>, PaddingError>
 where
     K: Key<R>,
     P: Padding<16>,
 {
     log::trace!("Encrypt bytes");
 
-    let mut blocks = Block::load(bytes, padding);
+    // run the key schedule only once
+    let key = &Cipher::new(key);
+
+    match mode {
+        EncryptionMode::CTR(counter) => return Ok(ctr(bytes, key, counter)),
+        EncryptionMode::CFB(iv) => return Ok(cfb(bytes, key, iv)),
+        EncryptionMode::OFB(iv) => return Ok(ofb(bytes, key, iv)),
+        _ => (),
+    }
+
+    let mut blocks = Block::load(bytes, padding)?;
 
     match mode {
         EncryptionMode::ECB => ecb(&mut blocks, key),
         EncryptionMode::CBC(iv) => cbc(&mut blocks, key, iv),
+        EncryptionMode::PCBC(iv) => pcbc(&mut blocks, key, iv),
+        EncryptionMode::CTR(_) | EncryptionMode::CFB(_) | EncryptionMode::OFB(_) => {
+            unreachable!("Stream modes do not work on padded blocks")
+        }
     }
 
-    blocks.into_iter().flat_map(|b| b.dump_bytes()).collect()
+    Ok(blocks.into_iter().flat_map(|b| b.dump_bytes()).collect())
+}
+
+/// Encrypt a byte slice in [CBC](EncryptionMode) mode
+///
+/// Same as [encrypt_bytes] with [EncryptionMode::CBC],
+/// but the IV is passed directly, which is convenient when encrypting many messages.
+pub fn encrypt_bytes_cbc<const R: usize, K, P>(
+    bytes: &[u8],
+    key: &K,
+    padding: &P,
+    iv: InitializationVector,
+) -> Vec<u8>
+where
+    K: Key<R>,
+    P: Padding<16>,
+{
+    encrypt_bytes(bytes, key, padding, EncryptionMode::CBC(iv))
+}
+
+/// Errors of [encrypt_bytes_cts] and [decrypt_bytes_cts](crate::decryption::decrypt_bytes_cts)
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CtsError {
+    /// The input is not longer than one block, so there is nothing to steal from
+    TooShort,
+}
+
+impl fmt::Display for CtsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CtsError::TooShort => {
+                write!(f, "Ciphertext stealing needs more than one block of input")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CtsError {}
+
+/// Encrypt a byte slice in [CBC](EncryptionMode) mode with ciphertext stealing (CS3)
+///
+/// No padding is added: the ciphertext is exactly as long as the input.
+/// The last block is zero-filled before encryption and the last two ciphertext blocks are swapped,
+/// with the (now last) second-to-last block truncated to the length of the final plaintext chunk.
+/// This is the variant Kerberos uses, see [RFC 3962](https://www.rfc-editor.org/rfc/rfc3962).
+///
+/// # Return value
+/// Fails with [CtsError::TooShort] if the input is not longer than one block.
+pub fn encrypt_bytes_cts<const R: usize, K>(
+    bytes: &[u8],
+    key: &K,
+    iv: InitializationVector,
+) -> Result<Vec<u8>, CtsError>
+where
+    K: Key<R>,
+{
+    log::trace!("Encrypt bytes with ciphertext stealing");
+
+    if bytes.len() <= 16 {
+        log::error!("{}", CtsError::TooShort);
+        return Err(CtsError::TooShort);
+    }
+
+    let n = bytes.len().div_ceil(16);
+    let last_len = bytes.len() - (n - 1) * 16;
+
+    let mut padded = bytes.to_vec();
+    padded.resize(n * 16, 0);
+
+    let encrypted = encrypt_bytes_cbc(&padded, key, &NoPadding, iv);
+    let (head, tail) = encrypted.split_at((n - 2) * 16);
+
+    let mut ciphertext = head.to_vec();
+    ciphertext.extend_from_slice(&tail[16..]);
+    ciphertext.extend_from_slice(&tail[..last_len]);
+
+    Ok(ciphertext)
+}
+
+/// XOR bytes with the encryption of caller-supplied counter blocks
+///
+/// This is [CTR](EncryptionMode::CTR) mode with a custom counter scheme
+/// (e.g. a nonce followed by a block counter).
+/// The keystream of the last block is truncated to the remaining bytes,
+/// so the same call also decrypts.
+///
+/// **Warning**: a counter block must never repeat under the same key,
+/// neither within one message nor across messages.
+/// Two messages encrypted with the same keystream reveal the XOR of their plaintexts.
+///
+/// # Panics
+/// Panics if the iterator runs out of counter blocks before all bytes are processed.
+pub fn keystream_with<const R: usize, K, I>(bytes: &[u8], key: &K, counter_blocks: I) -> Vec<u8>
+where
+    K: Key<R>,
+    I: IntoIterator<Item = [u8; 16]>,
+{
+    log::trace!("Keystream encryption");
+
+    let mut counter_blocks = counter_blocks.into_iter();
+
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut n = 0;
+    for chunk in bytes.chunks(16) {
+        let counter_block = counter_blocks
+            .next()
+            .expect("Ran out of counter blocks before the end of the input");
+
+        let mut keystream = Block::from_bytes(counter_block);
+        encrypt_block(&mut keystream, key);
+
+        if let Ok(chunk) = chunk.try_into() {
+            let mut block = Block::from_bytes(chunk);
+            block ^= keystream;
+            output.extend_from_slice(&block.dump_bytes());
+        } else {
+            let keystream = keystream.dump_bytes();
+            output.extend(chunk.iter().zip(keystream).map(|(b, k)| b ^ k));
+        }
+
+        n += 1;
+    }
+
+    log::debug!("Encrypted {n} blocks");
+
+    output
+}
+
+/// Endless [CTR](EncryptionMode::CTR) keystream
+///
+/// The counter blocks are encrypted lazily, one at a time, so the keystream can be XORed onto data
+/// that doesn't fit into a single slice (e.g. when it arrives in pieces).
+/// After the counter `u128::MAX` it wraps around to `0` like [encrypt_bytes].
+///
+/// **Warning**: a counter value must never be reused under the same key.
+pub struct CtrKeystream<const R: usize> {
+    cipher: Cipher<R>,
+    counter: u128,
+    block: [u8; 16],
+    position: usize,
+}
+
+impl<const R: usize> CtrKeystream<R> {
+    /// Constructor that takes the key and the counter of the first block
+    pub fn new<K>(key: &K, counter: u128) -> Self
+    where
+        K: Key<R>,
+    {
+        Self {
+            cipher: Cipher::new(key),
+            counter,
+            block: [0; 16],
+            position: 16,
+        }
+    }
+
+    /// Continue with the first byte of the keystream block of `counter`
+    pub fn seek(&mut self, counter: u128) {
+        self.counter = counter;
+        self.position = 16;
+    }
+}
+
+impl<const R: usize> Iterator for CtrKeystream<R> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.position == 16 {
+            let mut block = Block::from(self.counter);
+            self.cipher.encrypt_block(&mut block);
+
+            self.block = block.dump_bytes();
+            self.counter = self.counter.wrapping_add(1);
+            self.position = 0;
+        }
+
+        let byte = self.block[self.position];
+        self.position += 1;
+
+        Some(byte)
+    }
+}
+
+/// Encrypt a set of records (e.g. the fields of a column) independently of each other
+///
+/// Each record is encrypted in [CBC](EncryptionMode) mode with [Pkcs7Padding].
+/// The IV of the record at index `i` is derived from the tweak `tweak_base + i`,
+/// so identical records don't produce identical ciphertexts.
+/// The encryption is still deterministic for a given `tweak_base`,
+/// therefore different record sets should use non-overlapping tweak ranges.
+///
+/// # Parameters
+/// - `records`: byte slices to encrypt
+/// - `key`: [Key] used for encryption
+/// - `tweak_base`: tweak of the first record
+pub fn encrypt_records<const R: usize, K>(
+    records: &[&[u8]],
+    key: &K,
+    tweak_base: u128,
+) -> Vec<Vec<u8>>
+where
+    K: Key<R>,
+{
+    log::trace!("Encrypt records");
+
+    records
+        .iter()
+        .enumerate()
+        .map(|(i, record)| {
+            let iv = record_iv(key, tweak_base, i);
+            encrypt_bytes(record, key, &Pkcs7Padding, EncryptionMode::CBC(iv))
+        })
+        .collect()
+}
+
+/// Derive the IV of a record by encrypting its tweak
+pub(crate) fn record_iv<const R: usize, K>(
+    key: &K,
+    tweak_base: u128,
+    index: usize,
+) -> InitializationVector
+where
+    K: Key<R>,
+{
+    let mut block = Block::from(tweak_base.wrapping_add(index as u128));
+    encrypt_block(&mut block, key);
+
+    block.into()
+}
+
+/// Compute the number of bytes [encrypt_bytes] produces
+///
+/// This is a pure calculation, e.g. to pre-allocate buffers or report progress.
+///
+/// # Parameters
+/// - `input_len`: number of bytes to encrypt
+/// - `mode`: [EncryptionMode] that is used for encryption
+/// - `padding`: how the bytes are padded
+pub fn output_len<P>(input_len: usize, mode: &EncryptionMode, padding: &P) -> usize
+where
+    P: Padding<16>,
+{
+    match mode {
+        EncryptionMode::ECB | EncryptionMode::CBC(_) | EncryptionMode::PCBC(_) => {
+            padding.padded_len(input_len)
+        }
+        EncryptionMode::CTR(_) | EncryptionMode::CFB(_) | EncryptionMode::OFB(_) => input_len,
+    }
 }
 
 /// Implementation of [ECB](EncryptionMode) encryption
@@ -74,9 +377,21 @@ where
 {
     log::trace!("ECB encryption");
 
-    for block in blocks {
-        encrypt_block(block, key);
+    let n = blocks.len();
+    if cfg!(feature = "parallel") && n >= parallel::THRESHOLD {
+        let round_keys = key.round_key_blocks();
+        parallel::for_each_chunk(blocks, |_, chunk| {
+            for block in chunk {
+                encrypt_block_with(block, &round_keys);
+            }
+        });
+    } else {
+        for block in blocks {
+            encrypt_block(block, key);
+        }
     }
+
+    log::debug!("Encrypted {n} blocks");
 }
 
 /// Implementation of [CBC](EncryptionMode) encryption
@@ -86,10 +401,135 @@ where
 {
     log::trace!("CBC encryption");
 
+    let n = blocks.len();
     let mut prev: Block = iv.into();
     for block in blocks {
         *block ^= prev;
         encrypt_block(block, key);
         prev = *block;
     }
+
+    log::debug!("Encrypted {n} blocks");
+}
+
+/// Implementation of [PCBC](EncryptionMode) encryption
+fn pcbc<const R: usize, K>(blocks: &mut [Block], key: &K, iv: InitializationVector)
+where
+    K: Key<R>,
+{
+    log::trace!("PCBC encryption");
+
+    let n = blocks.len();
+    let mut prev: Block = iv.into();
+    for block in blocks {
+        let plaintext = *block;
+        *block ^= prev;
+        encrypt_block(block, key);
+        prev = plaintext ^ *block;
+    }
+
+    log::debug!("Encrypted {n} blocks");
+}
+
+/// Implementation of [CTR](EncryptionMode) encryption and decryption
+///
+/// The counter is incremented as big-endian integer and wraps around.
+pub(crate) fn ctr<const R: usize, K>(bytes: &[u8], key: &K, counter: u128) -> Vec<u8>
+where
+    K: Key<R>,
+{
+    log::trace!("CTR encryption");
+
+    if cfg!(feature = "parallel") && bytes.len() / 16 >= parallel::THRESHOLD {
+        let round_keys = key.round_key_blocks();
+
+        let mut output = bytes.to_vec();
+        let mut chunks: Vec<&mut [u8]> = output.chunks_mut(16).collect();
+        let n = chunks.len();
+
+        // the counter of every block follows from its index
+        parallel::for_each_chunk(&mut chunks, |first, chunks| {
+            for (i, chunk) in chunks.iter_mut().enumerate() {
+                let mut keystream = Block::from(counter.wrapping_add((first + i) as u128));
+                encrypt_block_with(&mut keystream, &round_keys);
+
+                for (b, k) in chunk.iter_mut().zip(keystream.dump_bytes()) {
+                    *b ^= k;
+                }
+            }
+        });
+
+        log::debug!("Encrypted {n} blocks");
+        return output;
+    }
+
+    let counter_blocks =
+        std::iter::successors(Some(counter), |c| Some(c.wrapping_add(1))).map(u128::to_be_bytes);
+
+    keystream_with(bytes, key, counter_blocks)
+}
+
+/// Implementation of [CFB](EncryptionMode) encryption
+///
+/// The ciphertext of every block is fed back to produce the keystream of the next one.
+/// The keystream of the last block is truncated to the remaining bytes.
+fn cfb<const R: usize, K>(bytes: &[u8], key: &K, iv: InitializationVector) -> Vec<u8>
+where
+    K: Key<R>,
+{
+    log::trace!("CFB encryption");
+
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut prev: Block = iv.into();
+    let mut n = 0;
+    for chunk in bytes.chunks(16) {
+        encrypt_block(&mut prev, key);
+
+        if let Ok(chunk) = chunk.try_into() {
+            prev ^= Block::from_bytes(chunk);
+            output.extend_from_slice(&prev.dump_bytes());
+        } else {
+            let keystream = prev.dump_bytes();
+            output.extend(chunk.iter().zip(keystream).map(|(b, k)| b ^ k));
+        }
+
+        n += 1;
+    }
+
+    log::debug!("Encrypted {n} blocks");
+
+    output
+}
+
+/// Implementation of [OFB](EncryptionMode) encryption and decryption
+///
+/// The feedback is the raw cipher output, so the keystream doesn't depend on the data.
+/// The keystream of the last block is truncated to the remaining bytes.
+pub(crate) fn ofb<const R: usize, K>(bytes: &[u8], key: &K, iv: InitializationVector) -> Vec<u8>
+where
+    K: Key<R>,
+{
+    log::trace!("OFB encryption");
+
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut keystream: Block = iv.into();
+    let mut n = 0;
+    for chunk in bytes.chunks(16) {
+        encrypt_block(&mut keystream, key);
+
+        if let Ok(chunk) = chunk.try_into() {
+            let mut block = Block::from_bytes(chunk);
+            block ^= keystream;
+            output.extend_from_slice(&block.dump_bytes());
+        } else {
+            let keystream = keystream.dump_bytes();
+            output.extend(chunk.iter().zip(keystream).map(|(b, k)| b ^ k));
+        }
+
+        n += 1;
+    }
+
+    log::debug!("Encrypted {n} blocks");
+
+    output
 }
@@ -0,0 +1,201 @@
+//! Encrypt-then-MAC module
+//!
+//! The [encryption modes](EncryptionMode) only provide confidentiality:
+//! a modified ciphertext still decrypts, usually to garbage, sometimes to something useful for an attacker.
+//! This module appends an HMAC-SHA256 tag to the ciphertext and checks it before anything is decrypted.
+//!
+//! The tag is computed over `associated data || IV || ciphertext || length of the associated data`,
+//! where the IV is empty in [ECB](EncryptionMode::ECB) mode and the initial counter (big-endian) in [CTR](EncryptionMode::CTR) mode.
+//! The length (in bits, as 64 bit big-endian integer) makes the boundary between associated data and IV unambiguous.
+//!
+//! The MAC key must be independent of the encryption key,
+//! e.g. both derived from one secret with [HKDF](crate::kdf::hkdf) and different `info` values.
+
+use std::fmt;
+
+use crate::decryption::{decrypt_bytes, DecryptError};
+use crate::encryption::try_encrypt_bytes;
+use crate::kdf::hmac_sha256;
+use crate::key::Key;
+use crate::padding::{Padding, PaddingError};
+use crate::util;
+use crate::EncryptionMode;
+
+/// Size of the authentication tag (in bytes)
+pub const TAG_SIZE: usize = 32;
+
+/// Errors of [verify_then_decrypt]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EtmError {
+    /// The data is too short to contain a tag
+    InvalidLength,
+
+    /// The tag does not match, so the ciphertext, the IV or the associated data was tampered with
+    AuthenticationFailed,
+
+    /// The tag matches, but the ciphertext could not be decrypted (e.g. because of a wrong padding mode)
+    Decrypt(DecryptError),
+}
+
+impl fmt::Display for EtmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EtmError::InvalidLength => write!(f, "Data is too short to contain a tag"),
+            EtmError::AuthenticationFailed => write!(f, "Authentication failed"),
+            EtmError::Decrypt(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for EtmError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EtmError::Decrypt(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Encrypt a byte slice and append an HMAC-SHA256 tag
+///
+/// # Parameters
+/// - `bytes`: byte slice to encrypt
+/// - `key`: [Key] used for encryption
+/// - `mac_key`: secret key of the HMAC, independent of `key`
+/// - `padding`: how the bytes should be padded, see [try_encrypt_bytes]
+/// - `mode`: [EncryptionMode] used for encryption
+/// - `aad`: associated data that is authenticated but not encrypted (may be empty)
+///
+/// # Return value
+/// The ciphertext followed by the 32 byte tag,
+/// or the [PaddingError] if the bytes can't be padded.
+pub fn encrypt_then_mac<const R: usize, K, P>(
+    bytes: &[u8],
+    key: &K,
+    mac_key: &[u8],
+    padding: &P,
+    mode: EncryptionMode,
+    aad: &[u8],
+) -> Result<Vec<u8>, PaddingError>
+where
+    K: Key<R>,
+    P: Padding<16>,
+{
+    log::trace!("Encrypt-then-MAC encryption");
+
+    let mut ciphertext = try_encrypt_bytes(bytes, key, padding, mode)?;
+    let tag = compute_tag(mac_key, mode, aad, &ciphertext);
+    ciphertext.extend_from_slice(&tag);
+
+    Ok(ciphertext)
+}
+
+/// Verify the tag of a byte slice and decrypt it
+///
+/// # Parameters
+/// - `bytes`: ciphertext followed by the 32 byte tag
+/// - `key`: [Key] that was used for encryption
+/// - `mac_key`: secret key of the HMAC that was used for encryption
+/// - `padding`: how the decrypted bytes should be unpadded, see [decrypt_bytes]
+/// - `mode`: [EncryptionMode] that was used for encryption
+/// - `aad`: associated data that was authenticated
+///
+/// # Return value
+/// The tag is checked (in constant time) before anything is decrypted,
+/// so no plaintext is produced for a tampered message.
+pub fn verify_then_decrypt<const R: usize, K, P>(
+    bytes: &[u8],
+    key: &K,
+    mac_key: &[u8],
+    padding: Option<P>,
+    mode: EncryptionMode,
+    aad: &[u8],
+) -> Result<Vec<u8>, EtmError>
+where
+    K: Key<R>,
+    P: Padding<16>,
+{
+    log::trace!("Encrypt-then-MAC decryption");
+
+    if bytes.len() < TAG_SIZE {
+        log::error!("{}", EtmError::InvalidLength);
+        return Err(EtmError::InvalidLength);
+    }
+
+    let (ciphertext, tag) = bytes.split_at(bytes.len() - TAG_SIZE);
+
+    let expected_tag = compute_tag(mac_key, mode, aad, ciphertext);
+    if !util::ct_eq(&expected_tag, tag) {
+        log::error!("{}", EtmError::AuthenticationFailed);
+        return Err(EtmError::AuthenticationFailed);
+    }
+
+    decrypt_bytes(ciphertext, key, padding, mode).map_err(EtmError::Decrypt)
+}
+
+/// HMAC-SHA256 over the associated data, the IV and the ciphertext
+fn compute_tag(
+    mac_key: &[u8],
+    mode: EncryptionMode,
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> [u8; TAG_SIZE] {
+    let iv = match mode {
+        EncryptionMode::ECB => Vec::new(),
+        EncryptionMode::CTR(counter) => counter.to_be_bytes().to_vec(),
+        EncryptionMode::CBC(iv)
+        | EncryptionMode::CFB(iv)
+        | EncryptionMode::OFB(iv)
+        | EncryptionMode::PCBC(iv) => iv.as_bytes().to_vec(),
+    };
+    let aad_bits = (aad.len() as u64 * 8).to_be_bytes();
+
+    hmac_sha256(mac_key, &[aad, &iv, ciphertext, &aad_bits].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::AES128Key;
+    use crate::padding::Pkcs7Padding;
+    use crate::InitializationVector;
+
+    #[test]
+    fn tag_covers_iv_and_aad() {
+        let key = AES128Key::from_bytes(*b"0123456789abcdef");
+        let mac_key = b"a separate mac key";
+        let mode = EncryptionMode::CBC(InitializationVector::from_bytes([7; 16]));
+
+        let data =
+            encrypt_then_mac(b"attack at dawn", &key, mac_key, &Pkcs7Padding, mode, b"v1").unwrap();
+        assert_eq!(data.len(), 16 + TAG_SIZE);
+
+        let other_iv = EncryptionMode::CBC(InitializationVector::from_bytes([8; 16]));
+        let results = [
+            verify_then_decrypt(&data, &key, mac_key, Some(Pkcs7Padding), other_iv, b"v1"),
+            verify_then_decrypt(&data, &key, mac_key, Some(Pkcs7Padding), mode, b"v2"),
+            verify_then_decrypt(&data, &key, b"wrong", Some(Pkcs7Padding), mode, b"v1"),
+        ];
+        for result in results {
+            assert_eq!(result, Err(EtmError::AuthenticationFailed));
+        }
+
+        // moving bytes between associated data and IV changes the tag
+        assert_ne!(
+            compute_tag(mac_key, EncryptionMode::ECB, &[7; 16], &data[..16]),
+            compute_tag(mac_key, mode, &[], &data[..16])
+        );
+
+        assert_eq!(
+            verify_then_decrypt(
+                &data[..TAG_SIZE - 1],
+                &key,
+                mac_key,
+                Some(Pkcs7Padding),
+                mode,
+                b"v1"
+            ),
+            Err(EtmError::InvalidLength)
+        );
+    }
+}
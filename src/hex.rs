@@ -0,0 +1,69 @@
+//! Hex module
+//!
+//! This module provides the hex encoding that is used to pass keys, IVs and salts as text.
+//! Both lower and upper case digits are accepted when decoding.
+
+use std::fmt;
+
+/// Errors of [decode]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum HexError {
+    /// A character is not a hex digit
+    InvalidDigit,
+
+    /// The number of digits is odd, so the last byte is incomplete
+    OddLength,
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HexError::InvalidDigit => write!(f, "Hex string must consist of hex digits only"),
+            HexError::OddLength => {
+                write!(f, "Hex string must consist of an even number of hex digits")
+            }
+        }
+    }
+}
+
+impl std::error::Error for HexError {}
+
+/// Encode bytes as lower case hex digits
+pub fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Decode an even number of hex digits
+pub fn decode(s: &str) -> Result<Vec<u8>, HexError> {
+    if !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(HexError::InvalidDigit);
+    }
+    if !s.len().is_multiple_of(2) {
+        return Err(HexError::OddLength);
+    }
+
+    Ok((0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(decode("").unwrap(), b"");
+        assert_eq!(decode("00ff7Fa0").unwrap(), [0x00, 0xff, 0x7f, 0xa0]);
+        assert_eq!(encode(&[0x00, 0xff, 0x7f, 0xa0]), "00ff7fa0");
+    }
+
+    #[test]
+    fn malformed() {
+        for s in ["0g", "+0", " 00", "ä0", "0x00"] {
+            assert_eq!(decode(s), Err(HexError::InvalidDigit), "{s}");
+        }
+        assert_eq!(decode("000"), Err(HexError::OddLength));
+    }
+}
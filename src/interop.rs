@@ -0,0 +1,197 @@
+//! Interop module
+//!
+//! This module reads and writes the container format of `openssl enc`:
+//! the magic bytes `Salted__`, an 8 byte salt and the ciphertext in [CBC](EncryptionMode::CBC) mode
+//! with PKCS #7 padding.
+//! Key and IV are derived from a passphrase and the salt like OpenSSL's `EVP_BytesToKey`
+//! with MD5 and a single iteration (`openssl enc -md md5`).
+//!
+//! This key derivation is far too cheap to resist guessing the passphrase,
+//! so the format should only be used to exchange data with OpenSSL.
+
+use std::fmt;
+
+use crate::decryption::DecryptError;
+use crate::iv::InitializationVector;
+use crate::key::AnyKey;
+use crate::md5::md5;
+use crate::padding::Pkcs7Padding;
+use crate::EncryptionMode;
+
+/// Magic bytes at the start of the container
+pub const SALTED_MAGIC: &[u8; 8] = b"Salted__";
+
+/// Length of the header (magic bytes and salt)
+pub const SALTED_HEADER_LEN: usize = 16;
+
+/// Error message of a missing header
+const MISSING_HEADER: &str = "Data does not start with the OpenSSL header \"Salted__\"";
+
+/// Errors of [decrypt_salted]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SaltedError {
+    /// The data doesn't start with [SALTED_MAGIC] and a salt
+    MissingHeader,
+
+    /// The ciphertext could not be decrypted, usually because the passphrase is wrong
+    Decrypt(DecryptError),
+}
+
+impl fmt::Display for SaltedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaltedError::MissingHeader => write!(f, "{MISSING_HEADER}"),
+            SaltedError::Decrypt(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for SaltedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SaltedError::MissingHeader => None,
+            SaltedError::Decrypt(err) => Some(err),
+        }
+    }
+}
+
+/// Derive a key of `key_len` bytes and an IV like `EVP_BytesToKey` with MD5 and one iteration
+///
+/// The derived bytes are `D_1 || D_2 || ...` with `D_i = MD5(D_(i-1) || passphrase || salt)`,
+/// the key comes first and the IV follows.
+pub fn evp_bytes_to_key(
+    passphrase: &[u8],
+    salt: &[u8; 8],
+    key_len: usize,
+) -> (Vec<u8>, InitializationVector) {
+    log::trace!("Derive key and IV with EVP_BytesToKey");
+
+    let mut derived = Vec::with_capacity(key_len + 32);
+    let mut digest: &[u8] = &[];
+    while derived.len() < key_len + 16 {
+        let start = derived.len();
+        derived.extend_from_slice(&md5(&[digest, passphrase, salt].concat()));
+        digest = &derived[start..];
+    }
+
+    let iv = InitializationVector::from_bytes(derived[key_len..key_len + 16].try_into().unwrap());
+    derived.truncate(key_len);
+
+    (derived, iv)
+}
+
+/// Split the header off an OpenSSL container
+///
+/// # Return value
+/// The salt and the ciphertext, or an error if the data doesn't start with [SALTED_MAGIC] and a salt.
+pub fn split_salted_header(data: &[u8]) -> Result<([u8; 8], &[u8]), &'static str> {
+    if data.len() < SALTED_HEADER_LEN || !data.starts_with(SALTED_MAGIC) {
+        log::error!("{}", MISSING_HEADER);
+        return Err(MISSING_HEADER);
+    }
+
+    let (header, ciphertext) = data.split_at(SALTED_HEADER_LEN);
+    Ok((header[8..].try_into().unwrap(), ciphertext))
+}
+
+/// Encrypt a byte slice like `openssl enc -aes-<bits>-cbc -md md5`
+///
+/// # Panics
+/// Panics if `bits` is not one of `128`, `192` or `256`.
+pub fn encrypt_salted(plaintext: &[u8], passphrase: &[u8], salt: [u8; 8], bits: usize) -> Vec<u8> {
+    log::trace!("Encrypt bytes in the OpenSSL format");
+
+    let (key, iv) = evp_bytes_to_key(passphrase, &salt, bits / 8);
+    let key = AnyKey::from_slice(&key).expect("AES keys have 128, 192 or 256 bits");
+
+    let mut data = [&SALTED_MAGIC[..], &salt].concat();
+    data.extend(key.encrypt_bytes(plaintext, &Pkcs7Padding, EncryptionMode::CBC(iv)));
+
+    data
+}
+
+/// Decrypt the output of `openssl enc -aes-<bits>-cbc -md md5`
+///
+/// # Return value
+/// Fails with [SaltedError::MissingHeader] if the header is missing
+/// or with [SaltedError::Decrypt] if the decryption fails, e.g. because the passphrase is wrong.
+///
+/// # Panics
+/// Panics if `bits` is not one of `128`, `192` or `256`.
+pub fn decrypt_salted(data: &[u8], passphrase: &[u8], bits: usize) -> Result<Vec<u8>, SaltedError> {
+    log::trace!("Decrypt bytes in the OpenSSL format");
+
+    let (salt, ciphertext) = split_salted_header(data).map_err(|_| SaltedError::MissingHeader)?;
+
+    let (key, iv) = evp_bytes_to_key(passphrase, &salt, bits / 8);
+    let key = AnyKey::from_slice(&key).expect("AES keys have 128, 192 or 256 bits");
+
+    key.decrypt_bytes(ciphertext, Some(Pkcs7Padding), EncryptionMode::CBC(iv))
+        .map_err(SaltedError::Decrypt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evp_bytes_to_key_matches_openssl() {
+        // openssl enc -aes-256-cbc -md md5 -S 0102030405060708 -pass pass:correct-horse -P
+        let (key, iv) = evp_bytes_to_key(b"correct-horse", &[1, 2, 3, 4, 5, 6, 7, 8], 32);
+        assert_eq!(
+            key,
+            [
+                0xe6, 0xe1, 0xd6, 0x13, 0xd6, 0x1f, 0xc6, 0x44, 0xb1, 0xdd, 0xf7, 0x6e, 0xbb, 0x33,
+                0xf5, 0xea, 0x71, 0xe6, 0xc6, 0x7b, 0xa5, 0x0b, 0xf0, 0x4f, 0x44, 0xa0, 0xd4, 0x15,
+                0xc0, 0x93, 0xb5, 0xb5
+            ]
+        );
+        assert_eq!(
+            iv.as_bytes(),
+            [
+                0x9a, 0x24, 0xa6, 0x2d, 0xc6, 0x70, 0x9d, 0x35, 0x96, 0x87, 0x3c, 0xa5, 0x54, 0x44,
+                0xb3, 0xdf
+            ]
+        );
+
+        // openssl enc -aes-128-cbc -md md5 -S a1b2c3d4e5f60718 -pass pass:secret -P
+        let salt = [0xa1, 0xb2, 0xc3, 0xd4, 0xe5, 0xf6, 0x07, 0x18];
+        let (key, iv) = evp_bytes_to_key(b"secret", &salt, 16);
+        assert_eq!(
+            key,
+            [
+                0x0f, 0x97, 0x33, 0x56, 0xe2, 0xee, 0xc6, 0x6b, 0xac, 0xd3, 0x39, 0x7c, 0x5d, 0x1d,
+                0x78, 0x74
+            ]
+        );
+        assert_eq!(
+            iv.as_bytes(),
+            [
+                0x37, 0xa6, 0xa3, 0x41, 0x7d, 0x6f, 0x76, 0x63, 0x7e, 0x2f, 0x22, 0xc9, 0xbe, 0xcb,
+                0xa5, 0xeb
+            ]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let plaintext = b"I use Rust btw";
+
+        for bits in [128, 192, 256] {
+            let data = encrypt_salted(plaintext, b"secret", *b"saltsalt", bits);
+            assert!(data.starts_with(b"Salted__saltsalt"));
+            assert_eq!(data.len(), 32);
+
+            assert_eq!(decrypt_salted(&data, b"secret", bits).unwrap(), plaintext);
+        }
+
+        assert_eq!(
+            decrypt_salted(b"Salted__salt", b"secret", 256),
+            Err(SaltedError::MissingHeader)
+        );
+        assert_eq!(
+            decrypt_salted(b"Unsalted__saltsalt0123456789abcdef", b"secret", 256),
+            Err(SaltedError::MissingHeader)
+        );
+    }
+}
@@ -2,7 +2,14 @@
 //!
 //! This module provides a wrapper type for a [Block] that is used as initialization vector (IV).
 
+use std::str::FromStr;
+
+use crate::base64;
 use crate::block::Block;
+use crate::hex;
+use crate::kdf::hkdf;
+use crate::key::{AES256Key, Key};
+use crate::mac::cmac;
 
 /// Initialization vector (IV) wrapper
 ///
@@ -21,7 +28,52 @@ impl InitializationVector {
     /// Construct a random IV
     #[cfg(feature = "rand")]
     pub fn random() -> Self {
-        Self(Block::from_bytes(rand::random()))
+        Self::random_from(&mut rand::thread_rng())
+    }
+
+    /// Construct a random IV using the given random number generator
+    #[cfg(feature = "rand")]
+    pub fn random_from<R>(rng: &mut R) -> Self
+    where
+        R: rand::Rng + ?Sized,
+    {
+        Self(Block::from_bytes(rng.gen()))
+    }
+
+    /// Derive an IV from the [CMAC](crate::mac::cmac) of a counter (as 16 big-endian bytes)
+    ///
+    /// The CMAC key is derived from the round keys of `key` with [HKDF](crate::kdf::hkdf),
+    /// so the encryption key is not used for the MAC as well.
+    /// CMAC is a pseudorandom function, so distinct counters give unpredictable IVs
+    /// that collide only with negligible probability.
+    /// No random number generator is needed as long as a counter is never used twice.
+    ///
+    /// [NIST SP 800-38A, Appendix C](https://csrc.nist.gov/publications/detail/sp/800-38a/final)
+    /// suggests encrypting the counter instead, which needs one block operation less.
+    pub fn from_counter<const R: usize, K>(counter: u128, key: &K) -> Self
+    where
+        K: Key<R>,
+    {
+        log::trace!("Derive IV from counter");
+
+        let round_keys: Vec<u8> = key
+            .round_key_blocks()
+            .iter()
+            .flat_map(Block::dump_bytes)
+            .collect();
+        let mac_key = hkdf(&round_keys, &[], b"aesculap iv counter", 32);
+        let mac_key = AES256Key::from_bytes(mac_key.try_into().unwrap());
+
+        Self::from_bytes(cmac(&mac_key, &counter.to_be_bytes()))
+    }
+
+    /// Parse an IV from Base64 that decodes to 16 bytes
+    pub fn from_base64(s: &str) -> Result<Self, &'static str> {
+        let bytes: [u8; 16] = base64::decode(s)?
+            .try_into()
+            .map_err(|_| "IV must have exactly 16 bytes")?;
+
+        Ok(Self::from_bytes(bytes))
     }
 
     /// Get the inner bytes
@@ -30,6 +82,18 @@ impl InitializationVector {
     }
 }
 
+impl FromStr for InitializationVector {
+    type Err = &'static str;
+
+    /// Parse an IV from 32 hex digits
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = "IV must consist of exactly 32 hex digits";
+        let bytes = hex::decode(s).map_err(|_| err)?;
+
+        Ok(Self::from_bytes(bytes.try_into().map_err(|_| err)?))
+    }
+}
+
 impl From<[u8; 16]> for InitializationVector {
     fn from(value: [u8; 16]) -> Self {
         InitializationVector::from_bytes(value)
@@ -53,3 +117,72 @@ impl From<InitializationVector> for Block {
         val.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_hex() {
+        let iv: InitializationVector = "000102030405060708090a0B0c0D0e0F".parse().unwrap();
+        assert_eq!(iv.as_bytes(), std::array::from_fn(|i| i as u8));
+    }
+
+    #[test]
+    fn from_base64() {
+        let iv = InitializationVector::from_base64("AAECAwQFBgcICQoLDA0ODw==").unwrap();
+        assert_eq!(iv.as_bytes(), std::array::from_fn(|i| i as u8));
+
+        assert_eq!(
+            InitializationVector::from_base64(&base64::encode(b"abcdef0123456789")).unwrap(),
+            InitializationVector::from_bytes(*b"abcdef0123456789")
+        );
+
+        assert_eq!(
+            InitializationVector::from_base64("AAECAwQFBgcICQoLDA0O").unwrap_err(),
+            "IV must have exactly 16 bytes"
+        );
+        assert!(InitializationVector::from_base64("AAECAwQFBgcICQoLDA0ODw").is_err());
+        assert!(InitializationVector::from_base64("AAECAwQFBgcICQoLDA0OD*==").is_err());
+    }
+
+    #[test]
+    fn from_counter() {
+        use crate::key::AES128Key;
+
+        // computed with the HKDF and CMAC of Python's cryptography package
+        let key = AES128Key::from_bytes(*b"0123456789abcdef");
+        let iv = InitializationVector::from_counter(1, &key);
+        assert_eq!(
+            iv,
+            InitializationVector::from(0x79cd3a26b0f1bd596c0ed14d6f02fb59)
+        );
+
+        // the encryption key is not used for the CMAC
+        assert_ne!(iv.as_bytes(), cmac(&key, &1u128.to_be_bytes()));
+
+        assert_ne!(
+            InitializationVector::from_counter(1, &key),
+            InitializationVector::from_counter(2, &key)
+        );
+    }
+
+    #[test]
+    fn from_invalid_hex() {
+        assert!("000102030405060708090a0b0c0d0e"
+            .parse::<InitializationVector>()
+            .is_err());
+        assert!("000102030405060708090a0b0c0d0e0f10"
+            .parse::<InitializationVector>()
+            .is_err());
+        assert!("000102030405060708090a0b0c0d0e0g"
+            .parse::<InitializationVector>()
+            .is_err());
+        assert!("+00102030405060708090a0b0c0d0e0f"
+            .parse::<InitializationVector>()
+            .is_err());
+        assert!("00010203040506070809äb0c0d0e0f"
+            .parse::<InitializationVector>()
+            .is_err());
+    }
+}
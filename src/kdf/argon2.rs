@@ -0,0 +1,393 @@
+//! Argon2id key derivation
+//!
+//! For reference, see [RFC 9106](https://www.rfc-editor.org/rfc/rfc9106).
+
+use crate::blake2b::blake2b;
+
+/// Size of a memory block (in 64 bit words)
+const BLOCK_WORDS: usize = 128;
+
+/// Number of slices per lane, the lanes synchronize after each slice
+const SYNC_POINTS: usize = 4;
+
+/// Argon2 version 1.3
+const VERSION: u32 = 0x13;
+
+/// Type identifier of Argon2id
+const ARGON2ID: u32 = 2;
+
+type Block = [u64; BLOCK_WORDS];
+
+/// Derive an `N` byte key from a passphrase using Argon2id
+///
+/// RFC 9106 recommends `memory = 2^21` (2 GiB), `iterations = 1` and `parallelism = 4`
+/// or, if that is too much memory, `memory = 2^16` (64 MiB), `iterations = 3` and `parallelism = 4`.
+///
+/// # Parameters
+/// - `passphrase`: the secret passphrase
+/// - `salt`: random value that is stored with the ciphertext, at least 16 bytes are recommended
+/// - `memory`: memory cost in KiB, at least `8 * parallelism`
+/// - `iterations`: number of passes over the memory
+/// - `parallelism`: number of lanes (the lanes are computed one after the other)
+///
+/// # Panics
+/// Panics if `N` is less than `4`, if `iterations` is zero,
+/// if `parallelism` is not between `1` and `2^24 - 1` or if `memory` is less than `8 * parallelism`.
+pub fn argon2id_key<const N: usize>(
+    passphrase: &[u8],
+    salt: &[u8],
+    memory: u32,
+    iterations: u32,
+    parallelism: u32,
+) -> [u8; N] {
+    log::trace!(
+        "Derive {N} bytes using Argon2id (m = {memory} KiB, t = {iterations}, p = {parallelism})"
+    );
+
+    assert!(N >= 4, "Argon2 derives at least 4 bytes");
+
+    let cost = Cost {
+        memory,
+        iterations,
+        parallelism,
+    };
+
+    let mut key = [0; N];
+    argon2id(passphrase, salt, &[], &[], cost, &mut key);
+
+    key
+}
+
+/// Cost parameters of Argon2
+#[derive(Clone, Copy)]
+struct Cost {
+    memory: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+/// Fill `out` using Argon2id with an optional secret and associated data
+fn argon2id(
+    passphrase: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    associated_data: &[u8],
+    cost: Cost,
+    out: &mut [u8],
+) {
+    let Cost {
+        memory,
+        iterations,
+        parallelism,
+    } = cost;
+
+    assert!(iterations > 0, "Argon2 needs at least one iteration");
+    assert!(
+        (1..1 << 24).contains(&parallelism),
+        "Argon2 parallelism must be between 1 and 2^24 - 1"
+    );
+    assert!(
+        memory / 8 >= parallelism,
+        "Argon2 needs at least 8 KiB of memory per lane"
+    );
+
+    let length_prefixed = |bytes: &[u8]| [&(bytes.len() as u32).to_le_bytes(), bytes].concat();
+    let h0 = blake2b(
+        &[
+            &parallelism.to_le_bytes()[..],
+            &(out.len() as u32).to_le_bytes(),
+            &memory.to_le_bytes(),
+            &iterations.to_le_bytes(),
+            &VERSION.to_le_bytes(),
+            &ARGON2ID.to_le_bytes(),
+            &length_prefixed(passphrase),
+            &length_prefixed(salt),
+            &length_prefixed(secret),
+            &length_prefixed(associated_data),
+        ]
+        .concat(),
+        64,
+    );
+
+    let lanes = parallelism as usize;
+    let lane_len = memory as usize / (SYNC_POINTS * lanes) * SYNC_POINTS;
+    let mut state = Memory {
+        blocks: vec![[0; BLOCK_WORDS]; lanes * lane_len],
+        lanes,
+        lane_len,
+        passes: iterations as usize,
+    };
+
+    for lane in 0..lanes {
+        for column in 0..2 {
+            let seed = [
+                &h0[..],
+                &(column as u32).to_le_bytes(),
+                &(lane as u32).to_le_bytes(),
+            ];
+            let bytes = hash_long(&seed.concat(), BLOCK_WORDS * 8);
+
+            state.blocks[lane * lane_len + column] = std::array::from_fn(|i| {
+                u64::from_le_bytes(bytes[i * 8..i * 8 + 8].try_into().unwrap())
+            });
+        }
+    }
+
+    for pass in 0..state.passes {
+        for slice in 0..SYNC_POINTS {
+            for lane in 0..lanes {
+                state.fill_segment(pass, slice, lane);
+            }
+        }
+    }
+
+    let mut last = [0; BLOCK_WORDS];
+    for lane in 0..lanes {
+        let block = &state.blocks[lane * lane_len + lane_len - 1];
+        last.iter_mut().zip(block).for_each(|(l, b)| *l ^= b);
+    }
+
+    let last: Vec<u8> = last.iter().flat_map(|word| word.to_le_bytes()).collect();
+    out.copy_from_slice(&hash_long(&last, out.len()));
+}
+
+/// Memory blocks of all lanes
+struct Memory {
+    blocks: Vec<Block>,
+    lanes: usize,
+    lane_len: usize,
+    passes: usize,
+}
+
+impl Memory {
+    /// Compute the blocks of one slice of a lane
+    fn fill_segment(&mut self, pass: usize, slice: usize, lane: usize) {
+        let segment_len = self.lane_len / SYNC_POINTS;
+
+        // Argon2id uses data-independent addressing in the first half of the first pass
+        let data_independent = pass == 0 && slice < SYNC_POINTS / 2;
+        let mut input = [0; BLOCK_WORDS];
+        let mut addresses = [0; BLOCK_WORDS];
+        input[..6].copy_from_slice(&[
+            pass as u64,
+            lane as u64,
+            slice as u64,
+            self.blocks.len() as u64,
+            self.passes as u64,
+            ARGON2ID as u64,
+        ]);
+
+        // the first two blocks of each lane are derived from the initial hash
+        let first = if pass == 0 && slice == 0 { 2 } else { 0 };
+        for index in first..segment_len {
+            let column = slice * segment_len + index;
+            let current = lane * self.lane_len + column;
+            let previous = if column == 0 {
+                current + self.lane_len - 1
+            } else {
+                current - 1
+            };
+
+            let pseudo_random = if data_independent {
+                if index == first || index % BLOCK_WORDS == 0 {
+                    input[6] += 1;
+                    addresses = compress(&[0; BLOCK_WORDS], &compress(&[0; BLOCK_WORDS], &input));
+                }
+                addresses[index % BLOCK_WORDS]
+            } else {
+                self.blocks[previous][0]
+            };
+
+            let reference_lane = if pass == 0 && slice == 0 {
+                lane
+            } else {
+                (pseudo_random >> 32) as usize % self.lanes
+            };
+            let reference_column = self.reference_column(
+                pass,
+                slice,
+                index,
+                pseudo_random as u32,
+                reference_lane == lane,
+            );
+            let reference = reference_lane * self.lane_len + reference_column;
+
+            let block = compress(&self.blocks[previous], &self.blocks[reference]);
+            if pass == 0 {
+                self.blocks[current] = block;
+            } else {
+                let current = &mut self.blocks[current];
+                current.iter_mut().zip(block).for_each(|(c, b)| *c ^= b);
+            }
+        }
+    }
+
+    /// Map a pseudo-random value to a column of the reference lane
+    ///
+    /// Only blocks that are already computed (and not in a segment that is computed in parallel)
+    /// can be referenced, with a bias towards recent ones.
+    fn reference_column(
+        &self,
+        pass: usize,
+        slice: usize,
+        index: usize,
+        pseudo_random: u32,
+        same_lane: bool,
+    ) -> usize {
+        let segment_len = self.lane_len / SYNC_POINTS;
+
+        let finished = if pass == 0 {
+            slice * segment_len
+        } else {
+            self.lane_len - segment_len
+        };
+        let area = if same_lane {
+            finished + index - 1
+        } else if index == 0 {
+            finished - 1
+        } else {
+            finished
+        };
+
+        let x = (pseudo_random as u64 * pseudo_random as u64) >> 32;
+        let relative = area - 1 - ((area as u64 * x) >> 32) as usize;
+
+        let start = if pass == 0 || slice == SYNC_POINTS - 1 {
+            0
+        } else {
+            (slice + 1) * segment_len
+        };
+
+        (start + relative) % self.lane_len
+    }
+}
+
+/// Compression function `G`
+fn compress(x: &Block, y: &Block) -> Block {
+    let r: Block = std::array::from_fn(|i| x[i] ^ y[i]);
+
+    let mut z = r;
+    for row in 0..8 {
+        permute(&mut z, std::array::from_fn(|i| 16 * row + i));
+    }
+    for column in 0..8 {
+        permute(
+            &mut z,
+            std::array::from_fn(|i| 16 * (i / 2) + 2 * column + i % 2),
+        );
+    }
+
+    z.iter_mut().zip(r).for_each(|(z, r)| *z ^= r);
+    z
+}
+
+/// Permutation `P` of the 16 words of `block` at `indices`
+fn permute(block: &mut Block, indices: [usize; 16]) {
+    let mut v: [u64; 16] = indices.map(|i| block[i]);
+
+    mix(&mut v, [0, 4, 8, 12]);
+    mix(&mut v, [1, 5, 9, 13]);
+    mix(&mut v, [2, 6, 10, 14]);
+    mix(&mut v, [3, 7, 11, 15]);
+    mix(&mut v, [0, 5, 10, 15]);
+    mix(&mut v, [1, 6, 11, 12]);
+    mix(&mut v, [2, 7, 8, 13]);
+    mix(&mut v, [3, 4, 9, 14]);
+
+    for (i, v) in indices.into_iter().zip(v) {
+        block[i] = v;
+    }
+}
+
+/// BLAKE2b mixing function with multiplications instead of message words (`GB`)
+fn mix(v: &mut [u64; 16], [a, b, c, d]: [usize; 4]) {
+    let multiply_add = |x: u64, y: u64| {
+        let product = (x as u32 as u64) * (y as u32 as u64);
+        x.wrapping_add(y).wrapping_add(product.wrapping_mul(2))
+    };
+
+    v[a] = multiply_add(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = multiply_add(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = multiply_add(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = multiply_add(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// Variable-length hash function `H'`
+fn hash_long(bytes: &[u8], out_len: usize) -> Vec<u8> {
+    let bytes = [&(out_len as u32).to_le_bytes(), bytes].concat();
+    if out_len <= 64 {
+        return blake2b(&bytes, out_len);
+    }
+
+    // the first 32 bytes of a chain of 64 byte digests, followed by a shorter last digest
+    let chained = out_len.div_ceil(32) - 2;
+    let mut out = Vec::with_capacity(out_len);
+    let mut v = blake2b(&bytes, 64);
+    for _ in 1..chained {
+        out.extend_from_slice(&v[..32]);
+        v = blake2b(&v, 64);
+    }
+    out.extend_from_slice(&v[..32]);
+    out.extend(blake2b(&v, out_len - 32 * chained));
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex;
+
+    #[test]
+    fn argon2id_rfc9106() {
+        // see https://www.rfc-editor.org/rfc/rfc9106#section-5.3
+        let cost = Cost {
+            memory: 32,
+            iterations: 3,
+            parallelism: 4,
+        };
+
+        let mut tag = [0; 32];
+        argon2id(&[1; 32], &[2; 16], &[3; 8], &[4; 12], cost, &mut tag);
+
+        assert_eq!(
+            tag.to_vec(),
+            hex::decode("0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn argon2id_minimal_cost() {
+        let key: [u8; 32] = argon2id_key(b"I use Rust btw", b"NaCl and pepper", 8, 1, 1);
+        assert_eq!(
+            key.to_vec(),
+            hex::decode("2b379a7e1a17b1d9366859f5c605a1afd45a94f9d2b6f074a3234e26c2ba0f58")
+                .unwrap()
+        );
+        assert_eq!(
+            argon2id_key::<32>(b"I use Rust btw", b"NaCl and pepper", 8, 1, 1),
+            key
+        );
+    }
+
+    #[test]
+    fn argon2id_long_segments() {
+        // more than one block of addresses per segment
+        let key: [u8; 24] = argon2id_key(b"I use Rust btw", b"NaCl and pepper", 2048, 2, 1);
+        assert_eq!(
+            key.to_vec(),
+            hex::decode("08dd94a6baf92d4082a71d21e468f7ad0e781a2e690b9300").unwrap()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 8 KiB of memory per lane")]
+    fn argon2id_too_little_memory() {
+        argon2id_key::<32>(b"I use Rust btw", b"NaCl and pepper", 31, 1, 4);
+    }
+}
@@ -0,0 +1,235 @@
+//! Key derivation module
+//!
+//! This module provides functions to derive keys from other secrets.
+//!
+//! - [HKDF](hkdf) splits high-entropy input keying material (e.g. a shared secret)
+//!   into several independent keys. It is fast and therefore not suited for passwords.
+//! - [PBKDF2](pbkdf2_key) turns a passphrase into a key.
+//!   It is deliberately slow to make guessing the passphrase expensive.
+//! - [scrypt](scrypt_key) turns a passphrase into a key, too,
+//!   but also needs a lot of memory, which makes guessing on GPUs or custom hardware expensive.
+//! - [Argon2id](argon2id_key) is memory-hard like scrypt, with separate memory and time costs.
+//! - [HMAC-SHA256](hmac_sha256) is the primitive HKDF and PBKDF2 are built on.
+
+use crate::sha256::sha256;
+
+mod argon2;
+mod scrypt;
+
+pub use argon2::argon2id_key;
+pub use scrypt::scrypt_key;
+
+/// Block size of SHA-256 (in bytes)
+const BLOCK_SIZE: usize = 64;
+
+/// Output size of SHA-256 (in bytes)
+const HASH_SIZE: usize = 32;
+
+/// Compute the HMAC-SHA256 of a message
+///
+/// For reference, see [RFC 2104](https://www.rfc-editor.org/rfc/rfc2104).
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; HASH_SIZE] {
+    let mut block_key = [0; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block_key[..HASH_SIZE].copy_from_slice(&sha256(key));
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+
+    let inner: Vec<u8> = block_key
+        .iter()
+        .map(|k| k ^ 0x36)
+        .chain(message.iter().copied())
+        .collect();
+
+    let outer: Vec<u8> = block_key
+        .iter()
+        .map(|k| k ^ 0x5c)
+        .chain(sha256(&inner))
+        .collect();
+
+    sha256(&outer)
+}
+
+/// Derive `out_len` bytes of keying material using HKDF-SHA256
+///
+/// For reference, see [RFC 5869](https://www.rfc-editor.org/rfc/rfc5869).
+///
+/// # Parameters
+/// - `ikm`: input keying material
+/// - `salt`: optional non-secret random value (an empty salt is the same as 32 zero bytes)
+/// - `info`: context that separates keys derived from the same `ikm` (e.g. `b"enc"` and `b"mac"`)
+/// - `out_len`: number of bytes to derive
+///
+/// # Panics
+/// Panics if `out_len` is greater than `255 * 32`.
+pub fn hkdf(ikm: &[u8], salt: &[u8], info: &[u8], out_len: usize) -> Vec<u8> {
+    log::trace!("Derive {out_len} bytes using HKDF");
+
+    assert!(
+        out_len <= 255 * HASH_SIZE,
+        "HKDF can derive at most {} bytes",
+        255 * HASH_SIZE
+    );
+
+    // extract
+    let prk = hmac_sha256(salt, ikm);
+
+    // expand
+    let mut okm = Vec::with_capacity(out_len);
+    let mut t = Vec::new();
+    for i in 1..=out_len.div_ceil(HASH_SIZE) {
+        t = hmac_sha256(&prk, &[&t, info, &[i as u8]].concat()).to_vec();
+        okm.extend_from_slice(&t);
+    }
+    okm.truncate(out_len);
+
+    okm
+}
+
+/// Derive an `N` byte key from a passphrase using PBKDF2-HMAC-SHA256
+///
+/// For reference, see [RFC 8018](https://www.rfc-editor.org/rfc/rfc8018#section-5.2).
+///
+/// # Parameters
+/// - `passphrase`: the secret passphrase
+/// - `salt`: random value that is stored with the ciphertext, at least 16 bytes are recommended
+/// - `iterations`: work factor, the higher the slower the guessing
+///   ([OWASP](https://cheatsheetseries.owasp.org/cheatsheets/Password_Storage_Cheat_Sheet.html#pbkdf2) recommends 600000)
+///
+/// # Panics
+/// Panics if `iterations` is zero.
+pub fn pbkdf2_key<const N: usize>(passphrase: &[u8], salt: &[u8], iterations: u32) -> [u8; N] {
+    log::trace!("Derive {N} bytes using PBKDF2 with {iterations} iterations");
+
+    assert!(iterations > 0, "PBKDF2 needs at least one iteration");
+
+    let mut key = [0; N];
+    pbkdf2(passphrase, salt, iterations, &mut key);
+
+    key
+}
+
+/// Fill `out` using PBKDF2-HMAC-SHA256
+fn pbkdf2(passphrase: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]) {
+    for (i, chunk) in out.chunks_mut(HASH_SIZE).enumerate() {
+        let index = (i as u32 + 1).to_be_bytes();
+
+        let mut u = hmac_sha256(passphrase, &[salt, &index].concat());
+        let mut t = u;
+        for _ in 1..iterations {
+            u = hmac_sha256(passphrase, &u);
+            t.iter_mut().zip(u).for_each(|(t, u)| *t ^= u);
+        }
+
+        chunk.copy_from_slice(&t[..chunk.len()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex;
+
+    #[test]
+    fn hmac_rfc4231() {
+        // see https://www.rfc-editor.org/rfc/rfc4231#section-4 (test cases 1, 2 and 6)
+        let vectors: [(&[u8], &[u8], &str); 3] = [
+            (
+                &[0x0b; 20],
+                b"Hi There",
+                "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            ),
+            (
+                b"Jefe",
+                b"what do ya want for nothing?",
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First",
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            ),
+        ];
+
+        for (key, message, expected) in vectors {
+            assert_eq!(
+                hmac_sha256(key, message).to_vec(),
+                hex::decode(expected).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn hkdf_rfc5869() {
+        // see https://www.rfc-editor.org/rfc/rfc5869#appendix-A (test cases 1 to 3)
+        let salt: Vec<u8> = (0x60..=0xaf).collect();
+        let info: Vec<u8> = (0xb0..=0xff).collect();
+
+        let vectors = [
+            (
+                vec![0x0b; 22],
+                hex::decode("000102030405060708090a0b0c").unwrap(),
+                hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap(),
+                "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865",
+            ),
+            (
+                (0x00..=0x4f).collect::<Vec<u8>>(),
+                salt,
+                info,
+                "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71cc30c58179ec3e87c14c01d5c1f3434f1d87",
+            ),
+            (
+                vec![0x0b; 22],
+                vec![],
+                vec![],
+                "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8",
+            ),
+        ];
+
+        for (ikm, salt, info, expected) in vectors {
+            let expected = hex::decode(expected).unwrap();
+            assert_eq!(hkdf(&ikm, &salt, &info, expected.len()), expected);
+        }
+    }
+
+    #[test]
+    fn pbkdf2_known_answers() {
+        assert_eq!(
+            pbkdf2_key::<32>(b"password", b"salt", 1).to_vec(),
+            hex::decode("120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b")
+                .unwrap()
+        );
+        assert_eq!(
+            pbkdf2_key::<32>(b"password", b"salt", 4096).to_vec(),
+            hex::decode("c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a")
+                .unwrap()
+        );
+
+        // see https://www.rfc-editor.org/rfc/rfc7914#section-11
+        assert_eq!(
+            pbkdf2_key::<64>(b"passwd", b"salt", 1).to_vec(),
+            hex::decode("55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783").unwrap()
+        );
+
+        // output length that is not a multiple of the hash size
+        assert_eq!(
+            pbkdf2_key::<40>(
+                b"passwordPASSWORDpassword",
+                b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+                4096
+            )
+            .to_vec(),
+            hex::decode(
+                "348c89dbcbd32b2f32d814b8116e84cf2b17347ebc1800181c4e2a1fb8dd53e1c635518c7dac47e9"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "at most 8160 bytes")]
+    fn hkdf_too_long() {
+        hkdf(b"secret", b"", b"", 255 * 32 + 1);
+    }
+}
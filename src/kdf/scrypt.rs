@@ -0,0 +1,188 @@
+//! scrypt key derivation
+//!
+//! For reference, see [RFC 7914](https://www.rfc-editor.org/rfc/rfc7914).
+
+use super::pbkdf2;
+
+/// Derive an `N` byte key from a passphrase using scrypt
+///
+/// Memory and time grow with `128 * r * cost` bytes per lane.
+/// Common parameters are `cost = 2^17`, `r = 8` and `p = 1` (128 MiB).
+///
+/// # Parameters
+/// - `passphrase`: the secret passphrase
+/// - `salt`: random value that is stored with the ciphertext, at least 16 bytes are recommended
+/// - `cost`: CPU/memory cost parameter `N`, a power of two greater than `1`
+/// - `r`: block size parameter
+/// - `p`: parallelization parameter (the lanes are computed one after the other)
+///
+/// # Panics
+/// Panics if `cost` is not a power of two greater than `1`, if `r` or `p` is zero,
+/// if `p * r` is not less than `2^30` (see RFC 7914) or if the memory of `128 * r * cost` bytes can't be addressed.
+pub fn scrypt_key<const N: usize>(
+    passphrase: &[u8],
+    salt: &[u8],
+    cost: usize,
+    r: usize,
+    p: usize,
+) -> [u8; N] {
+    log::trace!("Derive {N} bytes using scrypt (N = {cost}, r = {r}, p = {p})");
+
+    assert!(
+        cost > 1 && cost.is_power_of_two(),
+        "scrypt cost must be a power of two greater than 1"
+    );
+    assert!(r > 0 && p > 0, "scrypt parameters r and p must not be zero");
+    assert!(
+        p.checked_mul(r).is_some_and(|pr| pr < 1 << 30),
+        "scrypt parameters must satisfy p * r < 2^30"
+    );
+
+    let lane_size = r.checked_mul(128).expect("scrypt parameter r is too large");
+    let (Some(_), Some(len)) = (cost.checked_mul(lane_size), p.checked_mul(lane_size)) else {
+        panic!("scrypt parameters need more memory than can be addressed");
+    };
+
+    let mut lanes = vec![0; len];
+    pbkdf2(passphrase, salt, 1, &mut lanes);
+
+    for lane in lanes.chunks_exact_mut(lane_size) {
+        ro_mix(lane, cost);
+    }
+
+    let mut key = [0; N];
+    pbkdf2(passphrase, &lanes, 1, &mut key);
+
+    key
+}
+
+/// Sequential memory-hard mixing of one lane (`scryptROMix`)
+fn ro_mix(lane: &mut [u8], cost: usize) {
+    let mut x: Vec<u32> = lane
+        .chunks_exact(4)
+        .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+        .collect();
+    let len = x.len();
+
+    let mut v = Vec::with_capacity(cost * len);
+    for _ in 0..cost {
+        v.extend_from_slice(&x);
+        block_mix(&mut x);
+    }
+
+    for _ in 0..cost {
+        // integerify: the first 64 bits of the last 64 byte block
+        let j = (x[len - 16] as u64 | (x[len - 15] as u64) << 32) as usize & (cost - 1);
+
+        x.iter_mut()
+            .zip(&v[j * len..(j + 1) * len])
+            .for_each(|(x, v)| *x ^= v);
+        block_mix(&mut x);
+    }
+
+    for (bytes, word) in lane.chunks_exact_mut(4).zip(x) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+}
+
+/// Mix `2 * r` blocks of 64 bytes with Salsa20/8 (`scryptBlockMix`)
+fn block_mix(blocks: &mut [u32]) {
+    let count = blocks.len() / 16;
+
+    let mut x: [u32; 16] = blocks[blocks.len() - 16..].try_into().unwrap();
+    let mut y = vec![0; blocks.len()];
+
+    for (i, block) in blocks.chunks_exact(16).enumerate() {
+        x.iter_mut().zip(block).for_each(|(x, b)| *x ^= b);
+        salsa20_8(&mut x);
+
+        // even blocks go to the first half, odd blocks to the second half
+        let dst = (i % 2) * (count / 2) + i / 2;
+        y[dst * 16..(dst + 1) * 16].copy_from_slice(&x);
+    }
+
+    blocks.copy_from_slice(&y);
+}
+
+/// Salsa20 core with 8 rounds
+fn salsa20_8(block: &mut [u32; 16]) {
+    let mut x = *block;
+
+    for _ in 0..4 {
+        // columns
+        quarter_round(&mut x, 0, 4, 8, 12);
+        quarter_round(&mut x, 5, 9, 13, 1);
+        quarter_round(&mut x, 10, 14, 2, 6);
+        quarter_round(&mut x, 15, 3, 7, 11);
+
+        // rows
+        quarter_round(&mut x, 0, 1, 2, 3);
+        quarter_round(&mut x, 5, 6, 7, 4);
+        quarter_round(&mut x, 10, 11, 8, 9);
+        quarter_round(&mut x, 15, 12, 13, 14);
+    }
+
+    block
+        .iter_mut()
+        .zip(x)
+        .for_each(|(b, x)| *b = b.wrapping_add(x));
+}
+
+fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+    x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+    x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+    x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex;
+
+    #[test]
+    fn salsa20_8_rfc7914() {
+        // see https://www.rfc-editor.org/rfc/rfc7914#section-8
+        let input = hex::decode("7e879a214f3ec9867ca940e641718f26baee555b8c61c1b50df846116dcd3b1dee24f319df9b3d8514121e4b5ac5aa3276021d2909c74829edebc68db8b8c25e").unwrap();
+        let expected = hex::decode("a41f859c6608cc993b81cacb020cef05044b2181a2fd337dfd7b1c6396682f29b4393168e3c9e6bcfe6bc5b7a06d96bae424cc102c91745c24ad673dc7618f81").unwrap();
+
+        let mut block: [u32; 16] = std::array::from_fn(|i| {
+            u32::from_le_bytes(input[i * 4..i * 4 + 4].try_into().unwrap())
+        });
+        salsa20_8(&mut block);
+
+        let output: Vec<u8> = block.iter().flat_map(|w| w.to_le_bytes()).collect();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn scrypt_rfc7914() {
+        // see https://www.rfc-editor.org/rfc/rfc7914#section-12 (first vector)
+        assert_eq!(
+            scrypt_key::<64>(b"", b"", 16, 1, 1).to_vec(),
+            hex::decode("77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906").unwrap()
+        );
+    }
+
+    #[test]
+    fn scrypt_small_parameters() {
+        // several lanes and blocks, computed with Python's hashlib.scrypt
+        assert_eq!(
+            scrypt_key::<32>(b"I use Rust btw", b"NaCl", 16, 2, 3).to_vec(),
+            hex::decode("31adc21291994e329ae97edd35d56cc015ba80be1cc066c876922c406326387b")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "p * r < 2^30")]
+    fn scrypt_parameters_too_large() {
+        scrypt_key::<16>(b"password", b"NaCl", 16, 1 << 15, 1 << 15);
+    }
+
+    #[test]
+    #[should_panic(expected = "power of two")]
+    fn scrypt_cost_not_power_of_two() {
+        scrypt_key::<16>(b"password", b"NaCl", 1000, 8, 1);
+    }
+}
@@ -1,7 +1,9 @@
 //! AES keys
 
+use crate::util;
+
 use super::GenericKey;
-use super::Key;
+use super::RoundKeyProvider;
 use super::{Subkey, Word};
 
 /// A Rijndael key consisting of 128 bits (16 bytes)
@@ -13,19 +15,19 @@ pub type AES192Key = GenericKey<6, 13>;
 /// A Rijndael key consisting of 256 bits (32 bytes)
 pub type AES256Key = GenericKey<8, 15>;
 
-impl Key<11> for AES128Key {
+impl RoundKeyProvider<11> for AES128Key {
     fn round_keys(&self) -> [Subkey; 11] {
         self.generate_round_keys()
     }
 }
 
-impl Key<13> for AES192Key {
+impl RoundKeyProvider<13> for AES192Key {
     fn round_keys(&self) -> [Subkey; 13] {
         self.generate_round_keys()
     }
 }
 
-impl Key<15> for AES256Key {
+impl RoundKeyProvider<15> for AES256Key {
     fn round_keys(&self) -> [Subkey; 15] {
         self.generate_round_keys()
     }
@@ -33,48 +35,25 @@ impl Key<15> for AES256Key {
 
 impl AES128Key {
     pub fn from_bytes(bytes: [u8; 16]) -> Self {
-        let key_as_words: Vec<Word> = bytes
-            .chunks_exact(4)
-            .map(|c| {
-                c.iter()
-                    .enumerate()
-                    .map(|(i, &x)| (x as Word) << ((3 - i) * 8))
-                    .sum()
-            })
-            .collect();
-
-        Self(key_as_words.try_into().unwrap())
+        Self(bytes_as_words(bytes))
     }
 }
 
 impl AES192Key {
     pub fn from_bytes(bytes: [u8; 24]) -> Self {
-        let key_as_words: Vec<Word> = bytes
-            .chunks_exact(4)
-            .map(|c| {
-                c.iter()
-                    .enumerate()
-                    .map(|(i, &x)| (x as Word) << ((3 - i) * 8))
-                    .sum()
-            })
-            .collect();
-
-        Self(key_as_words.try_into().unwrap())
+        Self(bytes_as_words(bytes))
     }
 }
 
 impl AES256Key {
     pub fn from_bytes(bytes: [u8; 32]) -> Self {
-        let key_as_words: Vec<Word> = bytes
-            .chunks_exact(4)
-            .map(|c| {
-                c.iter()
-                    .enumerate()
-                    .map(|(i, &x)| (x as Word) << ((3 - i) * 8))
-                    .sum()
-            })
-            .collect();
-
-        Self(key_as_words.try_into().unwrap())
+        Self(bytes_as_words(bytes))
     }
 }
+
+/// Pack the key bytes into big-endian words
+fn bytes_as_words<const B: usize, const N: usize>(bytes: [u8; B]) -> [Word; N] {
+    debug_assert_eq!(B, N * 4);
+
+    std::array::from_fn(|i| util::bytes_as_u32(bytes[i * 4..i * 4 + 4].try_into().unwrap()))
+}
@@ -0,0 +1,188 @@
+//! AES key of any size
+
+use crate::decryption::{decrypt_bytes, DecryptError};
+use crate::encryption::encrypt_bytes;
+use crate::padding::Padding;
+use crate::EncryptionMode;
+
+use super::{AES128Key, AES192Key, AES256Key, Key};
+
+/// An AES key whose size is only known at runtime
+///
+/// The [Key](super::Key) trait is generic over the number of rounds,
+/// so code that gets the key size at runtime (e.g. from a key file) would have to match on it everywhere.
+/// [AnyKey] does this once and dispatches to the right key type.
+#[derive(Debug)]
+pub enum AnyKey {
+    Aes128(AES128Key),
+    Aes192(AES192Key),
+    Aes256(AES256Key),
+}
+
+/// An operation that is generic over the [Key] type, see [AnyKey::visit]
+///
+/// Closures can't be generic, so an operation that needs the concrete key type
+/// is a type that implements this trait instead.
+pub trait KeyVisitor {
+    type Output;
+
+    /// Run the operation with the concrete key
+    fn visit<const R: usize, K>(self, key: &K) -> Self::Output
+    where
+        K: Key<R>;
+}
+
+impl AnyKey {
+    /// Constructor that takes 16, 24 or 32 bytes
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, &'static str> {
+        log::trace!("Create AES key from {} bytes", bytes.len());
+
+        match bytes.len() {
+            16 => Ok(Self::Aes128(AES128Key::from_bytes(
+                bytes.try_into().unwrap(),
+            ))),
+            24 => Ok(Self::Aes192(AES192Key::from_bytes(
+                bytes.try_into().unwrap(),
+            ))),
+            32 => Ok(Self::Aes256(AES256Key::from_bytes(
+                bytes.try_into().unwrap(),
+            ))),
+            _ => Err("Key must have a size of 128, 192 or 256 bits (16, 24 or 32 bytes)"),
+        }
+    }
+
+    /// Size of the key in bits
+    pub fn bits(&self) -> usize {
+        match self {
+            Self::Aes128(_) => 128,
+            Self::Aes192(_) => 192,
+            Self::Aes256(_) => 256,
+        }
+    }
+
+    /// Run a [KeyVisitor] with the key this holds
+    pub fn visit<V>(&self, visitor: V) -> V::Output
+    where
+        V: KeyVisitor,
+    {
+        match self {
+            Self::Aes128(key) => visitor.visit(key),
+            Self::Aes192(key) => visitor.visit(key),
+            Self::Aes256(key) => visitor.visit(key),
+        }
+    }
+
+    /// Encrypt a byte slice, see [encrypt_bytes]
+    pub fn encrypt_bytes<P>(&self, bytes: &[u8], padding: &P, mode: EncryptionMode) -> Vec<u8>
+    where
+        P: Padding<16>,
+    {
+        match self {
+            Self::Aes128(key) => encrypt_bytes(bytes, key, padding, mode),
+            Self::Aes192(key) => encrypt_bytes(bytes, key, padding, mode),
+            Self::Aes256(key) => encrypt_bytes(bytes, key, padding, mode),
+        }
+    }
+
+    /// Decrypt a byte slice, see [decrypt_bytes]
+    pub fn decrypt_bytes<P>(
+        &self,
+        bytes: &[u8],
+        padding: Option<P>,
+        mode: EncryptionMode,
+    ) -> Result<Vec<u8>, DecryptError>
+    where
+        P: Padding<16>,
+    {
+        match self {
+            Self::Aes128(key) => decrypt_bytes(bytes, key, padding, mode),
+            Self::Aes192(key) => decrypt_bytes(bytes, key, padding, mode),
+            Self::Aes256(key) => decrypt_bytes(bytes, key, padding, mode),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::padding::Pkcs7Padding;
+    use crate::InitializationVector;
+
+    #[test]
+    fn from_slice() {
+        let bytes: Vec<u8> = (0..32).collect();
+        let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+        let mode = EncryptionMode::CBC(iv);
+        let plaintext = b"felis eget nunc lobortis mattis aliquam";
+
+        let key = AnyKey::from_slice(&bytes[..16]).unwrap();
+        assert_eq!(key.bits(), 128);
+        assert_eq!(
+            key.encrypt_bytes(plaintext, &Pkcs7Padding, mode),
+            encrypt_bytes(
+                plaintext,
+                &AES128Key::from_bytes(bytes[..16].try_into().unwrap()),
+                &Pkcs7Padding,
+                mode
+            )
+        );
+
+        let key = AnyKey::from_slice(&bytes[..24]).unwrap();
+        assert_eq!(key.bits(), 192);
+        assert_eq!(
+            key.encrypt_bytes(plaintext, &Pkcs7Padding, mode),
+            encrypt_bytes(
+                plaintext,
+                &AES192Key::from_bytes(bytes[..24].try_into().unwrap()),
+                &Pkcs7Padding,
+                mode
+            )
+        );
+
+        let key = AnyKey::from_slice(&bytes).unwrap();
+        assert_eq!(key.bits(), 256);
+        let encrypted = key.encrypt_bytes(plaintext, &Pkcs7Padding, mode);
+        assert_eq!(
+            encrypted,
+            encrypt_bytes(
+                plaintext,
+                &AES256Key::from_bytes(bytes[..].try_into().unwrap()),
+                &Pkcs7Padding,
+                mode
+            )
+        );
+        assert_eq!(
+            key.decrypt_bytes(&encrypted, Some(Pkcs7Padding), mode)
+                .unwrap(),
+            plaintext
+        );
+    }
+
+    #[test]
+    fn visit() {
+        struct Rounds;
+
+        impl KeyVisitor for Rounds {
+            type Output = usize;
+
+            fn visit<const R: usize, K>(self, _: &K) -> usize
+            where
+                K: Key<R>,
+            {
+                R
+            }
+        }
+
+        let bytes: Vec<u8> = (0..32).collect();
+        assert_eq!(AnyKey::from_slice(&bytes[..16]).unwrap().visit(Rounds), 11);
+        assert_eq!(AnyKey::from_slice(&bytes[..24]).unwrap().visit(Rounds), 13);
+        assert_eq!(AnyKey::from_slice(&bytes).unwrap().visit(Rounds), 15);
+    }
+
+    #[test]
+    fn from_slice_wrong_length() {
+        for len in [0, 15, 17, 31, 33] {
+            assert!(AnyKey::from_slice(&vec![0; len]).is_err());
+        }
+    }
+}
@@ -2,7 +2,10 @@
 //!
 //! For reference, see the [Wikipedia article](https://en.wikipedia.org/wiki/AES_key_schedule).
 
-use crate::lookups::sbox::*;
+use std::fmt;
+
+use crate::base64;
+use crate::hex::{self, HexError};
 use crate::util;
 
 use super::{Subkey, Word};
@@ -12,6 +15,43 @@ const RCON: [u8; 11] = [
     0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36,
 ];
 
+/// Errors of [GenericKey::from_hex] and [GenericKey::from_base64]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum KeyParseError {
+    /// The hex digits are malformed
+    Hex(HexError),
+
+    /// The Base64 is malformed
+    Base64(&'static str),
+
+    /// The decoded key has the wrong number of bytes
+    WrongLength,
+}
+
+impl fmt::Display for KeyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyParseError::Hex(HexError::InvalidDigit) => {
+                write!(f, "Key must consist of hex digits only")
+            }
+            KeyParseError::Hex(HexError::OddLength) => {
+                write!(f, "Key must consist of an even number of hex digits")
+            }
+            KeyParseError::Base64(err) => write!(f, "{err}"),
+            KeyParseError::WrongLength => write!(f, "Key has the wrong number of bytes"),
+        }
+    }
+}
+
+impl std::error::Error for KeyParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KeyParseError::Hex(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
 /// A generic Rijndael key type with variable size and round number
 #[derive(Debug)]
 pub struct GenericKey<const N: usize, const R: usize>(pub(super) [Word; N]);
@@ -22,6 +62,32 @@ impl<const N: usize, const R: usize> GenericKey<N, R> {
         Self(original_key)
     }
 
+    /// Parse a key from `8 * N` hex digits
+    ///
+    /// This also works for the AES keys, e.g. `AES128Key::from_hex` takes 32 hex digits.
+    pub fn from_hex(s: &str) -> Result<Self, KeyParseError> {
+        let bytes = hex::decode(s).map_err(KeyParseError::Hex)?;
+        if bytes.len() != N * 4 {
+            return Err(KeyParseError::WrongLength);
+        }
+
+        Ok(Self(std::array::from_fn(|i| {
+            Word::from_be_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap())
+        })))
+    }
+
+    /// Parse a key from Base64 that decodes to `4 * N` bytes
+    pub fn from_base64(s: &str) -> Result<Self, KeyParseError> {
+        let bytes = base64::decode(s).map_err(KeyParseError::Base64)?;
+        if bytes.len() != N * 4 {
+            return Err(KeyParseError::WrongLength);
+        }
+
+        Ok(Self(std::array::from_fn(|i| {
+            Word::from_be_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap())
+        })))
+    }
+
     /// AES key schedule
     ///
     /// For reference, see the [Wikipedia article](https://en.wikipedia.org/wiki/AES_key_schedule).
@@ -37,7 +103,7 @@ impl<const N: usize, const R: usize> GenericKey<N, R> {
             let prev = words[i - 1];
 
             if i % N == 0 {
-                let prev = util::apply_sbox(util::rot_left(prev.to_be_bytes(), 1), SBOX);
+                let prev = util::sub_bytes(util::rot_left(util::u32_as_bytes(prev), 1));
                 let expanded_word = prev_round
                     ^ util::bytes_as_u32(prev)
                     ^ util::bytes_as_u32([RCON[i / N], 0, 0, 0]);
@@ -47,7 +113,7 @@ impl<const N: usize, const R: usize> GenericKey<N, R> {
             }
 
             if N > 6 && i % N == 4 {
-                let prev = util::apply_sbox(prev.to_be_bytes(), SBOX);
+                let prev = util::sub_bytes(util::u32_as_bytes(prev));
                 let expanded_word = prev_round ^ util::bytes_as_u32(prev);
 
                 words.push(expanded_word);
@@ -77,3 +143,80 @@ impl<const N: usize, const R: usize> GenericKey<N, R> {
         round_keys.try_into().unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::KeyParseError;
+    use crate::hex::HexError;
+    use crate::key::{AES128Key, AES192Key, AES256Key};
+
+    #[test]
+    fn from_hex() {
+        let key = AES128Key::from_hex("000102030405060708090a0B0c0D0e0F").unwrap();
+        assert_eq!(
+            key.0,
+            AES128Key::from_bytes(std::array::from_fn(|i| i as u8)).0
+        );
+
+        let key = AES192Key::from_hex(&"69".repeat(24)).unwrap();
+        assert_eq!(key.0, [0x69696969; 6]);
+
+        let key = AES256Key::from_hex(&"ff".repeat(32)).unwrap();
+        assert_eq!(key.0, [u32::MAX; 8]);
+    }
+
+    #[test]
+    fn from_base64() {
+        let key = AES128Key::from_base64("MDEyMzQ1Njc4OWFiY2RlZg==").unwrap();
+        assert_eq!(key.0, AES128Key::from_bytes(*b"0123456789abcdef").0);
+
+        let key = AES256Key::from_base64("AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=").unwrap();
+        assert_eq!(
+            key.0,
+            AES256Key::from_bytes(std::array::from_fn(|i| i as u8)).0
+        );
+
+        assert_eq!(
+            AES192Key::from_base64("MDEyMzQ1Njc4OWFiY2RlZg==").unwrap_err(),
+            KeyParseError::WrongLength
+        );
+        for base64 in ["MDEyMzQ1Njc4OWFiY2RlZg", "MDEyMzQ1Njc4OWFiY2RlZg=!"] {
+            assert!(matches!(
+                AES128Key::from_base64(base64),
+                Err(KeyParseError::Base64(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn from_hex_wrong_length() {
+        for hex in ["", "0001020304050607", &"00".repeat(24), &"00".repeat(17)] {
+            assert_eq!(
+                AES128Key::from_hex(hex).unwrap_err(),
+                KeyParseError::WrongLength
+            );
+        }
+
+        let err = AES128Key::from_hex(&"0".repeat(31)).unwrap_err();
+        assert_eq!(err, KeyParseError::Hex(HexError::OddLength));
+        assert_eq!(
+            err.to_string(),
+            "Key must consist of an even number of hex digits"
+        );
+    }
+
+    #[test]
+    fn from_hex_invalid_digits() {
+        for hex in [
+            "000102030405060708090a0b0c0d0e0g",
+            "+00102030405060708090a0b0c0d0e0f",
+            "00010203040506070809äb0c0d0e0f",
+            " 000102030405060708090a0b0c0d0e0f",
+        ] {
+            assert_eq!(
+                AES128Key::from_hex(hex).unwrap_err(),
+                KeyParseError::Hex(HexError::InvalidDigit)
+            );
+        }
+    }
+}
@@ -2,21 +2,54 @@
 //!
 //! This module provides a [Key] trait, a [generic Rijndael key](GenericKey),
 //! and the three AES keys ([128](AES128Key), [192](AES192Key), [256](AES256Key)) that implement the trait.
+//! [AnyKey] holds one of them if the key size is only known at runtime,
+//! a [KeyVisitor] runs generic code on the key it holds.
+//!
+//! Round keys that come from somewhere else (e.g. a hardware security module)
+//! can be used by implementing [RoundKeyProvider].
+//!
+//! [key_strength] estimates how hard a key of a given size is to brute-force.
+
+use crate::block::Block;
 
 mod aes;
+mod any;
 mod generic;
+mod strength;
 
-pub use generic::GenericKey;
+pub use generic::{GenericKey, KeyParseError};
+
+pub use strength::{key_strength, KeyStrengthInfo};
 
 pub use aes::{AES128Key, AES192Key, AES256Key};
 
-/// A key that can be used in for AES encryption/decryption
-pub trait Key<const R: usize> {
+pub use any::{AnyKey, KeyVisitor};
+
+/// A source of the round keys for `R` rounds
+///
+/// [GenericKey] computes them with the software key schedule.
+/// Every provider is a [Key] and can be passed to the encryption and decryption functions.
+pub trait RoundKeyProvider<const R: usize> {
+    /// Get the subkey of every round
     fn round_keys(&self) -> [Subkey; R];
+
+    /// Get the subkey of every round as [Block]
+    ///
+    /// This way a round key can be xored onto the state directly.
+    fn round_key_blocks(&self) -> [Block; R] {
+        self.round_keys().map(Block::from)
+    }
 }
 
+/// A key that can be used in for AES encryption/decryption
+pub trait Key<const R: usize>: RoundKeyProvider<R> {}
+
+impl<const R: usize, T> Key<R> for T where T: RoundKeyProvider<R> {}
+
 type Word = u32;
-type Subkey = u128;
+
+/// A round key
+pub type Subkey = u128;
 
 #[cfg(test)]
 mod tests {
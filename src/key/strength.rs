@@ -0,0 +1,77 @@
+//! Key strength
+
+/// Seconds of a (Julian) year
+const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+
+/// Size of the key space and a rough assessment of a key size
+///
+/// This is only a back-of-the-envelope estimation of a brute-force attack,
+/// it says nothing about weaknesses of the algorithm or the way the key was generated.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct KeyStrengthInfo {
+    /// Size of the key in bits
+    pub key_bits: usize,
+
+    /// The key space consists of `2^key_space_log2` keys
+    pub key_space_log2: usize,
+
+    /// Qualitative assessment of the key size
+    pub note: &'static str,
+}
+
+impl KeyStrengthInfo {
+    /// Expected number of years to find the key by trying `keys_per_second` keys per second
+    ///
+    /// On average, half of the key space has to be searched.
+    pub fn brute_force_years(&self, keys_per_second: f64) -> f64 {
+        2f64.powi(self.key_space_log2 as i32 - 1) / keys_per_second / SECONDS_PER_YEAR
+    }
+}
+
+/// Report the key space and strength of a key with the given size
+///
+/// For reference, see [NIST SP 800-57 Part 1, Table 2](https://csrc.nist.gov/publications/detail/sp/800-57-part-1/rev-5/final).
+pub fn key_strength(key_bits: usize) -> KeyStrengthInfo {
+    let note = match key_bits {
+        0..=79 => "Insecure: within reach of a brute-force search",
+        80..=111 => "Legacy: no longer considered secure",
+        112..=127 => "Acceptable: secure against brute force for now",
+        128..=191 => "Strong: secure against brute force with classical computers",
+        192..=255 => "Very strong: large security margin",
+        _ => "Very strong: secure against brute force even with a quantum computer",
+    };
+
+    KeyStrengthInfo {
+        key_bits,
+        key_space_log2: key_bits,
+        note,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aes_key_spaces() {
+        for bits in [128, 192, 256] {
+            let info = key_strength(bits);
+            assert_eq!(info.key_bits, bits);
+            assert_eq!(info.key_space_log2, bits);
+        }
+
+        assert_ne!(key_strength(128).note, key_strength(256).note);
+        assert!(key_strength(56).note.starts_with("Insecure"));
+    }
+
+    #[test]
+    fn brute_force_years() {
+        // half of 2^56 DES keys at 2^55 keys per second takes one second
+        let years = key_strength(56).brute_force_years(2f64.powi(55));
+        assert!((years * SECONDS_PER_YEAR - 1.0).abs() < 1e-9);
+
+        // AES-128 at a trillion keys per second
+        let years = key_strength(128).brute_force_years(1e12);
+        assert!(years > 5e18 && years < 6e18);
+    }
+}
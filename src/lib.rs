@@ -1,15 +1,70 @@
+pub mod aead;
+pub mod base64;
 pub mod block;
+pub mod cipher;
+pub mod ciphertext;
+pub mod convergent;
 pub mod decryption;
 pub mod encryption;
+pub mod etm;
+pub mod hex;
+#[cfg(feature = "openssl-compat")]
+pub mod interop;
+pub mod kdf;
 pub mod key;
 pub mod lookups;
+pub mod mac;
+pub mod multi;
 pub mod padding;
+pub mod self_test;
+pub mod stream;
 
+#[cfg(all(feature = "aes-ni", target_arch = "x86_64"))]
+mod aesni;
+mod blake2b;
+#[cfg(feature = "constant-time")]
+mod ct_sbox;
 mod iv;
+mod parallel;
+mod sha256;
 mod util;
 
+#[cfg(feature = "openssl-compat")]
+mod md5;
+
 pub use iv::InitializationVector;
 
+/// Log target of the per-block messages
+///
+/// These messages are logged at trace level for every single block,
+/// so they flood the log on large inputs.
+/// The CLI only shows them if this target is enabled explicitly
+/// (`RUST_LOG=aesculap::per_block=trace`).
+pub const PER_BLOCK_LOG_TARGET: &str = "aesculap::per_block";
+
+/// Implementation of the AES rounds
+///
+/// [encrypt_block](encryption::encrypt_block) and [decrypt_block](decryption::decrypt_block)
+/// use the AES-NI instructions if the `aes-ni` feature is enabled and the CPU supports them.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Backend {
+    Software,
+    AesNi,
+}
+
+impl Backend {
+    /// The backend that is used on this machine
+    ///
+    /// The CPU is only checked on the first call.
+    pub fn detect() -> Self {
+        #[cfg(all(feature = "aes-ni", target_arch = "x86_64"))]
+        return aesni::backend();
+
+        #[cfg(not(all(feature = "aes-ni", target_arch = "x86_64")))]
+        Backend::Software
+    }
+}
+
 /// AES encryption mode
 ///
 /// Implemented modes:
@@ -21,7 +76,30 @@ pub use iv::InitializationVector;
 /// - Cipher Block Chaining (CBC):
 ///   An [initialization vector (IV)](InitializationVector) is used and the blocks are chained together.
 ///   It is generally more secure.
+///
+/// - Counter (CTR):
+///   Successive values of a 128 bit counter are encrypted and XORed onto the data.
+///   No padding is needed and decryption is the same operation as encryption.
+///   A counter value must never be reused with the same key.
+///
+/// - Cipher Feedback (CFB):
+///   The previous ciphertext block (starting with the [IV](InitializationVector)) is encrypted
+///   and XORed onto the next block. Like CTR it needs no padding.
+///
+/// - Output Feedback (OFB):
+///   The [IV](InitializationVector) is encrypted over and over again to produce the keystream.
+///   Like CTR it needs no padding and decryption is the same operation as encryption.
+///
+/// - Propagating Cipher Block Chaining (PCBC):
+///   Like CBC, but each block is XORed with both the previous plaintext and ciphertext block.
+///   A corrupted ciphertext block garbles all following blocks.
+///   Only useful for interoperability with legacy (Kerberos v4 style) systems.
+#[derive(Clone, Copy)]
 pub enum EncryptionMode {
     ECB,
     CBC(InitializationVector),
+    CTR(u128),
+    CFB(InitializationVector),
+    OFB(InitializationVector),
+    PCBC(InitializationVector),
 }
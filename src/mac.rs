@@ -0,0 +1,120 @@
+//! MAC module
+//!
+//! This module provides AES-CMAC (also known as OMAC1) as specified in
+//! [RFC 4493](https://www.rfc-editor.org/rfc/rfc4493) and [NIST SP 800-38B](https://csrc.nist.gov/publications/detail/sp/800-38b/final).
+//! Unlike the HMAC of the [etm](crate::etm) module it only needs the block cipher,
+//! but the same key must not be used for encryption.
+
+use crate::block::Block;
+use crate::cipher::Cipher;
+use crate::key::Key;
+
+/// Reduction constant of GF(2^128) for the big-endian doubling, `x^7 + x^2 + x + 1`
+const R_B: u128 = 0x87;
+
+/// Multiply an element of GF(2^128) by `x`
+fn double(v: u128) -> u128 {
+    (v << 1) ^ (R_B & 0u128.wrapping_sub(v >> 127))
+}
+
+/// Derive the subkeys `K1` and `K2` from the encryption of the all-zero block
+fn subkeys<const R: usize>(cipher: &Cipher<R>) -> (u128, u128) {
+    let mut l = Block::from_bytes([0; 16]);
+    cipher.encrypt_block(&mut l);
+
+    let k1 = double(u128::from_be_bytes(l.dump_bytes()));
+    (k1, double(k1))
+}
+
+/// Compute the AES-CMAC of a message
+///
+/// # Parameters
+/// - `key`: [Key] of the MAC, which must not be used for encryption as well
+/// - `message`: byte slice to authenticate (may be empty)
+///
+/// # Return value
+/// The 16 byte tag. Compare tags in constant time when verifying them.
+pub fn cmac<const R: usize, K>(key: &K, message: &[u8]) -> [u8; 16]
+where
+    K: Key<R>,
+{
+    log::trace!("Compute AES-CMAC");
+
+    let cipher = Cipher::new(key);
+    let (k1, k2) = subkeys(&cipher);
+
+    // the empty message is treated as one incomplete block
+    let n = message.len().div_ceil(16).max(1);
+    let (head, last) = message.split_at((n - 1) * 16);
+
+    // a complete last block is masked with K1, an incomplete one is padded with 10...0 and masked with K2
+    let last = if last.len() == 16 {
+        u128::from_be_bytes(last.try_into().unwrap()) ^ k1
+    } else {
+        let mut padded = [0; 16];
+        padded[..last.len()].copy_from_slice(last);
+        padded[last.len()] = 0x80;
+        u128::from_be_bytes(padded) ^ k2
+    };
+
+    let mut x = Block::from_bytes([0; 16]);
+    for chunk in head.chunks_exact(16) {
+        x ^= Block::from_bytes(chunk.try_into().unwrap());
+        cipher.encrypt_block(&mut x);
+    }
+
+    x ^= Block::from_bytes(last.to_be_bytes());
+    cipher.encrypt_block(&mut x);
+
+    x.dump_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::AES128Key;
+
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f,
+        0x3c,
+    ];
+
+    const MESSAGE: [u8; 64] = [
+        0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17,
+        0x2a, 0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf,
+        0x8e, 0x51, 0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11, 0xe5, 0xfb, 0xc1, 0x19, 0x1a,
+        0x0a, 0x52, 0xef, 0xf6, 0x9f, 0x24, 0x45, 0xdf, 0x4f, 0x9b, 0x17, 0xad, 0x2b, 0x41, 0x7b,
+        0xe6, 0x6c, 0x37, 0x10,
+    ];
+
+    #[test]
+    fn subkeys_rfc4493() {
+        // see https://www.rfc-editor.org/rfc/rfc4493#section-4
+        let cipher = Cipher::new(&AES128Key::from_bytes(KEY));
+        let (k1, k2) = subkeys(&cipher);
+
+        assert_eq!(k1, 0xfbeed618357133667c85e08f7236a8de);
+        assert_eq!(k2, 0xf7ddac306ae266ccf90bc11ee46d513b);
+    }
+
+    #[test]
+    fn cmac_rfc4493() {
+        // see https://www.rfc-editor.org/rfc/rfc4493#section-4
+        let key = AES128Key::from_bytes(KEY);
+
+        let vectors: [(usize, u128); 4] = [
+            (0, 0xbb1d6929e95937287fa37d129b756746),
+            (16, 0x070a16b46b4d4144f79bdd9dd04a287c),
+            (40, 0xdfa66747de9ae63030ca32611497c827),
+            (64, 0x51f0bebf7e3b9d92fc49741779363cfe),
+        ];
+
+        for (len, tag) in vectors {
+            assert_eq!(
+                cmac(&key, &MESSAGE[..len]),
+                tag.to_be_bytes(),
+                "length {len}"
+            );
+        }
+    }
+}
@@ -1,34 +1,80 @@
-use std::fs::File;
+use std::env;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
 use std::io::{self, Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
 
-use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap::error::ErrorKind;
+use clap::{ArgGroup, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+#[cfg(feature = "rand")]
+use rand::{rngs::StdRng, SeedableRng};
 
-use aesculap::key::{AES128Key, AES192Key, AES256Key, Key};
-use aesculap::padding::{Pkcs7Padding, ZeroPadding};
+use aesculap::base64;
+use aesculap::decryption::DecryptError;
+use aesculap::etm::{encrypt_then_mac, verify_then_decrypt, EtmError};
+use aesculap::hex::{self, HexError};
+#[cfg(feature = "openssl-compat")]
+use aesculap::interop;
+use aesculap::kdf::{argon2id_key, hkdf, pbkdf2_key, scrypt_key};
+use aesculap::key::{key_strength, AnyKey, Key, KeyStrengthInfo, KeyVisitor};
+#[cfg(feature = "bench")]
+use aesculap::key::{AES128Key, AES192Key, AES256Key};
+use aesculap::padding::{
+    AnsiX923Padding, Iso7816Padding, NoPadding, Padding, Pkcs7Padding, ZeroPadding,
+};
+use aesculap::self_test::self_test;
+use aesculap::stream::{DecryptingReader, EncryptingWriter};
+#[cfg(feature = "bench")]
+use aesculap::Backend;
 use aesculap::EncryptionMode;
 use aesculap::InitializationVector;
+use aesculap::PER_BLOCK_LOG_TARGET;
 
-use aesculap::decryption::decrypt_bytes;
+#[cfg(feature = "bench")]
 use aesculap::encryption::encrypt_bytes;
 
 #[derive(Parser, Debug)]
 #[command(author, version)]
 #[command(about, long_about = None)]
+#[command(arg_required_else_help = true)]
 struct Cli {
+    /// Run known-answer tests of the primitives and exit
+    #[arg(long, exclusive = true)]
+    self_test: bool,
+
     #[command(subcommand)]
-    cmd: Command,
+    cmd: Option<Command>,
 }
 
 #[derive(Subcommand, Debug)]
 enum Command {
     /// Encrypt data
     #[command(alias = "en")]
+    #[command(group(ArgGroup::new("encrypt_mode").args(["cbc", "ecb"]).required(true)))]
+    #[command(group(ArgGroup::new("cbc_iv").arg("cbc").requires("iv")))]
     Encrypt {
-        /// The key must have a size of 128, 192 or 256 bits (16, 24 or 32 bytes)
-        #[arg(long, short)]
-        key_file: PathBuf,
+        #[command(flatten)]
+        key: KeySource,
+
+        /// Require a key of exactly this size instead of inferring the AES variant from the key file
+        ///
+        /// With --passphrase, this is the size of the derived key [default: 256].
+        #[arg(long, value_name = "BITS", value_parser = parse_aes_bits)]
+        aes_bits: Option<u64>,
+
+        /// Salt of the key derivation as hex digits [default: random, stored in the sidecar file or a header of the output]
+        ///
+        /// Without --meta, the random salt and the key derivation parameters are written in front of the ciphertext.
+        #[arg(long, value_name = "HEX", value_parser = parse_salt, requires = "passphrase_source")]
+        salt: Option<Salt>,
+
+        /// Function that derives the key from the passphrase [default: pbkdf2]
+        #[arg(long, value_enum, requires = "passphrase_source")]
+        kdf: Option<KdfOption>,
+
+        #[command(flatten)]
+        kdf_cost: KdfCost,
 
         #[command(flatten)]
         mode: Mode,
@@ -41,48 +87,325 @@ enum Command {
         #[command(flatten)]
         iv: Option<Iv>,
 
+        /// Write the IV in front of the ciphertext, so decryption needs no IV option
+        #[arg(long, conflicts_with_all = ["ecb", "meta"])]
+        prepend_iv: bool,
+
+        /// Write the format of `openssl enc -aes-<BITS>-cbc -md md5` (needs --cbc and a passphrase)
+        ///
+        /// Key and IV are derived from the passphrase with a single round of MD5, which is much easier to attack than --kdf.
+        #[cfg(feature = "openssl-compat")]
+        #[arg(long, group = "iv", requires = "passphrase_source")]
+        #[arg(conflicts_with_all = ["salt", "kdf", "iterations", "scrypt_log_n", "argon2_memory", "argon2_iterations", "argon2_parallelism", "meta", "prepend_iv"])]
+        openssl_compat: bool,
+
+        /// Seed the generator of the random IV to get reproducible output
+        ///
+        /// Only meant for tests and documentation, never use it for real encryption!
+        #[cfg(feature = "rand")]
+        #[arg(long, requires = "random_iv")]
+        seed: Option<u64>,
+
+        /// Copy the first N bytes (e.g. a file header) unencrypted to the output
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        skip_bytes: usize,
+
+        /// Size of the chunks the input is read and encrypted in (in KiB)
+        #[arg(long, value_name = "KIB", default_value_t = 64)]
+        #[arg(value_parser = clap::value_parser!(u64).range(1..))]
+        chunk_size: u64,
+
+        /// Encode the output as Base64, wrapped at 64 columns, e.g. to paste it into an email
+        #[arg(long, visible_alias = "base64")]
+        armor: bool,
+
+        /// Append an HMAC-SHA256 tag, so decryption detects modified data
+        ///
+        /// The MAC key is derived from the key. The whole input is held in memory.
+        #[arg(long)]
+        authenticate: bool,
+
+        /// Write the mode, padding and IV to a sidecar file next to the output (<OUTPUT>.meta)
+        ///
+        /// Decryption reads the sidecar automatically if no mode is given.
+        #[arg(long, conflicts_with = "stdout")]
+        meta: bool,
+
         #[command(flatten)]
         input: Input,
 
         #[command(flatten)]
         output: Output,
+
+        /// Permissions of the created output file as octal number, e.g. 600 (Unix only)
+        #[arg(long, value_name = "MODE", value_parser = parse_permissions)]
+        #[arg(conflicts_with = "stdout")]
+        output_permissions: Option<u32>,
     },
 
     /// Decrypt data
     #[command(alias = "de")]
     Decrypt {
-        /// The key must have a size of 128, 192 or 256 bits (16, 24 or 32 bytes)
-        #[arg(long, short)]
-        key_file: PathBuf,
+        #[command(flatten)]
+        key: KeySource,
 
+        /// Require a key of exactly this size instead of inferring the AES variant from the key file
+        ///
+        /// With --passphrase, this is the size of the derived key [default: 256].
+        #[arg(long, value_name = "BITS", value_parser = parse_aes_bits)]
+        aes_bits: Option<u64>,
+
+        /// Salt of the key derivation as hex digits [default: the one of the sidecar file or of the header of the input]
+        #[arg(long, value_name = "HEX", value_parser = parse_salt, requires = "passphrase_source")]
+        salt: Option<Salt>,
+
+        /// Function that derives the key from the passphrase [default: the one of the sidecar file, or pbkdf2]
+        ///
+        /// Cost parameters that are not given are taken from the sidecar file as well.
+        #[arg(long, value_enum, requires = "passphrase_source")]
+        kdf: Option<KdfOption>,
+
+        #[command(flatten)]
+        kdf_cost: KdfCost,
+
+        // without a mode, it is read from the sidecar file of the input (<INPUT_FILE>.meta)
         #[command(flatten)]
         mode: Mode,
 
+        /// Padding that was used for encryption [default: pkcs7, or the one of the sidecar file]
         #[arg(long, short)]
-        #[arg(value_enum, default_value_t = PaddingOption::Pkcs7)]
-        padding: PaddingOption,
+        #[arg(value_enum)]
+        padding: Option<PaddingOption>,
 
-        /// In CBC mode an IV with a size of 128 bits (16 bytes) is required
+        /// The IV of CBC mode with a size of 128 bits (16 bytes) [default: the one of the sidecar file, or the first 16 bytes of the input]
+        ///
+        /// Without any IV option, the IV is read from the sidecar file of the input (<INPUT_FILE>.meta) if there is one,
+        /// else it is expected in front of the ciphertext (see `encrypt --prepend-iv`).
         #[arg(long)]
         #[arg(group = "iv")]
         iv_file: Option<PathBuf>,
 
+        /// The IV as 32 hex digits instead of a file
+        #[arg(long = "iv", id = "iv_hex", value_name = "HEX")]
+        #[arg(group = "iv")]
+        iv_hex: Option<InitializationVector>,
+
+        /// The IV as Base64 instead of a file
+        #[arg(long, value_name = "BASE64", value_parser = InitializationVector::from_base64)]
+        #[arg(group = "iv")]
+        iv_base64: Option<InitializationVector>,
+
+        /// The IV is in front of the ciphertext (see `encrypt --prepend-iv`), fail if there is a sidecar file with an IV
+        #[arg(long, group = "iv", requires = "cbc")]
+        prepend_iv: bool,
+
+        /// Read the format of `openssl enc -aes-<BITS>-cbc -md md5` (needs --cbc and a passphrase)
+        #[cfg(feature = "openssl-compat")]
+        #[arg(long, group = "iv", requires = "passphrase_source")]
+        #[arg(conflicts_with_all = ["salt", "kdf", "iterations", "scrypt_log_n", "argon2_memory", "argon2_iterations", "argon2_parallelism"])]
+        openssl_compat: bool,
+
+        /// Copy the first N bytes (e.g. a file header) unchanged to the output
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        skip_bytes: usize,
+
+        /// Size of the chunks the input is read and decrypted in (in KiB)
+        #[arg(long, value_name = "KIB", default_value_t = 64)]
+        #[arg(value_parser = clap::value_parser!(u64).range(1..))]
+        chunk_size: u64,
+
+        /// The input is Base64 (see `encrypt --armor`), whitespace is ignored
+        #[arg(long, visible_alias = "base64")]
+        armor: bool,
+
+        /// Check the HMAC-SHA256 tag of `encrypt --authenticate` before anything is decrypted
+        ///
+        /// The whole input is held in memory.
+        #[arg(long)]
+        authenticate: bool,
+
         #[command(flatten)]
         input: Input,
 
         #[command(flatten)]
         output: Output,
+
+        /// Permissions of the created output file as octal number, e.g. 600 (Unix only)
+        #[arg(long, value_name = "MODE", value_parser = parse_permissions)]
+        #[arg(conflicts_with = "stdout")]
+        output_permissions: Option<u32>,
+    },
+
+    /// Generate a random key
+    #[cfg(feature = "rand")]
+    #[command(group(ArgGroup::new("keygen_output").args(["output_file", "stdout"]).required(true)))]
+    Keygen {
+        /// Size of the key
+        #[arg(long, value_name = "BITS", value_parser = parse_aes_bits, default_value_t = 256)]
+        bits: u64,
+
+        /// Encoding of the key
+        ///
+        /// Only raw keys can be passed to --key-file, the others to --key-hex and --key-base64.
+        #[arg(long, value_enum, default_value_t = KeyFormat::Raw)]
+        format: KeyFormat,
+
+        /// Write the key to a file, which only the owner can read (Unix only)
+        #[arg(long, short)]
+        output_file: Option<PathBuf>,
+
+        /// Write the key to STDOUT
+        #[arg(long)]
+        stdout: bool,
+
+        /// Overwrite an existing output file
+        #[arg(long, requires = "output_file")]
+        force: bool,
+    },
+
+    /// Show the key space and brute-force strength of a key
+    Info {
+        /// The key must have a size of 128, 192 or 256 bits (16, 24 or 32 bytes)
+        #[arg(long, short)]
+        key_file: PathBuf,
+    },
+
+    /// Measure the encryption throughput on this machine
+    #[cfg(feature = "bench")]
+    Bench {
+        /// Size of the random buffer that is encrypted (in MiB)
+        #[arg(long, value_name = "MIB", default_value_t = 16)]
+        #[arg(value_parser = clap::value_parser!(u64).range(1..))]
+        size: u64,
     },
 }
 
 #[derive(Args, Debug)]
 #[group(required = true, multiple = false)]
+struct KeySource {
+    /// The key must have a size of 128, 192 or 256 bits (16, 24 or 32 bytes)
+    #[arg(long, short)]
+    key_file: Option<PathBuf>,
+
+    /// The key as 32, 48 or 64 hex digits instead of a file
+    ///
+    /// The key may be visible to other users of this machine (e.g. with `ps`).
+    #[arg(long, value_name = "HEX", value_parser = parse_key_hex)]
+    key_hex: Option<KeyBytes>,
+
+    /// The key as Base64 instead of a file
+    ///
+    /// The key may be visible to other users of this machine (e.g. with `ps`).
+    #[arg(long, value_name = "BASE64", value_parser = parse_key_base64)]
+    key_base64: Option<KeyBytes>,
+
+    /// Derive the key from a passphrase (see --kdf) instead of reading a key file
+    ///
+    /// The passphrase may be visible to other users of this machine (e.g. with `ps`).
+    #[arg(long, group = "passphrase_source")]
+    passphrase: Option<String>,
+
+    /// Like --passphrase, but read the passphrase from an environment variable
+    #[arg(long, value_name = "VAR", group = "passphrase_source")]
+    passphrase_env: Option<String>,
+
+    /// Like --passphrase, but ask for the passphrase on the terminal
+    #[arg(long, group = "passphrase_source")]
+    ask_passphrase: bool,
+}
+
+impl KeySource {
+    /// Fill in [passphrase](Self::passphrase) if it is read from the environment or the terminal
+    ///
+    /// With `confirm`, a passphrase from the terminal has to be entered twice.
+    fn resolve_passphrase(&mut self, confirm: bool) -> Result<(), CliError> {
+        if let Some(var) = self.passphrase_env.take() {
+            match env::var(&var) {
+                Ok(passphrase) => self.passphrase = Some(passphrase),
+                Err(err) => return Err(CliError::Env(var, err)),
+            }
+        } else if self.ask_passphrase {
+            let passphrase = prompt_passphrase("Passphrase: ")?;
+            if confirm && prompt_passphrase("Repeat the passphrase: ")? != passphrase {
+                return Err(CliError::Args("The passphrases don't match"));
+            }
+            self.passphrase = Some(passphrase);
+        }
+
+        Ok(())
+    }
+}
+
+/// Key bytes given on the command line
+#[derive(Clone, Debug)]
+struct KeyBytes(Vec<u8>);
+
+/// Salt of the key derivation
+#[derive(Clone, Debug)]
+struct Salt(Vec<u8>);
+
+/// Encoding of a generated key
+#[cfg(feature = "rand")]
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Debug)]
+enum KeyFormat {
+    /// The bytes of the key
+    Raw,
+
+    /// Hex digits and a line break
+    Hex,
+
+    /// Base64 and a line break
+    Base64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Debug)]
+enum KdfOption {
+    /// PBKDF2-HMAC-SHA256
+    Pbkdf2,
+
+    /// scrypt, which also needs a lot of memory and is therefore harder to attack with custom hardware
+    Scrypt,
+
+    /// Argon2id, which is memory-hard like scrypt but has separate memory and time costs
+    Argon2id,
+}
+
+/// Cost parameters of the key derivation functions
+#[derive(Args, Debug)]
+struct KdfCost {
+    /// Number of PBKDF2 iterations [default: 600000]
+    #[arg(long, value_name = "N", requires = "passphrase_source")]
+    #[arg(value_parser = clap::value_parser!(u32).range(1..))]
+    iterations: Option<u32>,
+
+    /// Cost of scrypt as a power of two, r = 8 and p = 1 are fixed [default: 17]
+    #[arg(long, value_name = "LOG_N", requires = "passphrase_source")]
+    #[arg(value_parser = clap::value_parser!(u8).range(1..=MAX_SCRYPT_LOG_N as i64))]
+    scrypt_log_n: Option<u8>,
+
+    /// Memory of Argon2id in KiB, at least 8 per lane [default: 65536]
+    #[arg(long, value_name = "KIB", requires = "passphrase_source")]
+    #[arg(value_parser = clap::value_parser!(u32).range(8..))]
+    argon2_memory: Option<u32>,
+
+    /// Number of passes of Argon2id over its memory [default: 3]
+    #[arg(long, value_name = "N", requires = "passphrase_source")]
+    #[arg(value_parser = clap::value_parser!(u32).range(1..))]
+    argon2_iterations: Option<u32>,
+
+    /// Number of lanes of Argon2id [default: 4]
+    #[arg(long, value_name = "LANES", requires = "passphrase_source")]
+    #[arg(value_parser = clap::value_parser!(u32).range(1..1 << 24))]
+    argon2_parallelism: Option<u32>,
+}
+
+#[derive(Args, Debug)]
+#[group(multiple = false)]
 struct Mode {
     /// Cipher Block Chaining mode
     ///
     /// An initialization vector (IV) is used and the blocks are chained together. It is generally more secure.
     #[arg(long)]
-    #[arg(requires = "iv")]
     cbc: bool,
 
     /// Electronic Code Book mode (not recommended)
@@ -98,6 +421,12 @@ enum PaddingOption {
     /// Padding is done according to PKCS #7 (recommended)
     Pkcs7,
 
+    /// Padding is done according to ANSI X.923
+    AnsiX923,
+
+    /// Padding is done according to ISO/IEC 7816-4
+    Iso7816,
+
     /// The blocks are filled with zeroes
     Zero,
 
@@ -113,11 +442,28 @@ struct Iv {
     #[arg(long)]
     iv_file: Option<PathBuf>,
 
+    /// The IV as 32 hex digits instead of a file
+    #[arg(long = "iv", id = "iv_hex", value_name = "HEX")]
+    iv_hex: Option<InitializationVector>,
+
+    /// The IV as Base64 instead of a file
+    #[arg(long, value_name = "BASE64", value_parser = InitializationVector::from_base64)]
+    iv_base64: Option<InitializationVector>,
+
     /// Generate a random IV and write it to a file
     #[cfg(feature = "rand")]
     #[arg(value_name = "IV_FILE")]
     #[arg(long)]
     random_iv: Option<PathBuf>,
+
+    /// Derive the IV from a counter that is incremented in a state file on every run
+    ///
+    /// The IV is unique without a random number generator, as long as the state file is kept.
+    /// Concurrent runs take turns using <STATE_FILE>.lock.
+    /// It is stored in the sidecar file, so this requires --meta.
+    #[arg(value_name = "STATE_FILE")]
+    #[arg(long, requires = "meta")]
+    iv_counter: Option<PathBuf>,
 }
 
 #[derive(Args, Debug)]
@@ -142,251 +488,2072 @@ struct Output {
     /// Write the output to STDOUT
     #[arg(long)]
     stdout: bool,
+
+    /// Replace the input file with the output
+    #[arg(long)]
+    #[arg(conflicts_with = "stdin")]
+    in_place: bool,
 }
 
 fn main() {
-    env_logger::builder().format_timestamp(None).init();
+    env_logger::Builder::new()
+        .format_timestamp(None)
+        .filter_level(log::LevelFilter::Error)
+        .filter_module(PER_BLOCK_LOG_TARGET, log::LevelFilter::Off)
+        .parse_default_env()
+        .init();
+
+    #[cfg(unix)]
+    interrupt::install();
 
     let cli = Cli::parse();
-    run_cmd(cli.cmd).unwrap_or_else(|err| {
-        log::error!("{err}");
+
+    if cli.self_test {
+        match self_test() {
+            Ok(()) => println!("Self-test passed"),
+            Err(err) => {
+                eprintln!("Error: {err}");
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let Some(cmd) = cli.cmd else {
+        Cli::command()
+            .error(ErrorKind::MissingSubcommand, "A subcommand is required")
+            .exit();
+    };
+
+    run_cmd(cmd).unwrap_or_else(|err| {
+        eprintln!("Error: {err}");
         process::exit(1);
     });
 }
 
-fn run_cmd(cmd: Command) -> io::Result<()> {
+fn run_cmd(cmd: Command) -> Result<(), CliError> {
     match cmd {
         Command::Encrypt {
-            key_file,
+            mut key,
+            aes_bits,
+            salt,
+            kdf,
+            kdf_cost,
             mode,
             padding,
             iv,
+            prepend_iv,
+            #[cfg(feature = "openssl-compat")]
+            openssl_compat,
+            #[cfg(feature = "rand")]
+            seed,
+            skip_bytes,
+            chunk_size,
+            armor,
+            authenticate,
+            meta,
             input,
             output,
+            output_permissions,
         } => {
-            let key = read_key(key_file)?;
+            key.resolve_passphrase(true)?;
+
+            // the random salt has to be stored somewhere
+            let embed_kdf = key.passphrase.is_some() && salt.is_none() && !meta;
+
+            // header, key and IV of the `openssl enc` format
+            #[cfg(feature = "openssl-compat")]
+            let openssl = match &key.passphrase {
+                Some(passphrase) if openssl_compat => {
+                    Some(openssl_key(passphrase, rand::random(), aes_bits))
+                }
+                _ => None,
+            };
+            #[cfg(not(feature = "openssl-compat"))]
+            let openssl: Option<(Vec<u8>, Vec<u8>, InitializationVector)> = None;
+
+            let (key, kdf) = match (key.key_file, key.key_hex.or(key.key_base64), key.passphrase) {
+                (Some(key_file), None, None) => (read_key(key_file, aes_bits)?, None),
+                (None, Some(key), None) => (check_key_size(key.0, aes_bits)?, None),
+                (None, None, Some(passphrase)) => match &openssl {
+                    Some((_, key, _)) => (key.clone(), None),
+                    None => {
+                        let salt = match salt {
+                            Some(salt) => salt.0,
+                            #[cfg(feature = "rand")]
+                            None => random_salt(),
+                            #[cfg(not(feature = "rand"))]
+                            None => {
+                                return Err(CliError::Args(
+                                    "Without the `rand` feature, a passphrase needs --salt",
+                                ))
+                            }
+                        };
+                        let derivation = KeyDerivation {
+                            salt,
+                            params: kdf_params(kdf, &kdf_cost, None),
+                        };
+                        let key = derive_key(&passphrase, &derivation, aes_bits);
+                        (key, Some(derivation))
+                    }
+                },
+                _ => return Err(CliError::Args(KEY_SOURCE_REQUIRED)),
+            };
+            #[cfg(unix)]
+            let key = interrupt::wipe_on_interrupt(key);
 
             let mode: EncryptionMode = match (mode.ecb, mode.cbc) {
                 (true, false) => EncryptionMode::ECB,
                 (false, true) => {
-                    let iv = iv.unwrap();
+                    let iv = iv.ok_or(CliError::Args(IV_REQUIRED))?;
+
+                    #[cfg(feature = "rand")]
+                    let random_iv = match iv.random_iv {
+                        Some(iv_file) => {
+                            let iv = match seed {
+                                Some(seed) => {
+                                    log::warn!(
+                                        "The IV is derived from a fixed seed, this is insecure"
+                                    );
+                                    InitializationVector::random_from(&mut StdRng::seed_from_u64(
+                                        seed,
+                                    ))
+                                }
+                                None => InitializationVector::random(),
+                            };
+                            write_iv(iv_file, &iv)?;
+                            Some(iv)
+                        }
+                        None => None,
+                    };
+                    #[cfg(not(feature = "rand"))]
+                    let random_iv: Option<InitializationVector> = None;
 
-                    if let Some(iv_file) = iv.iv_file {
+                    if let Some((_, _, iv)) = openssl {
+                        EncryptionMode::CBC(iv)
+                    } else if let Some(iv_file) = iv.iv_file {
                         let iv = read_iv(iv_file)?;
                         let iv = InitializationVector::from_bytes(iv);
                         EncryptionMode::CBC(iv)
-                    } else if let Some(iv_file) = iv.random_iv {
-                        if cfg!(feature = "rand") {
-                            let iv = InitializationVector::random();
-                            write_iv(iv_file, &iv)?;
-                            EncryptionMode::CBC(iv)
-                        } else {
-                            panic!("Feature 'rand' not enabled");
-                        }
+                    } else if let Some(iv) = iv.iv_hex.or(iv.iv_base64) {
+                        EncryptionMode::CBC(iv)
+                    } else if let Some(iv) = random_iv {
+                        EncryptionMode::CBC(iv)
+                    } else if let Some(state_file) = iv.iv_counter {
+                        let counter = next_counter(state_file)?;
+                        EncryptionMode::CBC(counter_iv(&key, counter))
                     } else {
-                        panic!("Invalid IV state");
+                        return Err(CliError::Args(IV_REQUIRED));
                     }
                 }
-                _ => panic!("Invalid encryption mode"),
+                _ => return Err(CliError::Args(MODE_CONFLICT)),
             };
 
-            let input = match (input.input_file, input.stdin) {
-                (Some(path), false) => read_file(path),
-                (None, true) => read_stdin(),
-                _ => panic!("Invalid input"),
-            }?;
+            let meta = meta.then(|| format_meta(&mode, padding, kdf.as_ref()));
+            let kdf_header = kdf.filter(|_| embed_kdf).map(|kdf| format_kdf_header(&kdf));
+            let prepended_iv = match mode {
+                EncryptionMode::CBC(iv) if prepend_iv => Some(iv),
+                _ => None,
+            };
 
-            if padding == PaddingOption::None && input.len() % 16 != 0 {
-                log::error!("Without padding the number of input bytes has to be divisible by 16");
-                process::exit(1);
-            }
+            let output_path = output_path(output, &input)?;
 
-            let mut output: Box<dyn Write> = match (output.output_file, output.stdout) {
-                (Some(path), false) => {
-                    let f = File::create(path)?;
-                    Box::new(f)
-                }
-                (None, true) => Box::new(io::stdout().lock()),
-                _ => panic!("Invalid output"),
-            };
+            let mut input = open_input(input)?;
+            let header = read_header(&mut input, skip_bytes)?;
+            let chunk_size = chunk_size as usize * 1024;
 
-            let output_bytes = match key.len() {
-                16 => {
-                    let key = AES128Key::from_bytes(key.try_into().unwrap());
-                    encrypt(&input, &key, padding, mode)
-                }
-                24 => {
-                    let key = AES192Key::from_bytes(key.try_into().unwrap());
-                    encrypt(&input, &key, padding, mode)
-                }
-                32 => {
-                    let key = AES256Key::from_bytes(key.try_into().unwrap());
-                    encrypt(&input, &key, padding, mode)
-                }
-                _ => {
-                    log::error!(
-                        "Key file must have a size of 128, 192 or 256 bits (16, 24, or 32 bytes)"
-                    );
-                    process::exit(1);
-                }
-            };
+            // the sidecar is only written once the output is complete
+            write_output(output_path.clone(), output_permissions, |output| {
+                write_armored(output, armor, |output| {
+                    output.write_all(&header)?;
+                    if let Some((openssl_header, ..)) = &openssl {
+                        output.write_all(openssl_header)?;
+                    }
+                    if let Some(kdf_header) = &kdf_header {
+                        output.write_all(kdf_header)?;
+                    }
+                    if let Some(iv) = prepended_iv {
+                        output.write_all(&iv.as_bytes())?;
+                    }
+
+                    let mac_key = authenticate.then(|| mac_key(&key));
+                    let mac = mac_key.as_deref().map(|mac_key| (mac_key, &header[..]));
 
-            output.write_all(&output_bytes)?;
+                    AnyKey::from_slice(&key)
+                        .expect("The key size is checked when the key is read")
+                        .visit(Encrypt {
+                            plaintext: input,
+                            output,
+                            padding,
+                            mode,
+                            mac,
+                            chunk_size,
+                        })
+                })
+            })?;
+
+            if let (Some(meta), Some(path)) = (meta, &output_path) {
+                write_file_atomically(&meta_path(path), output_permissions, |f| {
+                    f.write_all(meta.as_bytes())
+                })?;
+            }
         }
         Command::Decrypt {
-            key_file,
+            mut key,
+            aes_bits,
+            salt,
+            kdf,
+            kdf_cost,
             mode,
             padding,
             iv_file,
+            iv_hex,
+            iv_base64,
+            prepend_iv,
+            #[cfg(feature = "openssl-compat")]
+            openssl_compat,
+            skip_bytes,
+            chunk_size,
+            armor,
+            authenticate,
             input,
             output,
+            output_permissions,
         } => {
-            let key = read_key(key_file)?;
+            key.resolve_passphrase(false)?;
 
-            let mode: EncryptionMode = match (mode.ecb, mode.cbc) {
-                (true, false) => EncryptionMode::ECB,
-                (false, true) => {
-                    let iv = read_iv(iv_file.unwrap())?;
-                    let iv = InitializationVector::from_bytes(iv);
-                    EncryptionMode::CBC(iv)
+            #[cfg(not(feature = "openssl-compat"))]
+            let openssl_compat = false;
+
+            let has_mode = mode.ecb || mode.cbc;
+            let has_salt = key.passphrase.is_none() || salt.is_some() || openssl_compat;
+            let has_iv = iv_file.is_some() || iv_hex.is_some() || iv_base64.is_some();
+
+            let meta = if has_mode && has_salt {
+                // an explicit padding is still checked against an existing sidecar, which also has the IV of CBC mode
+                let needs_meta = padding.is_some() || (mode.cbc && !has_iv && !openssl_compat);
+                match input.input_file.as_deref().map(meta_path) {
+                    Some(meta_path) if needs_meta && meta_path.exists() => {
+                        Some((read_meta(meta_path.clone())?, meta_path))
+                    }
+                    _ => None,
                 }
-                _ => panic!("Invalid encryption mode"),
+            } else if has_mode
+                && !input
+                    .input_file
+                    .as_deref()
+                    .is_some_and(|path| meta_path(path).exists())
+            {
+                // the salt is read from the header of the input
+                None
+            } else {
+                let missing = "One of --cbc or --ecb is";
+
+                let Some(meta_path) = input.input_file.as_deref().map(meta_path) else {
+                    Cli::command()
+                        .error(
+                            ErrorKind::MissingRequiredArgument,
+                            format!("{missing} required when reading from STDIN"),
+                        )
+                        .exit();
+                };
+                if !meta_path.exists() {
+                    Cli::command()
+                        .error(
+                            ErrorKind::MissingRequiredArgument,
+                            format!(
+                                "{missing} required, there is no sidecar file {}",
+                                meta_path.display()
+                            ),
+                        )
+                        .exit();
+                }
+
+                Some((read_meta(meta_path.clone())?, meta_path))
             };
 
-            let input = match (input.input_file, input.stdin) {
-                (Some(path), false) => read_file(path),
-                (None, true) => read_stdin(),
-                _ => panic!("Invalid input"),
-            }?;
+            let (meta_mode, meta_padding, meta_kdf) = match meta {
+                Some((meta, meta_path)) => {
+                    if let (Some(padding), Some(meta_padding)) = (padding, meta.padding) {
+                        if padding != meta_padding {
+                            return Err(CliError::PaddingMismatch(padding, meta_padding));
+                        }
+                    }
 
-            let mut output: Box<dyn Write> = match (output.output_file, output.stdout) {
-                (Some(path), false) => {
-                    let f = File::create(path)?;
-                    Box::new(f)
+                    (
+                        Some((meta.mode, meta_path.clone())),
+                        meta.padding,
+                        Some((meta.kdf, meta_path)),
+                    )
                 }
-                (None, true) => Box::new(io::stdout().lock()),
-                _ => panic!("Invalid output"),
+                None => (None, None, None),
             };
 
-            let output_bytes = match key.len() {
-                16 => {
-                    let key = AES128Key::from_bytes(key.try_into().unwrap());
-                    decrypt(&input, &key, padding, mode)
+            let output_path = output_path(output, &input)?;
+
+            let mut input = open_input(input)?;
+            if armor {
+                input = Box::new(base64::DecodingReader::new(input));
+            }
+            let header = read_header(&mut input, skip_bytes)?;
+
+            // key and IV of the `openssl enc` format
+            #[cfg(feature = "openssl-compat")]
+            let openssl = match &key.passphrase {
+                Some(passphrase) if openssl_compat => {
+                    let mut openssl_header = [0; interop::SALTED_HEADER_LEN];
+                    input
+                        .read_exact(&mut openssl_header)
+                        .map_err(|err| match err.kind() {
+                            io::ErrorKind::UnexpectedEof => {
+                                CliError::KdfHeader("Unexpected end of input")
+                            }
+                            _ => err.into(),
+                        })?;
+                    let (salt, _) = interop::split_salted_header(&openssl_header)
+                        .map_err(CliError::KdfHeader)?;
+
+                    let (_, key, iv) = openssl_key(passphrase, salt, aes_bits);
+                    Some((key, iv))
                 }
-                24 => {
-                    let key = AES192Key::from_bytes(key.try_into().unwrap());
-                    decrypt(&input, &key, padding, mode)
+                _ => None,
+            };
+            #[cfg(not(feature = "openssl-compat"))]
+            let openssl: Option<(Vec<u8>, InitializationVector)> = None;
+            let iv_hex = iv_hex.or(openssl.as_ref().map(|&(_, iv)| iv));
+
+            let key_source = (key.key_file, key.key_hex.or(key.key_base64), key.passphrase);
+            let key = match (key_source, openssl) {
+                ((Some(key_file), None, None), _) => read_key(key_file, aes_bits)?,
+                ((None, Some(key), None), _) => check_key_size(key.0, aes_bits)?,
+                ((None, None, Some(_)), Some((key, _))) => key,
+                ((None, None, Some(passphrase)), None) => {
+                    let (salt, meta_params) = match (salt, meta_kdf) {
+                        (Some(salt), meta_kdf) => (
+                            salt.0,
+                            meta_kdf.and_then(|(kdf, _)| kdf).map(|kdf| kdf.params),
+                        ),
+                        (None, Some((Some(kdf), _))) => (kdf.salt, Some(kdf.params)),
+                        (None, Some((None, meta_path))) => {
+                            return Err(CliError::Meta(meta_path, "No salt for the passphrase"))
+                        }
+                        (None, None) => {
+                            let kdf = read_kdf_header(&mut input)?;
+                            (kdf.salt, Some(kdf.params))
+                        }
+                    };
+                    let derivation = KeyDerivation {
+                        salt,
+                        params: kdf_params(kdf, &kdf_cost, meta_params),
+                    };
+                    derive_key(&passphrase, &derivation, aes_bits)
                 }
-                32 => {
-                    let key = AES256Key::from_bytes(key.try_into().unwrap());
-                    decrypt(&input, &key, padding, mode)
+                _ => return Err(CliError::Args(KEY_SOURCE_REQUIRED)),
+            };
+            #[cfg(unix)]
+            let key = interrupt::wipe_on_interrupt(key);
+
+            let (mode, padding): (EncryptionMode, PaddingOption) = match (mode.ecb, mode.cbc) {
+                (true, false) => (EncryptionMode::ECB, padding.unwrap_or(PaddingOption::Pkcs7)),
+                (false, true) => match (iv_file, iv_hex.or(iv_base64)) {
+                    (Some(iv_file), None) => {
+                        let iv = read_iv(iv_file)?;
+                        let iv = InitializationVector::from_bytes(iv);
+                        (
+                            EncryptionMode::CBC(iv),
+                            padding.unwrap_or(PaddingOption::Pkcs7),
+                        )
+                    }
+                    (None, Some(iv)) => (
+                        EncryptionMode::CBC(iv),
+                        padding.unwrap_or(PaddingOption::Pkcs7),
+                    ),
+                    (None, None) => {
+                        let iv = match meta_mode {
+                            None => read_prepended_iv(&mut input)?,
+                            Some((EncryptionMode::CBC(iv), _)) if !prepend_iv => iv,
+                            Some((EncryptionMode::CBC(_), meta_path)) => {
+                                return Err(CliError::Meta(
+                                    meta_path,
+                                    "Has an IV, but --prepend-iv expects it in front of the ciphertext",
+                                ))
+                            }
+                            Some((_, meta_path)) => {
+                                return Err(CliError::Meta(meta_path, "Not in CBC mode"))
+                            }
+                        };
+                        (
+                            EncryptionMode::CBC(iv),
+                            padding.or(meta_padding).unwrap_or(PaddingOption::Pkcs7),
+                        )
+                    }
+                    _ => return Err(CliError::Args(IV_REQUIRED)),
+                },
+                (false, false) => (
+                    meta_mode
+                        .map(|(mode, _)| mode)
+                        .ok_or(CliError::Args("One of --cbc or --ecb is required"))?,
+                    padding.or(meta_padding).unwrap_or(PaddingOption::Pkcs7),
+                ),
+                _ => return Err(CliError::Args(MODE_CONFLICT)),
+            };
+
+            let chunk_size = chunk_size as usize * 1024;
+
+            write_output(output_path, output_permissions, |output| {
+                // an authenticated header is only written along with the plaintext
+                if !authenticate {
+                    output.write_all(&header)?;
                 }
-                _ => {
-                    log::error!(
-                        "Key file must have a size of 128, 192 or 256 bits (16, 24, or 32 bytes)"
-                    );
-                    process::exit(1);
+
+                let mac_key = authenticate.then(|| mac_key(&key));
+                let mac = mac_key.as_deref().map(|mac_key| (mac_key, &header[..]));
+
+                AnyKey::from_slice(&key)
+                    .expect("The key size is checked when the key is read")
+                    .visit(Decrypt {
+                        ciphertext: input,
+                        output,
+                        padding,
+                        mode,
+                        mac,
+                        chunk_size,
+                    })
+            })?;
+        }
+        #[cfg(feature = "rand")]
+        Command::Keygen {
+            bits,
+            format,
+            output_file,
+            stdout: _,
+            force,
+        } => {
+            if let Some(path) = &output_file {
+                if !force && path.exists() {
+                    return Err(CliError::Exists(path.clone()));
                 }
+            }
+
+            let key = &rand::random::<[u8; 32]>()[..bits as usize / 8];
+            let key = match format {
+                KeyFormat::Raw => key.to_vec(),
+                KeyFormat::Hex => format!("{}\n", hex::encode(key)).into_bytes(),
+                KeyFormat::Base64 => format!("{}\n", base64::encode(key)).into_bytes(),
             };
 
-            output.write_all(&output_bytes)?;
+            write_output(output_file, Some(0o600), |output| output.write_all(&key))?;
         }
+        Command::Info { key_file } => {
+            let key = read_key(key_file, None)?;
+            print!("{}", format_info(&key_strength(key.len() * 8)));
+        }
+        #[cfg(feature = "bench")]
+        Command::Bench { size } => bench(size as usize * 1024 * 1024),
     }
 
     Ok(())
 }
 
-fn read_key(path: PathBuf) -> io::Result<Vec<u8>> {
-    let mut f = File::open(path)?;
-    let meta = f.metadata()?;
+/// Plaintext buffer that is overwritten with zeroes when dropped
+///
+/// Only this buffer is wiped.
+/// Copies outside of it (intermediate buffers of the library, the OS page cache,
+/// swap or the written output itself) are out of reach and may persist.
+#[cfg(feature = "zeroize")]
+struct Zeroizing(Vec<u8>);
 
-    match meta.len() {
-        16 | 24 | 32 => (),
-        _ => {
-            log::error!("The key must have a size of 128, 192 or 256 bits (16, 24 or 32 bytes)");
-            process::exit(1);
+#[cfg(feature = "zeroize")]
+impl Zeroizing {
+    fn wipe(&mut self) {
+        for byte in self.0.iter_mut() {
+            // SAFETY: `byte` is a valid, aligned reference into the buffer
+            unsafe { std::ptr::write_volatile(byte, 0) };
+        }
+
+        for byte in self.0.spare_capacity_mut() {
+            // SAFETY: `byte` points into the allocated but unused capacity of the buffer
+            unsafe { std::ptr::write_volatile(byte.as_mut_ptr(), 0) };
         }
-    }
 
-    let mut key = Vec::with_capacity(meta.len() as usize);
-    f.read_to_end(&mut key)?;
+        std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+    }
+}
 
-    Ok(key)
+#[cfg(feature = "zeroize")]
+impl Drop for Zeroizing {
+    fn drop(&mut self) {
+        self.wipe();
+    }
 }
 
-fn read_iv(path: PathBuf) -> io::Result<[u8; 16]> {
-    let mut f = File::open(path)?;
-    let meta = f.metadata()?;
+#[cfg(feature = "zeroize")]
+impl std::ops::Deref for Zeroizing {
+    type Target = [u8];
 
-    if meta.len() != 16 {
-        log::error!("The IV must have a size of 128 bits (16 bytes)");
-        process::exit(1);
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl std::ops::DerefMut for Zeroizing {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
     }
+}
+
+const KEY_SOURCE_REQUIRED: &str =
+    "Exactly one of --key-file, --key-hex, --key-base64 and --passphrase is required";
+const IV_REQUIRED: &str = "CBC mode needs exactly one IV option";
+const MODE_CONFLICT: &str = "Only one of --cbc and --ecb can be given";
+
+/// Errors of the CLI that are reported by [main]
+#[derive(Debug)]
+enum CliError {
+    /// A file could not be opened or read
+    File(PathBuf, io::Error),
+
+    /// The key has a size (in bytes) that is not allowed
+    KeySize(u64),
+
+    /// The key has a size (in bytes) that doesn't match the requested AES variant (in bits)
+    KeyBits(u64, u64),
+
+    /// The IV file has a size (in bytes) that is not allowed
+    IvSize(u64),
+
+    /// The sidecar file could not be parsed
+    Meta(PathBuf, &'static str),
+
+    /// The IV counter could not be read or incremented
+    Counter(PathBuf, &'static str),
+
+    /// The given padding contradicts the one of the sidecar file
+    PaddingMismatch(PaddingOption, PaddingOption),
+
+    /// The ciphertext could not be decrypted
+    Decrypt(DecryptError),
+
+    /// The HMAC tag of the input is missing or doesn't match
+    Authentication(EtmError),
+
+    /// The passphrase could not be read from an environment variable
+    Env(String, env::VarError),
+
+    /// The key derivation header of the input could not be parsed
+    KdfHeader(&'static str),
+
+    /// The output file exists and may not be overwritten
+    #[cfg(feature = "rand")]
+    Exists(PathBuf),
+
+    /// The input (in bytes) is shorter than the prepended IV
+    PrependedIv(usize),
 
-    let mut iv: [u8; 16] = Default::default();
-    f.read_exact(&mut iv)?;
+    /// The input is shorter than the bytes to skip
+    Skip(usize, usize),
 
-    Ok(iv)
+    /// The arguments are inconsistent in a way the argument parser doesn't catch
+    Args(&'static str),
+
+    /// Any other I/O error
+    Io(io::Error),
 }
 
-fn read_file(path: PathBuf) -> io::Result<Vec<u8>> {
-    let mut f = File::open(path)?;
-    let meta = f.metadata()?;
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::File(path, err) => write!(f, "Cannot read {}: {err}", path.display()),
+            CliError::KeySize(size) => write!(
+                f,
+                "The key must have a size of 128, 192 or 256 bits (16, 24 or 32 bytes), not {size} bytes"
+            ),
+            CliError::KeyBits(bits, size) => write!(
+                f,
+                "AES-{bits} needs a key of {} bytes, but the key has {size} bytes",
+                bits / 8
+            ),
+            CliError::IvSize(size) => write!(
+                f,
+                "The IV must have a size of 128 bits (16 bytes), not {size} bytes"
+            ),
+            CliError::Meta(path, err) => write!(f, "Invalid sidecar file {}: {err}", path.display()),
+            CliError::Counter(path, err) => {
+                write!(f, "Invalid IV counter file {}: {err}", path.display())
+            }
+            CliError::PaddingMismatch(padding, meta_padding) => write!(
+                f,
+                "The data was padded with {}, not {} (according to the sidecar file)",
+                meta_padding.to_possible_value().unwrap().get_name(),
+                padding.to_possible_value().unwrap().get_name()
+            ),
+            CliError::Decrypt(DecryptError::NotBlockAligned) => write!(
+                f,
+                "Cannot decrypt the input, its size is not a multiple of 16 bytes (is it encrypted at all?)"
+            ),
+            CliError::Decrypt(DecryptError::InvalidPadding(_)) => write!(
+                f,
+                "Cannot decrypt the input, the padding is invalid (wrong key, IV or padding, or corrupted data?)"
+            ),
+            CliError::Authentication(EtmError::AuthenticationFailed) => write!(
+                f,
+                "Cannot decrypt the input, the authentication tag does not match (wrong key or IV, or modified data?)"
+            ),
+            CliError::Authentication(EtmError::InvalidLength) => write!(
+                f,
+                "Cannot decrypt the input, it is too short to contain an authentication tag"
+            ),
+            CliError::Authentication(err) => write!(f, "{err}"),
+            CliError::Env(var, err) => write!(f, "Cannot read the passphrase from ${var}: {err}"),
+            CliError::KdfHeader(err) => write!(f, "Invalid key derivation header: {err}"),
+            #[cfg(feature = "rand")]
+            CliError::Exists(path) => write!(
+                f,
+                "{} already exists, use --force to overwrite it",
+                path.display()
+            ),
+            CliError::PrependedIv(len) => write!(
+                f,
+                "The input has only {len} bytes, but the IV of 16 bytes is expected in front of the ciphertext"
+            ),
+            CliError::Skip(skip_bytes, len) => write!(
+                f,
+                "Cannot skip {skip_bytes} bytes of an input that only has {len} bytes"
+            ),
+            CliError::Args(err) => write!(f, "{err}"),
+            CliError::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
 
-    let mut file = Vec::with_capacity(meta.len() as usize);
-    f.read_to_end(&mut file)?;
+impl From<io::Error> for CliError {
+    fn from(err: io::Error) -> Self {
+        // the decrypting reader reports its errors as I/O errors
+        let Some(inner) = err.get_ref() else {
+            return CliError::Io(err);
+        };
 
-    Ok(file)
+        if let Some(&err) = inner.downcast_ref::<DecryptError>() {
+            CliError::Decrypt(err)
+        } else if let Some(&err) = inner.downcast_ref::<EtmError>() {
+            match err {
+                EtmError::Decrypt(err) => CliError::Decrypt(err),
+                err => CliError::Authentication(err),
+            }
+        } else {
+            CliError::Io(err)
+        }
+    }
 }
 
-fn read_stdin() -> io::Result<Vec<u8>> {
-    let stdin = io::stdin();
-    let mut buffer = Vec::new();
+fn read_key(path: PathBuf, aes_bits: Option<u64>) -> Result<Vec<u8>, CliError> {
+    check_key_size(read_file(path)?, aes_bits)
+}
 
-    {
-        let mut stdin = stdin.lock();
-        stdin.read_to_end(&mut buffer)?;
+fn check_key_size(key: Vec<u8>, aes_bits: Option<u64>) -> Result<Vec<u8>, CliError> {
+    match (key.len(), aes_bits) {
+        (size, Some(bits)) if size as u64 * 8 != bits => Err(CliError::KeyBits(bits, size as u64)),
+        (16 | 24 | 32, _) => Ok(key),
+        (size, _) => Err(CliError::KeySize(size as u64)),
     }
+}
+
+fn read_iv(path: PathBuf) -> Result<[u8; 16], CliError> {
+    let iv = read_file(path)?;
 
-    Ok(buffer)
+    iv.as_slice()
+        .try_into()
+        .map_err(|_| CliError::IvSize(iv.len() as u64))
 }
 
-fn write_iv(path: PathBuf, iv: &InitializationVector) -> io::Result<()> {
-    let mut f = File::create(path)?;
-    f.write_all(&iv.as_bytes())?;
+/// Increment the counter of the state file and return the new value
+///
+/// A missing state file starts at zero. The new value is persisted before it is used.
+/// Concurrent runs wait for an exclusive lock on `<STATE_FILE>.lock`,
+/// the state file itself is replaced on every run and can't hold the lock.
+fn next_counter(path: PathBuf) -> Result<u128, CliError> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let lock_path = path.with_file_name(format!("{file_name}.lock"));
+    let lock = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|err| CliError::File(lock_path.clone(), err))?;
+    // released when the file is closed, even if the process is killed
+    lock.lock().map_err(|err| CliError::File(lock_path, err))?;
 
-    Ok(())
+    let last = match fs::read_to_string(&path) {
+        Ok(contents) => contents
+            .trim()
+            .parse::<u128>()
+            .map_err(|_| CliError::Counter(path.clone(), "Not a counter"))?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => 0,
+        Err(err) => return Err(CliError::File(path, err)),
+    };
+
+    let counter = last
+        .checked_add(1)
+        .ok_or(CliError::Counter(path.clone(), "Counter is exhausted"))?;
+    write_file_atomically(&path, None, |f| {
+        f.write_all(format!("{counter}\n").as_bytes())
+    })?;
+
+    Ok(counter)
 }
 
-fn encrypt<const N: usize, K>(
-    plaintext: &[u8],
-    key: &K,
-    padding: PaddingOption,
-    mode: EncryptionMode,
-) -> Vec<u8>
-where
-    K: Key<N>,
-{
-    match padding {
-        PaddingOption::Pkcs7 => encrypt_bytes(plaintext, key, &Pkcs7Padding, mode),
-        PaddingOption::Zero | PaddingOption::None => {
-            encrypt_bytes(plaintext, key, &ZeroPadding, mode)
+fn counter_iv(key: &[u8], counter: u128) -> InitializationVector {
+    struct CounterIv(u128);
+
+    impl KeyVisitor for CounterIv {
+        type Output = InitializationVector;
+
+        fn visit<const R: usize, K>(self, key: &K) -> InitializationVector
+        where
+            K: Key<R>,
+        {
+            InitializationVector::from_counter(self.0, key)
         }
     }
+
+    AnyKey::from_slice(key)
+        .expect("Key size is checked when reading the key")
+        .visit(CounterIv(counter))
 }
 
-fn decrypt<const N: usize, K>(
-    ciphertext: &[u8],
-    key: &K,
-    padding: PaddingOption,
-    mode: EncryptionMode,
-) -> Vec<u8>
-where
-    K: Key<N>,
-{
-    match padding {
-        PaddingOption::Pkcs7 => decrypt_bytes(ciphertext, key, Some(Pkcs7Padding), mode).unwrap(),
-        PaddingOption::Zero => decrypt_bytes(ciphertext, key, Some(ZeroPadding), mode).unwrap(),
-        PaddingOption::None => decrypt_bytes(ciphertext, key, None::<ZeroPadding>, mode).unwrap(),
+/// Number of PBKDF2 iterations if none are given
+///
+/// As recommended by [OWASP](https://cheatsheetseries.owasp.org/cheatsheets/Password_Storage_Cheat_Sheet.html#pbkdf2).
+const DEFAULT_ITERATIONS: u32 = 600_000;
+
+/// scrypt cost (as a power of two) if none is given
+///
+/// With r = 8, this needs 128 MiB of memory, see [RFC 7914](https://www.rfc-editor.org/rfc/rfc7914#section-2).
+const DEFAULT_SCRYPT_LOG_N: u8 = 17;
+
+/// Highest accepted scrypt cost (as a power of two)
+const MAX_SCRYPT_LOG_N: u8 = 40;
+
+/// Most memory (in bytes) a key derivation may use
+///
+/// Enough for the first recommended option of [RFC 9106](https://www.rfc-editor.org/rfc/rfc9106#section-4) (2 GiB),
+/// but a crafted header or sidecar file can't make the allocation fail.
+const MAX_KDF_MEMORY: u64 = 4 << 30;
+
+/// Argon2id memory (in KiB), iterations and parallelism if none are given
+///
+/// This is the second recommended option of [RFC 9106](https://www.rfc-editor.org/rfc/rfc9106#section-4),
+/// the first one needs 2 GiB of memory.
+const DEFAULT_ARGON2: (u32, u32, u32) = (1 << 16, 3, 4);
+
+/// Choose the key derivation parameters
+///
+/// Explicit arguments take precedence over the parameters of the sidecar file (`fallback`),
+/// which take precedence over the defaults.
+fn kdf_params(kdf: Option<KdfOption>, cost: &KdfCost, fallback: Option<KdfParams>) -> KdfParams {
+    let given = [
+        (KdfOption::Pbkdf2, "--iterations", cost.iterations.is_some()),
+        (
+            KdfOption::Scrypt,
+            "--scrypt-log-n",
+            cost.scrypt_log_n.is_some(),
+        ),
+        (
+            KdfOption::Argon2id,
+            "--argon2-memory",
+            cost.argon2_memory.is_some(),
+        ),
+        (
+            KdfOption::Argon2id,
+            "--argon2-iterations",
+            cost.argon2_iterations.is_some(),
+        ),
+        (
+            KdfOption::Argon2id,
+            "--argon2-parallelism",
+            cost.argon2_parallelism.is_some(),
+        ),
+    ];
+
+    let kdf = kdf
+        .or(given
+            .iter()
+            .find(|(_, _, given)| *given)
+            .map(|(kdf, ..)| *kdf))
+        .or(fallback.map(|params| params.option()))
+        .unwrap_or(KdfOption::Pbkdf2);
+
+    if let Some((_, arg, _)) = given
+        .iter()
+        .find(|&&(option, _, given)| given && option != kdf)
+    {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "{arg} cannot be used with --kdf {}",
+                    kdf.to_possible_value().unwrap().get_name()
+                ),
+            )
+            .exit();
+    }
+
+    let params = match kdf {
+        KdfOption::Pbkdf2 => {
+            let fallback = match fallback {
+                Some(KdfParams::Pbkdf2 { iterations }) => iterations,
+                _ => DEFAULT_ITERATIONS,
+            };
+            KdfParams::Pbkdf2 {
+                iterations: cost.iterations.unwrap_or(fallback),
+            }
+        }
+        KdfOption::Scrypt => {
+            let (log_n, r, p) = match fallback {
+                Some(KdfParams::Scrypt { log_n, r, p }) => (log_n, r, p),
+                _ => (DEFAULT_SCRYPT_LOG_N, 8, 1),
+            };
+            KdfParams::Scrypt {
+                log_n: cost.scrypt_log_n.unwrap_or(log_n),
+                r,
+                p,
+            }
+        }
+        KdfOption::Argon2id => {
+            let (memory, iterations, parallelism) = match fallback {
+                Some(KdfParams::Argon2id {
+                    memory,
+                    iterations,
+                    parallelism,
+                }) => (memory, iterations, parallelism),
+                _ => DEFAULT_ARGON2,
+            };
+            KdfParams::Argon2id {
+                memory: cost.argon2_memory.unwrap_or(memory),
+                iterations: cost.argon2_iterations.unwrap_or(iterations),
+                parallelism: cost.argon2_parallelism.unwrap_or(parallelism),
+            }
+        }
+    };
+
+    if let Err(err) = params.validate() {
+        Cli::command().error(ErrorKind::ValueValidation, err).exit();
+    }
+    params
+}
+
+fn derive_key(passphrase: &str, derivation: &KeyDerivation, aes_bits: Option<u64>) -> Vec<u8> {
+    match aes_bits {
+        Some(128) => derive::<16>(passphrase.as_bytes(), derivation).to_vec(),
+        Some(192) => derive::<24>(passphrase.as_bytes(), derivation).to_vec(),
+        _ => derive::<32>(passphrase.as_bytes(), derivation).to_vec(),
+    }
+}
+
+fn derive<const N: usize>(passphrase: &[u8], derivation: &KeyDerivation) -> [u8; N] {
+    let salt = &derivation.salt;
+
+    match derivation.params {
+        KdfParams::Pbkdf2 { iterations } => pbkdf2_key(passphrase, salt, iterations),
+        KdfParams::Scrypt { log_n, r, p } => {
+            scrypt_key(passphrase, salt, 1 << log_n, r as usize, p as usize)
+        }
+        KdfParams::Argon2id {
+            memory,
+            iterations,
+            parallelism,
+        } => argon2id_key(passphrase, salt, memory, iterations, parallelism),
+    }
+}
+
+#[cfg(feature = "rand")]
+fn random_salt() -> Vec<u8> {
+    rand::random::<[u8; 16]>().to_vec()
+}
+
+/// Read a line from the terminal without echoing it
+#[cfg(unix)]
+fn prompt_passphrase(prompt: &str) -> io::Result<String> {
+    use std::io::BufRead;
+    use std::os::unix::io::AsRawFd;
+
+    let mut tty = File::options().read(true).write(true).open("/dev/tty")?;
+    tty.write_all(prompt.as_bytes())?;
+
+    let fd = tty.as_raw_fd();
+    // SAFETY: `termios` is plain data that `tcgetattr` fills in
+    let mut termios = unsafe { std::mem::zeroed::<libc::termios>() };
+    // SAFETY: `fd` is open for the lifetime of `tty`
+    if unsafe { libc::tcgetattr(fd, &mut termios) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let original = termios;
+    termios.c_lflag &= !libc::ECHO;
+    // SAFETY: see above
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &termios) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut line = String::new();
+    let read = io::BufReader::new(&tty).read_line(&mut line);
+
+    // SAFETY: see above
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &original) };
+    tty.write_all(b"\n")?;
+    read?;
+
+    Ok(line.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+/// Read a line from STDIN, the terminal echoes it
+#[cfg(not(unix))]
+fn prompt_passphrase(prompt: &str) -> io::Result<String> {
+    eprint!("{prompt}");
+
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+
+    Ok(line.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+/// Header, key and IV of the `openssl enc` format with the given salt
+#[cfg(feature = "openssl-compat")]
+fn openssl_key(
+    passphrase: &str,
+    salt: [u8; 8],
+    aes_bits: Option<u64>,
+) -> (Vec<u8>, Vec<u8>, InitializationVector) {
+    let key_len = aes_bits.unwrap_or(256) as usize / 8;
+    let (key, iv) = interop::evp_bytes_to_key(passphrase.as_bytes(), &salt, key_len);
+
+    ([&interop::SALTED_MAGIC[..], &salt].concat(), key, iv)
+}
+
+/// Read the IV that `encrypt --prepend-iv` wrote in front of the ciphertext
+fn read_prepended_iv(input: &mut impl Read) -> Result<InitializationVector, CliError> {
+    let mut iv = Vec::with_capacity(16);
+    input.take(16).read_to_end(&mut iv)?;
+
+    match iv.try_into() {
+        Ok(iv) => Ok(InitializationVector::from_bytes(iv)),
+        Err(iv) => Err(CliError::PrependedIv(iv.len())),
+    }
+}
+
+/// Magic bytes that start the key derivation header in front of the ciphertext
+const KDF_HEADER_MAGIC: &[u8; 8] = b"aesculap";
+
+/// Version of the key derivation header
+const KDF_HEADER_VERSION: u8 = 1;
+
+/// Describe the key derivation as binary header
+///
+/// The header consists of the magic bytes, the version, the key derivation function (1 = PBKDF2, 2 = scrypt, 3 = Argon2id),
+/// the length of the salt, the salt and the cost parameters as big-endian numbers.
+fn format_kdf_header(derivation: &KeyDerivation) -> Vec<u8> {
+    let mut header = KDF_HEADER_MAGIC.to_vec();
+    header.push(KDF_HEADER_VERSION);
+
+    let params = match derivation.params {
+        KdfParams::Pbkdf2 { iterations } => {
+            header.push(1);
+            iterations.to_be_bytes().to_vec()
+        }
+        KdfParams::Scrypt { log_n, r, p } => {
+            header.push(2);
+            [&[log_n][..], &r.to_be_bytes(), &p.to_be_bytes()].concat()
+        }
+        KdfParams::Argon2id {
+            memory,
+            iterations,
+            parallelism,
+        } => {
+            header.push(3);
+            [memory, iterations, parallelism]
+                .iter()
+                .flat_map(|n| n.to_be_bytes())
+                .collect()
+        }
+    };
+
+    header.push(derivation.salt.len() as u8);
+    header.extend(&derivation.salt);
+    header.extend(params);
+
+    header
+}
+
+/// Read the header written by [format_kdf_header]
+fn read_kdf_header(input: &mut impl Read) -> Result<KeyDerivation, CliError> {
+    fn read_exact(input: &mut impl Read, buf: &mut [u8]) -> Result<(), CliError> {
+        input.read_exact(buf).map_err(|err| match err.kind() {
+            io::ErrorKind::UnexpectedEof => CliError::KdfHeader("Unexpected end of input"),
+            _ => err.into(),
+        })
+    }
+    fn read<const N: usize>(input: &mut impl Read) -> Result<[u8; N], CliError> {
+        let mut buf = [0; N];
+        read_exact(input, &mut buf)?;
+        Ok(buf)
+    }
+    fn read_u32(input: &mut impl Read) -> Result<u32, CliError> {
+        read(input).map(u32::from_be_bytes)
+    }
+
+    if read(input)? != *KDF_HEADER_MAGIC {
+        return Err(CliError::KdfHeader(
+            "Not found, the salt has to be given with --salt or in a sidecar file",
+        ));
+    }
+    if read(input)? != [KDF_HEADER_VERSION] {
+        return Err(CliError::KdfHeader("Unknown version"));
+    }
+
+    let [kdf, salt_len] = read(input)?;
+    let mut salt = vec![0; salt_len as usize];
+    read_exact(input, &mut salt)?;
+
+    let params = match kdf {
+        1 => KdfParams::Pbkdf2 {
+            iterations: read_u32(input)?,
+        },
+        2 => {
+            let [log_n] = read(input)?;
+            KdfParams::Scrypt {
+                log_n,
+                r: read_u32(input)?,
+                p: read_u32(input)?,
+            }
+        }
+        3 => KdfParams::Argon2id {
+            memory: read_u32(input)?,
+            iterations: read_u32(input)?,
+            parallelism: read_u32(input)?,
+        },
+        _ => return Err(CliError::KdfHeader("Unknown key derivation function")),
+    };
+
+    let valid = match params {
+        KdfParams::Pbkdf2 { iterations } => iterations > 0,
+        KdfParams::Scrypt { log_n, r, p } => {
+            (1..=MAX_SCRYPT_LOG_N).contains(&log_n) && r > 0 && p > 0
+        }
+        KdfParams::Argon2id {
+            iterations,
+            parallelism,
+            ..
+        } => iterations > 0 && (1..1 << 24).contains(&parallelism),
+    };
+    if !valid || params.validate().is_err() {
+        return Err(CliError::KdfHeader("Invalid cost parameters"));
+    }
+
+    Ok(KeyDerivation { salt, params })
+}
+
+fn read_file(path: PathBuf) -> Result<Vec<u8>, CliError> {
+    fs::read(&path).map_err(|err| CliError::File(path, err))
+}
+
+fn open_input(input: Input) -> Result<Box<dyn Read>, CliError> {
+    match (input.input_file, input.stdin) {
+        (Some(path), false) => match File::open(&path) {
+            Ok(f) => Ok(Box::new(f)),
+            Err(err) => Err(CliError::File(path, err)),
+        },
+        (None, true) => Ok(Box::new(io::stdin().lock())),
+        _ => Err(CliError::Args(
+            "Exactly one of --input-file and --stdin is required",
+        )),
+    }
+}
+
+fn read_header(input: &mut impl Read, skip_bytes: usize) -> Result<Vec<u8>, CliError> {
+    let mut header = Vec::with_capacity(skip_bytes);
+    input.take(skip_bytes as u64).read_to_end(&mut header)?;
+
+    if header.len() < skip_bytes {
+        return Err(CliError::Skip(skip_bytes, header.len()));
+    }
+
+    Ok(header)
+}
+
+fn parse_salt(salt: &str) -> Result<Salt, String> {
+    match hex::decode(salt) {
+        Ok(salt) if !salt.is_empty() => Ok(Salt(salt)),
+        _ => Err("The salt must consist of an even number of hex digits".to_owned()),
+    }
+}
+
+fn parse_key_hex(key: &str) -> Result<KeyBytes, String> {
+    // the size is checked like the one of a key file
+    hex::decode(key).map(KeyBytes).map_err(|err| {
+        match err {
+            HexError::InvalidDigit => "The key must consist of hex digits only",
+            HexError::OddLength => "The key must consist of an even number of hex digits",
+        }
+        .to_owned()
+    })
+}
+
+fn parse_key_base64(key: &str) -> Result<KeyBytes, String> {
+    base64::decode(key).map(KeyBytes).map_err(str::to_owned)
+}
+
+fn parse_aes_bits(bits: &str) -> Result<u64, String> {
+    match bits.parse() {
+        Ok(bits @ (128 | 192 | 256)) => Ok(bits),
+        _ => Err(format!("'{bits}' is not one of 128, 192 or 256")),
+    }
+}
+
+fn parse_permissions(mode: &str) -> Result<u32, String> {
+    match u32::from_str_radix(mode, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        _ => Err(format!("'{mode}' is not an octal file mode")),
+    }
+}
+
+/// Resolve the output file, `None` means STDOUT
+fn output_path(output: Output, input: &Input) -> Result<Option<PathBuf>, CliError> {
+    match (output.output_file, output.stdout, output.in_place) {
+        (Some(path), false, false) => Ok(Some(path)),
+        (None, true, false) => Ok(None),
+        (None, false, true) => match &input.input_file {
+            Some(path) => Ok(Some(path.clone())),
+            None => Err(CliError::Args("--in-place needs an --input-file")),
+        },
+        _ => Err(CliError::Args(
+            "Exactly one of --output-file, --stdout and --in-place is required",
+        )),
+    }
+}
+
+fn write_output(
+    path: Option<PathBuf>,
+    permissions: Option<u32>,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    if let Some(path) = path {
+        return write_file_atomically(&path, permissions, write);
+    }
+
+    let mut stdout = io::stdout().lock();
+    write(&mut stdout)?;
+    stdout.flush()
+}
+
+/// Write to a temporary file next to `path` and rename it on success
+///
+/// This way an interrupted or failed write never leaves a truncated file at `path`.
+/// If no permissions are given, an existing file at `path` keeps its permissions (Unix only).
+fn write_file_atomically(
+    path: &Path,
+    permissions: Option<u32>,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{file_name}.{}.tmp", process::id()));
+
+    #[cfg(unix)]
+    let permissions = permissions.or_else(|| {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path)
+            .ok()
+            .map(|m| m.permissions().mode() & 0o7777)
+    });
+
+    #[cfg(unix)]
+    let _temp_guard = interrupt::remove_on_interrupt(&temp_path);
+
+    let result = create_file(&temp_path, permissions).and_then(|mut f| {
+        write(&mut f)?;
+        f.sync_all()
+    });
+
+    match result.and_then(|_| fs::rename(&temp_path, path)) {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            let _ = fs::remove_file(&temp_path);
+            Err(err)
+        }
+    }
+}
+
+/// Create a new file, on Unix with the given permissions
+fn create_file(path: &Path, permissions: Option<u32>) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    if let Some(mode) = permissions {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(mode);
+    }
+
+    let f = options.open(path)?;
+
+    #[cfg(unix)]
+    if let Some(mode) = permissions {
+        // the mode passed to open is restricted by the umask
+        use std::os::unix::fs::PermissionsExt;
+        f.set_permissions(fs::Permissions::from_mode(mode))?;
+    }
+
+    #[cfg(not(unix))]
+    if permissions.is_some() {
+        log::warn!("Output permissions are only supported on Unix");
+    }
+
+    Ok(f)
+}
+
+/// Cleanup when the user presses Ctrl-C (SIGINT)
+///
+/// The signal handler wipes the registered key bytes, removes the temporary output file
+/// and exits with status 130.
+/// It may only use async-signal-safe calls, so everything it touches is registered up front.
+/// Copies of the key inside the library are not reachable,
+/// but they go away with the process like all other memory.
+#[cfg(unix)]
+mod interrupt {
+    use std::ffi::CString;
+    use std::ops::Deref;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::ptr;
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+    static KEY: AtomicPtr<u8> = AtomicPtr::new(ptr::null_mut());
+    static KEY_LEN: AtomicUsize = AtomicUsize::new(0);
+    static TEMP_PATH: AtomicPtr<libc::c_char> = AtomicPtr::new(ptr::null_mut());
+
+    pub fn install() {
+        let handler: extern "C" fn(libc::c_int) = handle;
+
+        // SAFETY: the handler only performs async-signal-safe operations
+        unsafe { libc::signal(libc::SIGINT, handler as libc::sighandler_t) };
+    }
+
+    extern "C" fn handle(_signal: libc::c_int) {
+        let key = KEY.load(Ordering::SeqCst);
+        if !key.is_null() {
+            for i in 0..KEY_LEN.load(Ordering::SeqCst) {
+                // SAFETY: the key stays registered only while the guard borrows it
+                unsafe { ptr::write_volatile(key.add(i), 0) };
+            }
+        }
+
+        let temp_path = TEMP_PATH.load(Ordering::SeqCst);
+        if !temp_path.is_null() {
+            // SAFETY: the path stays registered only while the guard owns it
+            unsafe { libc::unlink(temp_path) };
+        }
+
+        // SAFETY: `_exit` is async-signal-safe, unlike `process::exit`
+        unsafe { libc::_exit(130) };
+    }
+
+    /// Key bytes that stay registered until dropped
+    pub struct KeyGuard(Vec<u8>);
+
+    pub fn wipe_on_interrupt(mut key: Vec<u8>) -> KeyGuard {
+        KEY_LEN.store(key.len(), Ordering::SeqCst);
+        KEY.store(key.as_mut_ptr(), Ordering::SeqCst);
+
+        KeyGuard(key)
+    }
+
+    impl Deref for KeyGuard {
+        type Target = [u8];
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl Drop for KeyGuard {
+        fn drop(&mut self) {
+            KEY.store(ptr::null_mut(), Ordering::SeqCst);
+            KEY_LEN.store(0, Ordering::SeqCst);
+        }
+    }
+
+    /// Keeps the temporary file registered until dropped
+    pub struct TempFileGuard {
+        _path: CString,
+    }
+
+    pub fn remove_on_interrupt(path: &Path) -> TempFileGuard {
+        let path = CString::new(path.as_os_str().as_bytes()).unwrap_or_default();
+        TEMP_PATH.store(path.as_ptr().cast_mut(), Ordering::SeqCst);
+
+        TempFileGuard { _path: path }
+    }
+
+    impl Drop for TempFileGuard {
+        fn drop(&mut self) {
+            TEMP_PATH.store(ptr::null_mut(), Ordering::SeqCst);
+        }
+    }
+}
+
+/// Path of the sidecar file that belongs to `path`
+fn meta_path(path: &Path) -> PathBuf {
+    let mut meta_path = path.as_os_str().to_owned();
+    meta_path.push(".meta");
+    meta_path.into()
+}
+
+/// Contents of a sidecar file
+struct Meta {
+    mode: EncryptionMode,
+
+    /// Padding, if the sidecar file names one
+    padding: Option<PaddingOption>,
+
+    /// Key derivation if the key was derived from a passphrase
+    kdf: Option<KeyDerivation>,
+}
+
+/// Salt and parameters of a key derivation
+#[derive(Debug, PartialEq, Eq)]
+struct KeyDerivation {
+    salt: Vec<u8>,
+    params: KdfParams,
+}
+
+/// Key derivation function with its cost parameters
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum KdfParams {
+    Pbkdf2 {
+        iterations: u32,
+    },
+    Scrypt {
+        log_n: u8,
+        r: u32,
+        p: u32,
+    },
+    Argon2id {
+        memory: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+}
+
+impl KdfParams {
+    fn option(self) -> KdfOption {
+        match self {
+            KdfParams::Pbkdf2 { .. } => KdfOption::Pbkdf2,
+            KdfParams::Scrypt { .. } => KdfOption::Scrypt,
+            KdfParams::Argon2id { .. } => KdfOption::Argon2id,
+        }
+    }
+
+    /// Check that the parameters can be used without a panic and within [MAX_KDF_MEMORY]
+    ///
+    /// The individual parameters are checked when they are parsed, only their combination is left.
+    fn validate(self) -> Result<(), &'static str> {
+        let memory = match self {
+            KdfParams::Pbkdf2 { .. } => 0,
+            KdfParams::Scrypt { log_n, r, p } => {
+                // see https://www.rfc-editor.org/rfc/rfc7914#section-6
+                if r as u64 * p as u64 >= 1 << 30 {
+                    return Err("scrypt parameters must satisfy p * r < 2^30");
+                }
+                128 * r as u64 * ((1u64 << log_n) + p as u64)
+            }
+            KdfParams::Argon2id {
+                memory,
+                parallelism,
+                ..
+            } => {
+                if memory / 8 < parallelism {
+                    return Err("Argon2id needs at least 8 KiB of memory per lane");
+                }
+                memory as u64 * 1024
+            }
+        };
+
+        if memory > MAX_KDF_MEMORY {
+            return Err("Key derivation needs more than 4 GiB of memory");
+        }
+        Ok(())
+    }
+}
+
+/// Describe mode, padding, IV and key derivation as `key=value` lines
+fn format_meta(
+    mode: &EncryptionMode,
+    padding: PaddingOption,
+    kdf: Option<&KeyDerivation>,
+) -> String {
+    let padding = padding.to_possible_value().unwrap();
+
+    let mut meta = match mode {
+        EncryptionMode::ECB => format!("mode=ecb\npadding={}\n", padding.get_name()),
+        EncryptionMode::CBC(iv) => {
+            format!(
+                "mode=cbc\npadding={}\niv={}\n",
+                padding.get_name(),
+                hex::encode(&iv.as_bytes())
+            )
+        }
+        _ => unreachable!("The CLI only supports ECB and CBC"),
+    };
+
+    if let Some(KeyDerivation { salt, params }) = kdf {
+        meta += &format!("salt={}\n", hex::encode(salt));
+        meta += &match params {
+            KdfParams::Pbkdf2 { iterations } => format!("kdf=pbkdf2\niterations={iterations}\n"),
+            KdfParams::Scrypt { log_n, r, p } => {
+                format!("kdf=scrypt\nscrypt-log-n={log_n}\nscrypt-r={r}\nscrypt-p={p}\n")
+            }
+            KdfParams::Argon2id {
+                memory,
+                iterations,
+                parallelism,
+            } => format!(
+                "kdf=argon2id\nargon2-memory={memory}\nargon2-iterations={iterations}\nargon2-parallelism={parallelism}\n"
+            ),
+        };
+    }
+
+    meta
+}
+
+/// Read a sidecar file written by [format_meta]
+fn read_meta(path: PathBuf) -> Result<Meta, CliError> {
+    let contents = read_file(path.clone())?;
+    let invalid = |err| CliError::Meta(path.clone(), err);
+
+    let contents = String::from_utf8(contents).map_err(|_| invalid("Not UTF-8"))?;
+
+    let (mut mode, mut padding, mut iv) = (None, None, None);
+    let (mut salt, mut kdf, mut iterations) = (None, None, None);
+    let (mut log_n, mut r, mut p) = (None, None, None);
+    let (mut memory, mut argon2_iterations, mut parallelism) = (None, None, None);
+    let positive = |value: &str, err| {
+        value
+            .parse::<u32>()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| invalid(err))
+    };
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match line.split_once('=') {
+            Some(("mode", value)) => mode = Some(value),
+            Some(("padding", value)) => {
+                padding = Some(
+                    PaddingOption::from_str(value, false)
+                        .map_err(|_| invalid("Unknown padding"))?,
+                )
+            }
+            Some(("iv", value)) => iv = Some(value.parse().map_err(invalid)?),
+            Some(("salt", value)) => {
+                salt = Some(parse_salt(value).map_err(|_| invalid("Invalid salt"))?.0)
+            }
+            Some(("kdf", value)) => {
+                kdf = Some(KdfOption::from_str(value, false).map_err(|_| invalid("Unknown kdf"))?)
+            }
+            Some(("iterations", value)) => {
+                iterations = Some(positive(value, "Invalid number of iterations")?)
+            }
+            Some(("scrypt-log-n", value)) => {
+                log_n = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|n| (1..=MAX_SCRYPT_LOG_N).contains(n))
+                        .ok_or_else(|| invalid("Invalid scrypt cost"))?,
+                )
+            }
+            Some(("scrypt-r", value)) => r = Some(positive(value, "Invalid scrypt block size")?),
+            Some(("scrypt-p", value)) => p = Some(positive(value, "Invalid scrypt parallelism")?),
+            Some(("argon2-memory", value)) => {
+                memory = Some(positive(value, "Invalid Argon2 memory")?)
+            }
+            Some(("argon2-iterations", value)) => {
+                argon2_iterations = Some(positive(value, "Invalid number of Argon2 iterations")?)
+            }
+            Some(("argon2-parallelism", value)) => {
+                parallelism = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|p| (1..1 << 24).contains(p))
+                        .ok_or_else(|| invalid("Invalid Argon2 parallelism"))?,
+                )
+            }
+            _ => {
+                return Err(invalid(
+                    "Expected mode, padding, iv or key derivation parameters as key=value",
+                ))
+            }
+        }
+    }
+
+    let mode = match (mode, iv) {
+        (Some("ecb"), None) => EncryptionMode::ECB,
+        (Some("cbc"), Some(iv)) => EncryptionMode::CBC(iv),
+        (Some("cbc"), None) => return Err(invalid("CBC mode needs an IV")),
+        (Some("ecb"), Some(_)) => return Err(invalid("ECB mode has no IV")),
+        (Some(_), _) => return Err(invalid("Unknown mode")),
+        (None, _) => return Err(invalid("No mode")),
+    };
+
+    // sidecar files without a kdf line are from before scrypt was supported
+    let scrypt = (log_n, r, p);
+    let argon2 = (memory, argon2_iterations, parallelism);
+    let params = match (kdf, iterations, scrypt, argon2) {
+        (
+            None | Some(KdfOption::Pbkdf2),
+            Some(iterations),
+            (None, None, None),
+            (None, None, None),
+        ) => Some(KdfParams::Pbkdf2 { iterations }),
+        (Some(KdfOption::Scrypt), None, (Some(log_n), Some(r), Some(p)), (None, None, None)) => {
+            Some(KdfParams::Scrypt { log_n, r, p })
+        }
+        (
+            Some(KdfOption::Argon2id),
+            None,
+            (None, None, None),
+            (Some(memory), Some(iterations), Some(parallelism)),
+        ) => Some(KdfParams::Argon2id {
+            memory,
+            iterations,
+            parallelism,
+        }),
+        (None, None, (None, None, None), (None, None, None)) => None,
+        _ => return Err(invalid("Incomplete or mixed key derivation parameters")),
+    };
+    if let Some(params) = params {
+        params.validate().map_err(invalid)?;
+    }
+
+    let kdf = match (salt, params) {
+        (Some(salt), Some(params)) => Some(KeyDerivation { salt, params }),
+        (None, None) => None,
+        _ => {
+            return Err(invalid(
+                "Salt and key derivation parameters have to be given together",
+            ))
+        }
+    };
+
+    Ok(Meta { mode, padding, kdf })
+}
+
+#[cfg(feature = "rand")]
+fn write_iv(path: PathBuf, iv: &InitializationVector) -> io::Result<()> {
+    let mut f = File::create(path)?;
+    f.write_all(&iv.as_bytes())?;
+
+    Ok(())
+}
+
+/// Key of the `--authenticate` HMAC
+fn mac_key(key: &[u8]) -> Vec<u8> {
+    hkdf(key, &[], b"aesculap authenticate", 32)
+}
+
+/// Encrypt `plaintext` to `output` with the key of an [AnyKey]
+///
+/// With `mac` (the MAC key and the associated data), the whole plaintext is read
+/// and the ciphertext is followed by an HMAC tag.
+struct Encrypt<'a, I> {
+    plaintext: I,
+    output: &'a mut dyn Write,
+    padding: PaddingOption,
+    mode: EncryptionMode,
+    mac: Option<(&'a [u8], &'a [u8])>,
+    chunk_size: usize,
+}
+
+impl<I: Read> KeyVisitor for Encrypt<'_, I> {
+    type Output = io::Result<()>;
+
+    fn visit<const N: usize, K>(self, key: &K) -> io::Result<()>
+    where
+        K: Key<N>,
+    {
+        fn encrypt_with<const N: usize, K, P>(
+            mut plaintext: impl Read,
+            output: &mut dyn Write,
+            key: &K,
+            padding: P,
+            mode: EncryptionMode,
+            mac: Option<(&[u8], &[u8])>,
+            chunk_size: usize,
+        ) -> io::Result<()>
+        where
+            K: Key<N>,
+            P: Padding<16>,
+        {
+            if let Some((mac_key, aad)) = mac {
+                let mut bytes = Vec::new();
+                plaintext.read_to_end(&mut bytes)?;
+
+                let data = encrypt_then_mac(&bytes, key, mac_key, &padding, mode, aad)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+                return output.write_all(&data);
+            }
+
+            let mut writer = EncryptingWriter::new(output, key, padding, mode);
+            copy_chunks(plaintext, &mut writer, chunk_size)?;
+            writer.finish()?;
+
+            Ok(())
+        }
+
+        let Encrypt {
+            plaintext,
+            output,
+            padding,
+            mode,
+            mac,
+            chunk_size,
+        } = self;
+
+        match padding {
+            PaddingOption::Pkcs7 => {
+                encrypt_with(plaintext, output, key, Pkcs7Padding, mode, mac, chunk_size)
+            }
+            PaddingOption::AnsiX923 => encrypt_with(
+                plaintext,
+                output,
+                key,
+                AnsiX923Padding,
+                mode,
+                mac,
+                chunk_size,
+            ),
+            PaddingOption::Iso7816 => encrypt_with(
+                plaintext,
+                output,
+                key,
+                Iso7816Padding,
+                mode,
+                mac,
+                chunk_size,
+            ),
+            PaddingOption::Zero => {
+                encrypt_with(plaintext, output, key, ZeroPadding, mode, mac, chunk_size)
+            }
+            PaddingOption::None => encrypt_with(
+                plaintext, output, key, NoPadding, mode, mac, chunk_size,
+            )
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::InvalidInput => io::Error::new(
+                    err.kind(),
+                    "Without padding the number of input bytes has to be divisible by 16",
+                ),
+                _ => err,
+            }),
+        }
+    }
+}
+
+/// Decrypt `ciphertext` to `output` with the key of an [AnyKey]
+///
+/// With `mac` (the MAC key and the associated data), the whole ciphertext is read
+/// and nothing is written unless its HMAC tag matches.
+/// Then the associated data is written in front of the plaintext.
+struct Decrypt<'a, I> {
+    ciphertext: I,
+    output: &'a mut dyn Write,
+    padding: PaddingOption,
+    mode: EncryptionMode,
+    mac: Option<(&'a [u8], &'a [u8])>,
+    chunk_size: usize,
+}
+
+impl<I: Read> KeyVisitor for Decrypt<'_, I> {
+    type Output = io::Result<()>;
+
+    fn visit<const N: usize, K>(self, key: &K) -> io::Result<()>
+    where
+        K: Key<N>,
+    {
+        fn decrypt_with<const N: usize, K, P>(
+            mut ciphertext: impl Read,
+            output: &mut dyn Write,
+            key: &K,
+            padding: P,
+            mode: EncryptionMode,
+            mac: Option<(&[u8], &[u8])>,
+            chunk_size: usize,
+        ) -> io::Result<()>
+        where
+            K: Key<N>,
+            P: Padding<16>,
+        {
+            if let Some((mac_key, aad)) = mac {
+                let mut bytes = Vec::new();
+                ciphertext.read_to_end(&mut bytes)?;
+
+                let plaintext = verify_then_decrypt(&bytes, key, mac_key, Some(padding), mode, aad)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                output.write_all(aad)?;
+                return output.write_all(&plaintext);
+            }
+
+            let reader =
+                DecryptingReader::with_capacity(chunk_size, ciphertext, key, Some(padding), mode);
+            copy_chunks(reader, output, chunk_size)
+        }
+
+        let Decrypt {
+            ciphertext,
+            output,
+            padding,
+            mode,
+            mac,
+            chunk_size,
+        } = self;
+
+        match padding {
+            PaddingOption::Pkcs7 => {
+                decrypt_with(ciphertext, output, key, Pkcs7Padding, mode, mac, chunk_size)
+            }
+            PaddingOption::AnsiX923 => decrypt_with(
+                ciphertext,
+                output,
+                key,
+                AnsiX923Padding,
+                mode,
+                mac,
+                chunk_size,
+            ),
+            PaddingOption::Iso7816 => decrypt_with(
+                ciphertext,
+                output,
+                key,
+                Iso7816Padding,
+                mode,
+                mac,
+                chunk_size,
+            ),
+            PaddingOption::Zero => {
+                decrypt_with(ciphertext, output, key, ZeroPadding, mode, mac, chunk_size)
+            }
+            PaddingOption::None => {
+                decrypt_with(ciphertext, output, key, NoPadding, mode, mac, chunk_size)
+            }
+        }
+    }
+}
+
+/// Length of the lines of `--armor` output
+const ARMOR_LINE_LENGTH: usize = 64;
+
+/// Let `write` write to `output`, through a Base64 encoder if the output is armored
+fn write_armored(
+    output: &mut dyn Write,
+    armor: bool,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    if !armor {
+        return write(output);
+    }
+
+    let mut encoder = base64::EncodingWriter::new(output, ARMOR_LINE_LENGTH);
+    write(&mut encoder)?;
+    encoder.finish()?;
+
+    Ok(())
+}
+
+/// Pass everything from `reader` to `writer` in chunks of `chunk_size` bytes
+///
+/// Unlike [io::copy], this keeps the plaintext in a single buffer of a known size.
+fn copy_chunks(mut reader: impl Read, writer: &mut dyn Write, chunk_size: usize) -> io::Result<()> {
+    #[cfg(feature = "zeroize")]
+    let mut buffer = Zeroizing(vec![0; chunk_size]);
+    #[cfg(not(feature = "zeroize"))]
+    let mut buffer = vec![0; chunk_size];
+
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+
+        writer.write_all(&buffer[..n])?;
+    }
+}
+
+/// Assumed speed of a brute-force attack (in keys per second)
+const BRUTE_FORCE_RATE: f64 = 1e12;
+
+/// Text of the `info` subcommand
+fn format_info(info: &KeyStrengthInfo) -> String {
+    format!(
+        "Key size: {} bits (AES-{})\nKey space: 2^{} keys\nBrute force: {:.1e} years at {:e} keys per second\n{}\n",
+        info.key_bits,
+        info.key_bits,
+        info.key_space_log2,
+        info.brute_force_years(BRUTE_FORCE_RATE),
+        BRUTE_FORCE_RATE,
+        info.note
+    )
+}
+
+/// Encrypt a random buffer with every key size and mode and print the throughput
+#[cfg(feature = "bench")]
+fn bench(size: usize) {
+    use rand::RngCore;
+
+    let mut buffer = vec![0; size];
+    rand::thread_rng().fill_bytes(&mut buffer);
+
+    match Backend::detect() {
+        Backend::AesNi => println!("Backend: AES-NI"),
+        #[cfg(target_arch = "x86_64")]
+        Backend::Software => println!(
+            "Backend: software (CPU supports AES-NI: {})",
+            if std::arch::is_x86_feature_detected!("aes") {
+                "yes"
+            } else {
+                "no"
+            }
+        ),
+        #[cfg(not(target_arch = "x86_64"))]
+        Backend::Software => println!("Backend: software"),
+    }
+
+    bench_key("AES-128", &AES128Key::from_bytes(rand::random()), &buffer);
+    bench_key("AES-192", &AES192Key::from_bytes(rand::random()), &buffer);
+    bench_key("AES-256", &AES256Key::from_bytes(rand::random()), &buffer);
+}
+
+#[cfg(feature = "bench")]
+fn bench_key<const N: usize, K>(name: &str, key: &K, buffer: &[u8])
+where
+    K: Key<N>,
+{
+    let modes = [
+        ("ECB", EncryptionMode::ECB),
+        ("CBC", EncryptionMode::CBC(InitializationVector::random())),
+    ];
+
+    for (mode_name, mode) in modes {
+        let start = std::time::Instant::now();
+        let ciphertext = encrypt_bytes(buffer, key, &Pkcs7Padding, mode);
+        let elapsed = start.elapsed();
+        std::hint::black_box(ciphertext);
+
+        let mib = buffer.len() as f64 / (1024.0 * 1024.0);
+        println!(
+            "{name} {mode_name} {:>10.2} MiB/s",
+            mib / elapsed.as_secs_f64()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("aesculap-{}-{name}", process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn read_key_wrong_size() {
+        let path = temp_file("read_key_wrong_size", b"0123456789");
+
+        assert!(matches!(
+            read_key(path.clone(), None),
+            Err(CliError::KeySize(10))
+        ));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn read_iv_too_short() {
+        let path = temp_file("read_iv_too_short", b"0123456789abcde");
+
+        assert!(matches!(read_iv(path.clone()), Err(CliError::IvSize(15))));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn read_missing_file() {
+        let path = std::env::temp_dir().join("aesculap-missing-file");
+
+        for result in [
+            read_file(path.clone()).map(|_| ()),
+            read_key(path.clone(), None).map(|_| ()),
+            read_iv(path.clone()).map(|_| ()),
+        ] {
+            match result {
+                Err(CliError::File(p, err)) => {
+                    assert_eq!(p, path);
+                    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+                }
+                other => panic!("unexpected result: {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn meta_round_trip() {
+        let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+        let path = temp_file(
+            "meta_round_trip",
+            format_meta(&EncryptionMode::CBC(iv), PaddingOption::None, None).as_bytes(),
+        );
+
+        assert!(matches!(
+            read_meta(path.clone()),
+            Ok(Meta { mode: EncryptionMode::CBC(read_iv), padding: Some(PaddingOption::None), kdf: None })
+                if read_iv.as_bytes() == iv.as_bytes()
+        ));
+
+        fs::write(&path, "# comment\nmode=ecb\n").unwrap();
+        assert!(matches!(
+            read_meta(path.clone()),
+            Ok(Meta {
+                mode: EncryptionMode::ECB,
+                padding: None,
+                kdf: None
+            })
+        ));
+
+        for params in [
+            KdfParams::Pbkdf2 { iterations: 1000 },
+            KdfParams::Scrypt {
+                log_n: 4,
+                r: 8,
+                p: 1,
+            },
+            KdfParams::Argon2id {
+                memory: 64,
+                iterations: 1,
+                parallelism: 2,
+            },
+        ] {
+            let kdf = KeyDerivation {
+                salt: b"NaCl".to_vec(),
+                params,
+            };
+            fs::write(
+                &path,
+                format_meta(&EncryptionMode::ECB, PaddingOption::Pkcs7, Some(&kdf)),
+            )
+            .unwrap();
+            assert!(matches!(
+                read_meta(path.clone()),
+                Ok(Meta { kdf: Some(read_kdf), .. }) if read_kdf == kdf
+            ));
+        }
+
+        fs::write(&path, "mode=ecb\nsalt=4e61436c\niterations=1000\n").unwrap();
+        assert!(matches!(
+            read_meta(path.clone()),
+            Ok(Meta {
+                kdf: Some(KeyDerivation {
+                    params: KdfParams::Pbkdf2 { iterations: 1000 },
+                    ..
+                }),
+                ..
+            })
+        ));
+
+        for invalid in [
+            "mode=cbc\n",
+            "mode=ecb\niv=00\n",
+            "mode=ctr\n",
+            "padding=zero\n",
+            "mode=ecb\nsalt=4e61436c\n",
+            "mode=ecb\nsalt=4e61436c\niterations=0\n",
+            "mode=ecb\nsalt=4e61436c\nkdf=scrypt\niterations=1000\n",
+            "mode=ecb\nsalt=4e61436c\nkdf=scrypt\nscrypt-log-n=4\nscrypt-r=8\n",
+            "mode=ecb\nsalt=4e61436c\nkdf=argon2id\nargon2-memory=31\nargon2-iterations=1\nargon2-parallelism=4\n",
+        ] {
+            fs::write(&path, invalid).unwrap();
+            assert!(matches!(read_meta(path.clone()), Err(CliError::Meta(..))));
+        }
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn kdf_header_round_trip() {
+        for params in [
+            KdfParams::Pbkdf2 { iterations: 1000 },
+            KdfParams::Scrypt {
+                log_n: 4,
+                r: 8,
+                p: 1,
+            },
+            KdfParams::Argon2id {
+                memory: 64,
+                iterations: 1,
+                parallelism: 2,
+            },
+        ] {
+            let kdf = KeyDerivation {
+                salt: b"NaCl".to_vec(),
+                params,
+            };
+            let mut header = format_kdf_header(&kdf);
+            header.extend(b"ciphertext");
+
+            let mut input = &header[..];
+            assert_eq!(read_kdf_header(&mut input).unwrap(), kdf);
+            assert_eq!(input, b"ciphertext");
+
+            for len in [0, 8, 9, header.len() - 11] {
+                assert!(matches!(
+                    read_kdf_header(&mut &header[..len]),
+                    Err(CliError::KdfHeader(_))
+                ));
+            }
+        }
+
+        for invalid in [
+            &b"not a header"[..],
+            b"aesculap\x02\x01\x00",
+            b"aesculap\x01\x04\x00",
+            b"aesculap\x01\x01\x00\x00\x00\x00\x00",
+            b"aesculap\x01\x02\x00\x00\x00\x00\x08\x00\x00\x00\x01",
+        ] {
+            assert!(matches!(
+                read_kdf_header(&mut &invalid[..]),
+                Err(CliError::KdfHeader(_))
+            ));
+        }
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroizing_wipes_buffer() {
+        let mut plaintext = b"I use Rust btw".to_vec();
+        plaintext.truncate(5);
+
+        let mut buffer = Zeroizing(plaintext);
+        assert_eq!(&*buffer, b"I use");
+
+        buffer.wipe();
+        assert_eq!(&*buffer, [0; 5]);
+        assert!(buffer.0.spare_capacity_mut().iter().all(|byte| {
+            // SAFETY: the spare capacity was initialized by the original bytes and then wiped
+            unsafe { byte.assume_init() == 0 }
+        }));
     }
 }
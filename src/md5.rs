@@ -0,0 +1,113 @@
+//! MD5 module
+//!
+//! This module provides the MD5 message digest, which is only needed
+//! to derive keys the way OpenSSL's `EVP_BytesToKey` does.
+//! MD5 is broken and must not be used for anything else.
+//!
+//! For reference, see [RFC 1321](https://www.rfc-editor.org/rfc/rfc1321).
+
+/// Per-round shift amounts
+const S: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, //
+    5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, //
+    4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, //
+    6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+/// Integer parts of the sines of the integers (in radians) times 2^32
+const K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+/// Compute the MD5 digest of a byte slice
+pub fn md5(bytes: &[u8]) -> [u8; 16] {
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+    let bit_len = (bytes.len() as u64).wrapping_mul(8);
+
+    let mut message = bytes.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&bit_len.to_le_bytes());
+
+    for chunk in message.chunks_exact(64) {
+        compress(&mut state, chunk.try_into().unwrap());
+    }
+
+    let mut digest = [0; 16];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+
+    digest
+}
+
+/// Process a single 512 bit chunk
+fn compress(state: &mut [u32; 4], chunk: &[u8; 64]) {
+    let m: Vec<u32> = chunk
+        .chunks_exact(4)
+        .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+        .collect();
+
+    let [mut a, mut b, mut c, mut d] = *state;
+
+    for i in 0..64 {
+        let (f, g) = match i / 16 {
+            0 => ((b & c) | (!b & d), i),
+            1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+            2 => (b ^ c ^ d, (3 * i + 5) % 16),
+            _ => (c ^ (b | !d), (7 * i) % 16),
+        };
+
+        let f = f.wrapping_add(a).wrapping_add(K[i]).wrapping_add(m[g]);
+        a = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(f.rotate_left(S[i]));
+    }
+
+    for (s, x) in state.iter_mut().zip([a, b, c, d]) {
+        *s = s.wrapping_add(x);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc1321_test_suite() {
+        // see https://www.rfc-editor.org/rfc/rfc1321#appendix-A.5
+        let vectors: [(&[u8], u128); 7] = [
+            (b"", 0xd41d8cd98f00b204e9800998ecf8427e),
+            (b"a", 0x0cc175b9c0f1b6a831c399e269772661),
+            (b"abc", 0x900150983cd24fb0d6963f7d28e17f72),
+            (b"message digest", 0xf96b697d7cb7938d525a2f31aaf161d0),
+            (
+                b"abcdefghijklmnopqrstuvwxyz",
+                0xc3fcd3d76192e4007dfb496cca67e13b,
+            ),
+            (
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+                0xd174ab98d277d9f5a5611c2c9f419d9f,
+            ),
+            (
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                0x57edf4a22be3c955ac49da2e2107b67a,
+            ),
+        ];
+
+        for (input, expected) in vectors {
+            assert_eq!(md5(input), expected.to_be_bytes());
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Multi-message module
+//!
+//! This module provides functions to write and read a stream of concatenated messages
+//! that were encrypted independently of each other.
+//!
+//! Each message is stored as a record:
+//! the 16 byte [IV](InitializationVector), the length of the ciphertext as 64 bit big-endian integer
+//! and the ciphertext itself ([CBC](crate::EncryptionMode::CBC) with [Pkcs7Padding]).
+
+use std::io::{self, Read};
+
+use crate::decryption::decrypt_bytes_cbc;
+use crate::encryption::encrypt_bytes_cbc;
+use crate::iv::InitializationVector;
+use crate::key::Key;
+use crate::padding::Pkcs7Padding;
+
+/// Encrypt a message into a single record
+///
+/// Records can simply be concatenated and read back with [decrypt_stream_multi].
+pub fn encrypt_message<const R: usize, K>(
+    bytes: &[u8],
+    key: &K,
+    iv: InitializationVector,
+) -> Vec<u8>
+where
+    K: Key<R>,
+{
+    log::trace!("Encrypt message record");
+
+    let ciphertext = encrypt_bytes_cbc(bytes, key, &Pkcs7Padding, iv);
+
+    let mut record = Vec::with_capacity(24 + ciphertext.len());
+    record.extend_from_slice(&iv.as_bytes());
+    record.extend_from_slice(&(ciphertext.len() as u64).to_be_bytes());
+    record.extend_from_slice(&ciphertext);
+
+    record
+}
+
+/// Decrypt a stream of concatenated records
+///
+/// The returned [MultiMessageReader] yields the plaintexts one by one.
+pub fn decrypt_stream_multi<const R: usize, Rd, K>(
+    reader: Rd,
+    key: &K,
+) -> MultiMessageReader<'_, R, Rd, K>
+where
+    Rd: Read,
+    K: Key<R>,
+{
+    MultiMessageReader::new(reader, key)
+}
+
+/// Iterator over the plaintexts of a stream of concatenated records
+///
+/// Reading stops at the end of the stream or after the first error.
+/// A stream that ends in the middle of a record yields an [UnexpectedEof](io::ErrorKind::UnexpectedEof) error,
+/// a record that can't be decrypted an [InvalidData](io::ErrorKind::InvalidData) error.
+pub struct MultiMessageReader<'k, const R: usize, Rd, K> {
+    reader: Rd,
+    key: &'k K,
+    done: bool,
+}
+
+impl<'k, const R: usize, Rd, K> MultiMessageReader<'k, R, Rd, K>
+where
+    Rd: Read,
+    K: Key<R>,
+{
+    /// Constructor that takes the stream and the [Key] used for decryption
+    pub fn new(reader: Rd, key: &'k K) -> Self {
+        Self {
+            reader,
+            key,
+            done: false,
+        }
+    }
+
+    /// Read and decrypt the next record, `None` at the end of the stream
+    fn read_message(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut iv = [0; 16];
+        if !self.read_start(&mut iv)? {
+            return Ok(None);
+        }
+
+        let mut len = [0; 8];
+        self.reader.read_exact(&mut len)?;
+        let len = u64::from_be_bytes(len);
+
+        let mut ciphertext = Vec::new();
+        (&mut self.reader).take(len).read_to_end(&mut ciphertext)?;
+        if (ciphertext.len() as u64) < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let iv = InitializationVector::from_bytes(iv);
+        decrypt_bytes_cbc(&ciphertext, self.key, Some(Pkcs7Padding), iv)
+            .map(Some)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Fill `buf`, `false` if the stream ended before the first byte
+    fn read_start(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        let n = loop {
+            match self.reader.read(buf) {
+                Ok(n) => break n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        };
+
+        if n == 0 {
+            return Ok(false);
+        }
+
+        self.reader.read_exact(&mut buf[n..])?;
+        Ok(true)
+    }
+}
+
+impl<const R: usize, Rd, K> Iterator for MultiMessageReader<'_, R, Rd, K>
+where
+    Rd: Read,
+    K: Key<R>,
+{
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let message = self.read_message().transpose();
+        self.done = !matches!(message, Some(Ok(_)));
+
+        message
+    }
+}
@@ -5,16 +5,116 @@
 //!
 //! Possible padding modes:
 //! - [PKCS7](Pkcs7Padding) (recommended)
+//! - [ANSI X.923](AnsiX923Padding)
+//! - [ISO/IEC 7816-4](Iso7816Padding)
 //! - [Byte padding](BytePadding)
 //! - [Zeroes](ZeroPadding)
+//! - [No padding](NoPadding) (input must already be aligned)
+//! - [Random length](RandomLengthPadding) (hides the exact plaintext length)
+
+use std::fmt;
 
 /// A trait that defines a common padding interface
+///
+/// The chunk size `B` must not be zero.
+/// All paddings of this module reject `B == 0` at compile time:
+///
+/// ```compile_fail
+/// use aesculap::padding::{Padding, PaddingError, Pkcs7Padding};
+///
+/// let _: Result<Vec<[u8; 0]>, PaddingError> = Pkcs7Padding.pad(b"I use Rust btw");
+/// ```
 pub trait Padding<const B: usize> {
     /// Pad the given bytes so they fit in equal-sized chunks
-    fn pad(&self, bytes: &[u8]) -> Vec<[u8; B]>;
+    fn pad(&self, bytes: &[u8]) -> Result<Vec<[u8; B]>, PaddingError>;
 
     /// Undo the padding
-    fn unpad(&self, padded_bytes: &[[u8; B]]) -> Vec<u8>;
+    ///
+    /// Fails if the padded bytes don't end with a valid padding.
+    fn unpad(&self, padded_bytes: &[[u8; B]]) -> Result<Vec<u8>, PaddingError>;
+
+    /// Number of bytes that [pad](Self::pad) produces for an input of `len` bytes
+    ///
+    /// The default implementation pads a dummy remainder of `len % B` bytes,
+    /// which is correct as long as the padding only depends on the length of the last chunk.
+    /// If the remainder can't be padded, `len` is returned.
+    fn padded_len(&self, len: usize) -> usize {
+        let remainder = vec![0; len % B];
+        let padded = self
+            .pad(&remainder)
+            .map_or(remainder.len(), |chunks| chunks.len() * B);
+
+        (len - remainder.len()) + padded
+    }
+}
+
+/// Errors that can occur while padding or unpadding
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PaddingError {
+    /// There are no bytes to unpad, although the padding always adds at least one byte
+    Empty,
+
+    /// The number of bytes is not a multiple of the chunk size
+    Misaligned,
+
+    /// The byte that holds the length of the padding is out of range
+    InvalidLength,
+
+    /// The padding bytes don't have the expected values
+    Malformed,
+}
+
+impl fmt::Display for PaddingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaddingError::Empty => write!(f, "There are no padded bytes"),
+            PaddingError::Misaligned => {
+                write!(f, "The number of bytes is not a multiple of the chunk size")
+            }
+            PaddingError::InvalidLength => write!(f, "The padding length is out of range"),
+            PaddingError::Malformed => write!(f, "The padding is malformed"),
+        }
+    }
+}
+
+impl std::error::Error for PaddingError {}
+
+/// Reject a chunk size of zero at compile time
+///
+/// Chunking with `B == 0` would otherwise panic deep inside `chunks_exact`.
+const fn assert_chunk_size<const B: usize>() {
+    const { assert!(B > 0, "Padding chunk size must not be zero") }
+}
+
+/// Strip a padding whose last byte holds its length
+///
+/// The length must be between 1 and `max_len`,
+/// every other padding byte must be `fill(length)`.
+fn unpad_counted(
+    mut bytes: Vec<u8>,
+    max_len: usize,
+    fill: impl Fn(u8) -> u8,
+) -> Result<Vec<u8>, PaddingError> {
+    let Some(&last_byte) = bytes.last() else {
+        return Err(PaddingError::Empty);
+    };
+
+    let padding_len = last_byte as usize;
+    if padding_len == 0 || padding_len > max_len.min(bytes.len()) {
+        return Err(PaddingError::InvalidLength);
+    }
+
+    let padding_start = bytes.len() - padding_len;
+    if bytes[padding_start..bytes.len() - 1]
+        .iter()
+        .any(|&b| b != fill(last_byte))
+    {
+        return Err(PaddingError::Malformed);
+    }
+
+    bytes.truncate(padding_start);
+
+    Ok(bytes)
 }
 
 /// PKCS #7 padding standard
@@ -24,9 +124,11 @@ pub trait Padding<const B: usize> {
 pub struct Pkcs7Padding;
 
 impl<const B: usize> Padding<B> for Pkcs7Padding {
-    fn pad(&self, bytes: &[u8]) -> Vec<[u8; B]> {
+    fn pad(&self, bytes: &[u8]) -> Result<Vec<[u8; B]>, PaddingError> {
         log::trace!("Pad using PKCS #7");
 
+        assert_chunk_size::<B>();
+
         let mut chunks: Vec<[u8; B]> = bytes
             .chunks_exact(B)
             .map(|c| c.try_into().unwrap())
@@ -35,98 +137,300 @@ impl<const B: usize> Padding<B> for Pkcs7Padding {
         let remainder = bytes.chunks_exact(B).remainder();
         let missing_bytes = B - remainder.len();
 
-        let last_chunk: [u8; B] = remainder
-            .iter()
-            .chain(vec![missing_bytes as u8; missing_bytes].iter())
-            .copied()
-            .collect::<Vec<u8>>()
-            .try_into()
-            .unwrap();
+        let mut last_chunk = [missing_bytes as u8; B];
+        last_chunk[..remainder.len()].copy_from_slice(remainder);
         chunks.push(last_chunk);
 
-        chunks
+        Ok(chunks)
     }
 
-    fn unpad(&self, padded_bytes: &[[u8; B]]) -> Vec<u8> {
+    fn unpad(&self, padded_bytes: &[[u8; B]]) -> Result<Vec<u8>, PaddingError> {
         log::trace!("Unpad using PKCS #7");
 
+        assert_chunk_size::<B>();
+
+        let bytes: Vec<u8> = padded_bytes.iter().flatten().copied().collect();
+        unpad_counted(bytes, B, |len| len)
+    }
+
+    fn padded_len(&self, len: usize) -> usize {
+        (len / B + 1) * B
+    }
+}
+
+/// ANSI X.923 padding standard
+///
+/// Like [PKCS #7](Pkcs7Padding), but only the last byte holds the number of padding bytes,
+/// the other padding bytes are zero.
+#[derive(Debug)]
+pub struct AnsiX923Padding;
+
+impl<const B: usize> Padding<B> for AnsiX923Padding {
+    fn pad(&self, bytes: &[u8]) -> Result<Vec<[u8; B]>, PaddingError> {
+        log::trace!("Pad using ANSI X.923");
+
+        assert_chunk_size::<B>();
+
+        let mut chunks: Vec<[u8; B]> = bytes
+            .chunks_exact(B)
+            .map(|c| c.try_into().unwrap())
+            .collect();
+
+        let remainder = bytes.chunks_exact(B).remainder();
+        let missing_bytes = B - remainder.len();
+
+        let mut last_chunk = [0; B];
+        last_chunk[..remainder.len()].copy_from_slice(remainder);
+        last_chunk[B - 1] = missing_bytes as u8;
+        chunks.push(last_chunk);
+
+        Ok(chunks)
+    }
+
+    fn unpad(&self, padded_bytes: &[[u8; B]]) -> Result<Vec<u8>, PaddingError> {
+        log::trace!("Unpad using ANSI X.923");
+
+        assert_chunk_size::<B>();
+
+        let bytes: Vec<u8> = padded_bytes.iter().flatten().copied().collect();
+        unpad_counted(bytes, B, |_| 0)
+    }
+
+    fn padded_len(&self, len: usize) -> usize {
+        (len / B + 1) * B
+    }
+}
+
+/// ISO/IEC 7816-4 padding standard
+///
+/// A mandatory `0x80` byte marks the end of the data, the rest of the chunk is filled with zeroes.
+#[derive(Debug)]
+pub struct Iso7816Padding;
+
+impl<const B: usize> Padding<B> for Iso7816Padding {
+    fn pad(&self, bytes: &[u8]) -> Result<Vec<[u8; B]>, PaddingError> {
+        log::trace!("Pad using ISO/IEC 7816-4");
+
+        assert_chunk_size::<B>();
+
+        let mut chunks: Vec<[u8; B]> = bytes
+            .chunks_exact(B)
+            .map(|c| c.try_into().unwrap())
+            .collect();
+
+        let remainder = bytes.chunks_exact(B).remainder();
+
+        let mut last_chunk = [0; B];
+        last_chunk[..remainder.len()].copy_from_slice(remainder);
+        last_chunk[remainder.len()] = 0x80;
+        chunks.push(last_chunk);
+
+        Ok(chunks)
+    }
+
+    fn unpad(&self, padded_bytes: &[[u8; B]]) -> Result<Vec<u8>, PaddingError> {
+        log::trace!("Unpad using ISO/IEC 7816-4");
+
+        assert_chunk_size::<B>();
+
         if padded_bytes.is_empty() {
-            return vec![];
+            return Err(PaddingError::Empty);
         }
 
         let mut bytes: Vec<u8> = padded_bytes.iter().flatten().copied().collect();
-        let last_byte = *bytes.last().unwrap();
-        bytes.truncate(bytes.len() - last_byte as usize);
 
-        bytes
+        // the marker has to be in the last chunk
+        let marker = bytes
+            .iter()
+            .rposition(|&b| b != 0)
+            .filter(|&i| bytes[i] == 0x80 && bytes.len() - i <= B)
+            .ok_or(PaddingError::Malformed)?;
+        bytes.truncate(marker);
+
+        Ok(bytes)
+    }
+
+    fn padded_len(&self, len: usize) -> usize {
+        (len / B + 1) * B
     }
 }
 
 /// Fill empty chunk space with a given byte
+///
+/// Input that is already aligned is left as it is (no extra chunk of fill bytes is added).
+/// Unpadding strips every trailing fill byte, so data that ends with the fill byte
+/// can't be restored exactly.
 #[derive(Debug)]
 pub struct BytePadding(pub u8);
 
 impl<const B: usize> Padding<B> for BytePadding {
-    fn pad(&self, bytes: &[u8]) -> Vec<[u8; B]> {
+    fn pad(&self, bytes: &[u8]) -> Result<Vec<[u8; B]>, PaddingError> {
         log::trace!("Pad with byte (0x{:x})", self.0);
 
-        let missing_bytes = bytes.len() % B;
+        assert_chunk_size::<B>();
 
-        [bytes, &vec![self.0; missing_bytes]]
+        let missing_bytes = (B - bytes.len() % B) % B;
+
+        Ok([bytes, &vec![self.0; missing_bytes]]
             .concat()
             .chunks_exact(B)
             .map(|c| c.try_into().unwrap())
-            .collect()
+            .collect())
     }
 
-    fn unpad(&self, padded_bytes: &[[u8; B]]) -> Vec<u8> {
+    fn unpad(&self, padded_bytes: &[[u8; B]]) -> Result<Vec<u8>, PaddingError> {
         log::trace!("Unpad with byte (0x{:x})", self.0);
 
-        if padded_bytes.is_empty() {
-            return vec![];
-        }
+        assert_chunk_size::<B>();
 
         let mut bytes: Vec<u8> = padded_bytes.iter().flatten().copied().collect();
 
-        while *bytes.last().unwrap() == self.0 {
+        while bytes.last() == Some(&self.0) {
             bytes.pop();
         }
 
-        bytes
+        Ok(bytes)
     }
 }
 
 /// Fill empty chunk space with zeroes
+///
+/// Like [BytePadding] with `0`: aligned input is left as it is
+/// and trailing zeroes of the data are lost when unpadding.
 #[derive(Debug)]
 pub struct ZeroPadding;
 
 impl<const B: usize> Padding<B> for ZeroPadding {
-    fn pad(&self, bytes: &[u8]) -> Vec<[u8; B]> {
+    fn pad(&self, bytes: &[u8]) -> Result<Vec<[u8; B]>, PaddingError> {
         log::trace!("Pad with zeroes");
 
-        let missing_bytes = bytes.len() % B;
+        assert_chunk_size::<B>();
 
-        [bytes, &vec![0; missing_bytes]]
+        let missing_bytes = (B - bytes.len() % B) % B;
+
+        Ok([bytes, &vec![0; missing_bytes]]
             .concat()
             .chunks_exact(B)
             .map(|c| c.try_into().unwrap())
-            .collect()
+            .collect())
     }
 
-    fn unpad(&self, padded_bytes: &[[u8; B]]) -> Vec<u8> {
+    fn unpad(&self, padded_bytes: &[[u8; B]]) -> Result<Vec<u8>, PaddingError> {
         log::trace!("Unpad with zeroes");
 
-        if padded_bytes.is_empty() {
-            return vec![];
-        }
+        assert_chunk_size::<B>();
 
         let mut bytes: Vec<u8> = padded_bytes.iter().flatten().copied().collect();
 
-        while *bytes.last().unwrap() == 0 {
+        while bytes.last() == Some(&0) {
             bytes.pop();
         }
 
-        bytes
+        Ok(bytes)
+    }
+}
+
+/// Don't pad at all
+///
+/// The input must already consist of whole chunks.
+/// Unpadding leaves the bytes untouched.
+#[derive(Debug)]
+pub struct NoPadding;
+
+impl<const B: usize> Padding<B> for NoPadding {
+    /// Fails if the number of bytes is not a multiple of the chunk size
+    fn pad(&self, bytes: &[u8]) -> Result<Vec<[u8; B]>, PaddingError> {
+        log::trace!("No padding");
+
+        assert_chunk_size::<B>();
+
+        if !bytes.len().is_multiple_of(B) {
+            return Err(PaddingError::Misaligned);
+        }
+
+        Ok(bytes
+            .chunks_exact(B)
+            .map(|c| c.try_into().unwrap())
+            .collect())
+    }
+
+    fn unpad(&self, padded_bytes: &[[u8; B]]) -> Result<Vec<u8>, PaddingError> {
+        log::trace!("No unpadding");
+
+        assert_chunk_size::<B>();
+
+        Ok(padded_bytes.iter().flatten().copied().collect())
+    }
+}
+
+/// Append a random number of dummy chunks to hide the exact length
+///
+/// Like [PKCS #7](Pkcs7Padding), every padding byte holds the total number of padding bytes,
+/// but the padding extends over a random number of additional chunks
+/// (as allowed in [TLS 1.2](https://www.rfc-editor.org/rfc/rfc5246#section-6.2.3.2)).
+/// The ciphertext length then only reveals the plaintext length up to `B * max` bytes,
+/// at the cost of up to `B * max` bytes of overhead.
+///
+/// The number of padding bytes has to fit in a single byte,
+/// so with 16 byte chunks at most 14 dummy chunks are possible.
+#[cfg(feature = "rand")]
+#[derive(Debug, Clone)]
+pub struct RandomLengthPadding {
+    extra_chunks: std::ops::RangeInclusive<usize>,
+}
+
+#[cfg(feature = "rand")]
+impl RandomLengthPadding {
+    /// Constructor that takes the range of the number of dummy chunks
+    ///
+    /// # Panics
+    /// Panics if the range is empty.
+    pub fn new(extra_chunks: std::ops::RangeInclusive<usize>) -> Self {
+        assert!(
+            !extra_chunks.is_empty(),
+            "Range of dummy chunks must not be empty"
+        );
+
+        Self { extra_chunks }
+    }
+}
+
+#[cfg(feature = "rand")]
+impl<const B: usize> Padding<B> for RandomLengthPadding {
+    /// Fails if the maximal padding does not fit in a single byte
+    fn pad(&self, bytes: &[u8]) -> Result<Vec<[u8; B]>, PaddingError> {
+        use rand::Rng;
+
+        log::trace!("Pad with random length");
+
+        assert_chunk_size::<B>();
+
+        if B * (self.extra_chunks.end() + 1) > u8::MAX as usize {
+            return Err(PaddingError::InvalidLength);
+        }
+
+        let extra_chunks = rand::thread_rng().gen_range(self.extra_chunks.clone());
+        let missing_bytes = B - bytes.len() % B + extra_chunks * B;
+
+        Ok([bytes, &vec![missing_bytes as u8; missing_bytes]]
+            .concat()
+            .chunks_exact(B)
+            .map(|c| c.try_into().unwrap())
+            .collect())
+    }
+
+    fn unpad(&self, padded_bytes: &[[u8; B]]) -> Result<Vec<u8>, PaddingError> {
+        log::trace!("Unpad with random length");
+
+        assert_chunk_size::<B>();
+
+        let bytes: Vec<u8> = padded_bytes.iter().flatten().copied().collect();
+        unpad_counted(bytes, B * (self.extra_chunks.end() + 1), |len| len)
+    }
+
+    /// Upper bound, the actual number of bytes depends on the random number of dummy chunks
+    fn padded_len(&self, len: usize) -> usize {
+        (len / B + 1 + self.extra_chunks.end()) * B
     }
 }
 
@@ -153,7 +457,7 @@ mod tests {
         ];
 
         let padding = Pkcs7Padding;
-        let padded_bytes: Vec<[u8; 16]> = padding.pad(&bytes);
+        let padded_bytes: Vec<[u8; 16]> = padding.pad(&bytes).unwrap();
 
         assert_eq!(padded_bytes, expected);
     }
@@ -177,8 +481,291 @@ mod tests {
         ];
 
         let padding = Pkcs7Padding;
-        let unpadded = padding.unpad(&padded);
+        let unpadded = padding.unpad(&padded).unwrap();
+
+        assert_eq!(unpadded, expected);
+    }
+
+    #[test]
+    fn pkcs7_invalid() {
+        let mut padded = [[0x69; 16]; 2];
+        assert_eq!(
+            Pkcs7Padding.unpad(&padded),
+            Err(PaddingError::InvalidLength)
+        );
+
+        padded[1][15] = 0x00;
+        assert_eq!(
+            Pkcs7Padding.unpad(&padded),
+            Err(PaddingError::InvalidLength)
+        );
+
+        padded[1][15] = 0x03;
+        assert_eq!(Pkcs7Padding.unpad(&padded), Err(PaddingError::Malformed));
+
+        assert_eq!(
+            Padding::<16>::unpad(&Pkcs7Padding, &[]),
+            Err(PaddingError::Empty)
+        );
+    }
+
+    #[test]
+    fn fill_byte_only() {
+        assert_eq!(BytePadding(0x69).unpad(&[[0x69; 16]]), Ok(vec![]));
+        assert_eq!(ZeroPadding.unpad(&[[0; 16]]), Ok(vec![]));
+    }
+
+    #[test]
+    fn ansi_x923_pad() {
+        let bytes = [
+            0xf1, 0x4a, 0xdb, 0xda, 0x01, 0x9d, 0x6d, 0xb7, 0xef, 0xd9, 0x15, 0x46, 0xe3, 0xff,
+            0x84, 0x44, 0x9b, 0xcb,
+        ];
+
+        let expected = vec![
+            [
+                0xf1, 0x4a, 0xdb, 0xda, 0x01, 0x9d, 0x6d, 0xb7, 0xef, 0xd9, 0x15, 0x46, 0xe3, 0xff,
+                0x84, 0x44,
+            ],
+            [
+                0x9b, 0xcb, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x0e,
+            ],
+        ];
+
+        let padding = AnsiX923Padding;
+        let padded_bytes: Vec<[u8; 16]> = padding.pad(&bytes).unwrap();
+
+        assert_eq!(padded_bytes, expected);
+    }
+
+    #[test]
+    fn ansi_x923_unpad() {
+        let padded = vec![
+            [
+                0xf1, 0x4a, 0xdb, 0xda, 0x01, 0x9d, 0x6d, 0xb7, 0xef, 0xd9, 0x15, 0x46, 0xe3, 0xff,
+                0x84, 0x44,
+            ],
+            [
+                0x9b, 0xcb, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x0e,
+            ],
+        ];
+
+        let expected = vec![
+            0xf1, 0x4a, 0xdb, 0xda, 0x01, 0x9d, 0x6d, 0xb7, 0xef, 0xd9, 0x15, 0x46, 0xe3, 0xff,
+            0x84, 0x44, 0x9b, 0xcb,
+        ];
+
+        let padding = AnsiX923Padding;
+        let unpadded = padding.unpad(&padded).unwrap();
 
         assert_eq!(unpadded, expected);
     }
+
+    #[test]
+    fn ansi_x923_aligned() {
+        let bytes = [0x69; 16];
+
+        let padded: Vec<[u8; 16]> = AnsiX923Padding.pad(&bytes).unwrap();
+        assert_eq!(padded.len(), 2);
+        assert_eq!(padded[1][..15], [0; 15]);
+        assert_eq!(padded[1][15], 16);
+
+        assert_eq!(AnsiX923Padding.unpad(&padded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn ansi_x923_invalid() {
+        let mut padded = [[0; 16]];
+        padded[0][14] = 0x01;
+        padded[0][15] = 0x04;
+        assert_eq!(AnsiX923Padding.unpad(&padded), Err(PaddingError::Malformed));
+
+        padded[0][15] = 0x11;
+        assert_eq!(
+            AnsiX923Padding.unpad(&padded),
+            Err(PaddingError::InvalidLength)
+        );
+
+        assert_eq!(
+            Padding::<16>::unpad(&AnsiX923Padding, &[]),
+            Err(PaddingError::Empty)
+        );
+    }
+
+    #[test]
+    fn iso7816_pad() {
+        let bytes = [
+            0xf1, 0x4a, 0xdb, 0xda, 0x01, 0x9d, 0x6d, 0xb7, 0xef, 0xd9, 0x15, 0x46, 0xe3, 0xff,
+            0x84, 0x44, 0x9b, 0xcb,
+        ];
+
+        let expected = vec![
+            [
+                0xf1, 0x4a, 0xdb, 0xda, 0x01, 0x9d, 0x6d, 0xb7, 0xef, 0xd9, 0x15, 0x46, 0xe3, 0xff,
+                0x84, 0x44,
+            ],
+            [
+                0x9b, 0xcb, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00,
+            ],
+        ];
+
+        let padding = Iso7816Padding;
+        let padded_bytes: Vec<[u8; 16]> = padding.pad(&bytes).unwrap();
+
+        assert_eq!(padded_bytes, expected);
+    }
+
+    #[test]
+    fn iso7816_unpad() {
+        let padded = vec![
+            [
+                0xf1, 0x4a, 0xdb, 0xda, 0x01, 0x9d, 0x6d, 0xb7, 0xef, 0xd9, 0x15, 0x46, 0xe3, 0xff,
+                0x84, 0x44,
+            ],
+            [
+                0x9b, 0xcb, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00,
+            ],
+        ];
+
+        let expected = vec![
+            0xf1, 0x4a, 0xdb, 0xda, 0x01, 0x9d, 0x6d, 0xb7, 0xef, 0xd9, 0x15, 0x46, 0xe3, 0xff,
+            0x84, 0x44, 0x9b, 0xcb,
+        ];
+
+        let padding = Iso7816Padding;
+        let unpadded = padding.unpad(&padded).unwrap();
+
+        assert_eq!(unpadded, expected);
+    }
+
+    #[test]
+    fn iso7816_aligned() {
+        let mut bytes = [0x80; 16];
+        bytes[15] = 0;
+
+        let padded: Vec<[u8; 16]> = Iso7816Padding.pad(&bytes).unwrap();
+        assert_eq!(padded.len(), 2);
+        assert_eq!(padded[1][0], 0x80);
+        assert_eq!(padded[1][1..], [0; 15]);
+
+        assert_eq!(Iso7816Padding.unpad(&padded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn iso7816_missing_marker() {
+        let mut padded = [[0; 16]; 2];
+        padded[0][3] = 0x69;
+        assert_eq!(Iso7816Padding.unpad(&padded), Err(PaddingError::Malformed));
+
+        // the marker must not be followed by a whole chunk of zeroes
+        padded[0][3] = 0x80;
+        assert_eq!(Iso7816Padding.unpad(&padded), Err(PaddingError::Malformed));
+    }
+
+    #[test]
+    fn aes_chunk_size() {
+        let bytes = b"I use Rust btw";
+
+        let padded: Vec<[u8; 16]> = Pkcs7Padding.pad(bytes).unwrap();
+        assert_eq!(padded.len(), 1);
+        assert_eq!(Pkcs7Padding.unpad(&padded).unwrap(), bytes);
+
+        let padded: Vec<[u8; 16]> = AnsiX923Padding.pad(bytes).unwrap();
+        assert_eq!(padded.len(), 1);
+        assert_eq!(AnsiX923Padding.unpad(&padded).unwrap(), bytes);
+
+        let padded: Vec<[u8; 16]> = Iso7816Padding.pad(bytes).unwrap();
+        assert_eq!(padded.len(), 1);
+        assert_eq!(Iso7816Padding.unpad(&padded).unwrap(), bytes);
+
+        let padded: Vec<[u8; 16]> = BytePadding(0x69).pad(bytes).unwrap();
+        assert_eq!(padded.len(), 1);
+        assert_eq!(BytePadding(0x69).unpad(&padded).unwrap(), bytes);
+
+        let padded: Vec<[u8; 16]> = ZeroPadding.pad(bytes).unwrap();
+        assert_eq!(padded.len(), 1);
+        assert_eq!(ZeroPadding.unpad(&padded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn byte_padding_keeps_remainder() {
+        let bytes: Vec<u8> = (0..20).collect();
+
+        let padded: Vec<[u8; 16]> = BytePadding(0x69).pad(&bytes).unwrap();
+        let flat: Vec<u8> = padded.iter().flatten().copied().collect();
+        assert_eq!(flat.len(), 32);
+        assert_eq!(flat[..20], bytes);
+        assert!(flat[20..].iter().all(|&b| b == 0x69));
+        assert_eq!(BytePadding(0x69).unpad(&padded).unwrap(), bytes);
+
+        let padded: Vec<[u8; 16]> = ZeroPadding.pad(&bytes[1..]).unwrap();
+        let flat: Vec<u8> = padded.iter().flatten().copied().collect();
+        assert_eq!(flat.len(), 32);
+        assert_eq!(flat[..19], bytes[1..]);
+        assert!(flat[19..].iter().all(|&b| b == 0));
+        assert_eq!(ZeroPadding.unpad(&padded).unwrap(), bytes[1..]);
+
+        // aligned input doesn't get an extra chunk
+        let padded: Vec<[u8; 16]> = BytePadding(0x69).pad(&bytes[..16]).unwrap();
+        assert_eq!(padded.len(), 1);
+        let padded: Vec<[u8; 16]> = ZeroPadding.pad(&bytes[..16]).unwrap();
+        assert_eq!(padded.len(), 1);
+    }
+
+    #[test]
+    fn no_padding_aligned() {
+        let bytes = [
+            0xf1, 0x4a, 0xdb, 0xda, 0x01, 0x9d, 0x6d, 0xb7, 0xef, 0xd9, 0x15, 0x46, 0xe3, 0xff,
+            0x00, 0x00,
+        ];
+
+        let padded: Vec<[u8; 16]> = NoPadding.pad(&bytes).unwrap();
+        assert_eq!(padded, vec![bytes]);
+
+        assert_eq!(NoPadding.unpad(&padded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn no_padding_misaligned() {
+        let bytes = [0xf1, 0x4a, 0xdb, 0xda, 0x01, 0x9d, 0x6d, 0xb7, 0xef];
+
+        let padded: Result<Vec<[u8; 16]>, _> = NoPadding.pad(&bytes);
+        assert_eq!(padded, Err(PaddingError::Misaligned));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_length_round_trip() {
+        let padding = RandomLengthPadding::new(2..=14);
+        let mut lengths = std::collections::HashSet::new();
+
+        for len in 0..=48 {
+            let bytes: Vec<u8> = (0..len as u8).collect();
+
+            for _ in 0..16 {
+                let padded: Vec<[u8; 16]> = padding.pad(&bytes).unwrap();
+                let padded_len = padded.len() * 16;
+
+                assert!(padded_len >= (len / 16 + 3) * 16);
+                assert!(padded_len <= Padding::<16>::padded_len(&padding, len));
+                assert_eq!(padding.unpad(&padded).unwrap(), bytes);
+
+                lengths.insert(padded_len - len);
+            }
+        }
+
+        assert!(lengths.len() > 16);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_length_too_long() {
+        let padded: Result<Vec<[u8; 16]>, _> =
+            RandomLengthPadding::new(0..=15).pad(b"I use Rust btw");
+        assert_eq!(padded, Err(PaddingError::InvalidLength));
+    }
 }
@@ -0,0 +1,31 @@
+//! Parallel module
+//!
+//! This module spreads independent blocks (in [ECB](crate::EncryptionMode::ECB) and [CTR](crate::EncryptionMode::CTR)) across threads.
+//! It is only used with the `parallel` feature.
+
+use std::num::NonZeroUsize;
+use std::thread;
+
+/// Minimum number of blocks that are worth spawning threads for
+pub const THRESHOLD: usize = 1024;
+
+/// Call `f` on consecutive chunks of `items`, one chunk per available thread
+///
+/// `f` also gets the index of the first item of its chunk.
+pub fn for_each_chunk<T, F>(items: &mut [T], f: F)
+where
+    T: Send,
+    F: Fn(usize, &mut [T]) + Sync,
+{
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let chunk_len = items.len().div_ceil(threads).max(1);
+
+    log::trace!("Process {} items on {threads} threads", items.len());
+
+    thread::scope(|scope| {
+        for (i, chunk) in items.chunks_mut(chunk_len).enumerate() {
+            let f = &f;
+            scope.spawn(move || f(i * chunk_len, chunk));
+        }
+    });
+}
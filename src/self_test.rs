@@ -0,0 +1,167 @@
+//! Self-test module
+//!
+//! This module provides a power-on self-test in the spirit of FIPS 140.
+//! It runs known-answer tests of the primitives,
+//! so that a miscompiled or corrupted build fails loudly instead of producing wrong results.
+
+use std::fmt;
+
+use crate::aead::{decrypt_gcm, decrypt_gcm_siv, encrypt_gcm, encrypt_gcm_siv};
+use crate::block::Block;
+use crate::decryption::decrypt_block;
+use crate::encryption::encrypt_block;
+use crate::key::{AES128Key, AES192Key, AES256Key, Key, RoundKeyProvider};
+use crate::mac::cmac;
+
+/// Plaintext of the known-answer tests
+///
+/// For reference, see [FIPS 197, Appendix C](https://csrc.nist.gov/publications/detail/fips/197/final).
+const PLAINTEXT: u128 = 0x00112233445566778899aabbccddeeff;
+
+/// Errors that a failing [self_test] reports
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SelfTestError {
+    /// The expanded key does not match
+    KeySchedule,
+
+    /// Encrypting the known plaintext with a key of this size (in bits) gave a wrong result
+    Encryption(usize),
+
+    /// Decrypting the known ciphertext with a key of this size (in bits) gave a wrong result
+    Decryption(usize),
+
+    /// AES-GCM-SIV gave a wrong result
+    Aead,
+
+    /// AES-GCM gave a wrong result
+    Gcm,
+
+    /// AES-CMAC gave a wrong tag
+    Cmac,
+}
+
+impl fmt::Display for SelfTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelfTestError::KeySchedule => write!(f, "Self-test of the key schedule failed"),
+            SelfTestError::Encryption(bits) => {
+                write!(f, "Self-test of AES-{bits} encryption failed")
+            }
+            SelfTestError::Decryption(bits) => {
+                write!(f, "Self-test of AES-{bits} decryption failed")
+            }
+            SelfTestError::Aead => write!(f, "Self-test of AES-GCM-SIV failed"),
+            SelfTestError::Gcm => write!(f, "Self-test of AES-GCM failed"),
+            SelfTestError::Cmac => write!(f, "Self-test of AES-CMAC failed"),
+        }
+    }
+}
+
+impl std::error::Error for SelfTestError {}
+
+/// Run known-answer tests of all primitives
+///
+/// Checks the key schedule, encryption and decryption with every key size, AES-GCM-SIV, AES-GCM and AES-CMAC.
+pub fn self_test() -> Result<(), SelfTestError> {
+    log::trace!("Run self-test");
+
+    // see FIPS 197, Appendix A.1
+    let key = AES128Key::from_bytes(0x2b7e151628aed2a6abf7158809cf4f3c_u128.to_be_bytes());
+    if key.round_keys()[10] != 0xd014f9a8c9ee2589e13f0cc8b6630ca6 {
+        return Err(SelfTestError::KeySchedule);
+    }
+
+    known_answer(
+        &AES128Key::from_bytes(key_bytes()),
+        0x69c4e0d86a7b0430d8cdb78070b4c55a,
+        128,
+    )?;
+    known_answer(
+        &AES192Key::from_bytes(key_bytes()),
+        0xdda97ca4864cdfe06eaf70a0ec0d7191,
+        192,
+    )?;
+    known_answer(
+        &AES256Key::from_bytes(key_bytes()),
+        0x8ea2b7ca516745bfeafc49904b496089,
+        256,
+    )?;
+
+    // see RFC 8452, Appendix C.1
+    let key = AES128Key::from_bytes(0x01000000000000000000000000000000_u128.to_be_bytes());
+    let nonce = [0x03, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let plaintext = [0x01, 0, 0, 0, 0, 0, 0, 0];
+    let expected = [
+        0xb5, 0xd8, 0x39, 0x33, 0x0a, 0xc7, 0xb7, 0x86, 0x57, 0x87, 0x82, 0xff, 0xf6, 0x01, 0x3b,
+        0x81, 0x5b, 0x28, 0x7c, 0x22, 0x49, 0x3a, 0x36, 0x4c,
+    ];
+
+    let ciphertext = encrypt_gcm_siv(&key, &nonce, &[], &plaintext);
+    if ciphertext != expected
+        || decrypt_gcm_siv(&key, &nonce, &[], &ciphertext) != Ok(plaintext.to_vec())
+    {
+        return Err(SelfTestError::Aead);
+    }
+
+    // see the GCM specification (McGrew, Viega), Test Case 2
+    let key = AES128Key::from_bytes([0; 16]);
+    let expected = [
+        0x03, 0x88, 0xda, 0xce, 0x60, 0xb6, 0xa3, 0x92, 0xf3, 0x28, 0xc2, 0xb9, 0x71, 0xb2, 0xfe,
+        0x78, 0xab, 0x6e, 0x47, 0xd4, 0x2c, 0xec, 0x13, 0xbd, 0xf5, 0x3a, 0x67, 0xb2, 0x12, 0x57,
+        0xbd, 0xdf,
+    ];
+
+    let ciphertext = encrypt_gcm(&key, &[0; 12], &[], &[0; 16]);
+    if ciphertext != expected || decrypt_gcm(&key, &[0; 12], &[], &ciphertext) != Ok(vec![0; 16]) {
+        return Err(SelfTestError::Gcm);
+    }
+
+    // see RFC 4493, Section 4, Example 2
+    let key = AES128Key::from_bytes(0x2b7e151628aed2a6abf7158809cf4f3c_u128.to_be_bytes());
+    let message = 0x6bc1bee22e409f96e93d7e117393172a_u128.to_be_bytes();
+    if cmac(&key, &message) != 0x070a16b46b4d4144f79bdd9dd04a287c_u128.to_be_bytes() {
+        return Err(SelfTestError::Cmac);
+    }
+
+    log::debug!("Self-test passed");
+    Ok(())
+}
+
+/// Key bytes `00 01 02 ...` of the FIPS 197 examples
+fn key_bytes<const N: usize>() -> [u8; N] {
+    std::array::from_fn(|i| i as u8)
+}
+
+/// Encrypt and decrypt the known plaintext
+fn known_answer<const R: usize, K>(
+    key: &K,
+    ciphertext: u128,
+    bits: usize,
+) -> Result<(), SelfTestError>
+where
+    K: Key<R>,
+{
+    let mut block = Block::from(PLAINTEXT);
+
+    encrypt_block(&mut block, key);
+    if block != Block::from(ciphertext) {
+        return Err(SelfTestError::Encryption(bits));
+    }
+
+    decrypt_block(&mut block, key);
+    if block != Block::from(PLAINTEXT) {
+        return Err(SelfTestError::Decryption(bits));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn self_test_passes() {
+        assert_eq!(self_test(), Ok(()));
+    }
+}
@@ -0,0 +1,280 @@
+//! Stream module
+//!
+//! This module provides an [EncryptingWriter] that encrypts bytes while they are written
+//! and a [DecryptingReader] that decrypts bytes while they are read,
+//! so the whole input never has to be held in memory.
+
+use std::io::{self, Read, Write};
+
+use crate::decryption::decrypt_bytes;
+use crate::encryption::encrypt_bytes;
+use crate::key::Key;
+use crate::padding::{NoPadding, Padding};
+use crate::EncryptionMode;
+
+/// Writer adapter that encrypts everything written to it
+///
+/// Complete blocks are encrypted and passed on to the inner writer right away,
+/// only the bytes of an incomplete block are buffered.
+/// The chaining state (e.g. the previous ciphertext block in [CBC](EncryptionMode::CBC)
+/// or the counter in [CTR](EncryptionMode::CTR)) carries over from one `write` call to the next,
+/// so the output is the same as the one of [encrypt_bytes] on the whole input.
+///
+/// [EncryptingWriter::finish] has to be called at the end:
+/// dropping the writer loses the buffered bytes and the padding.
+pub struct EncryptingWriter<'k, const R: usize, W, K, P> {
+    writer: W,
+    key: &'k K,
+    padding: P,
+    mode: EncryptionMode,
+    buffer: Vec<u8>,
+}
+
+impl<'k, const R: usize, W, K, P> EncryptingWriter<'k, R, W, K, P>
+where
+    W: Write,
+    K: Key<R>,
+    P: Padding<16>,
+{
+    /// Constructor that takes the inner writer and the parameters of [encrypt_bytes]
+    pub fn new(writer: W, key: &'k K, padding: P, mode: EncryptionMode) -> Self {
+        Self {
+            writer,
+            key,
+            padding,
+            mode,
+            buffer: Vec::with_capacity(16),
+        }
+    }
+
+    /// Encrypt the remaining bytes, flush the inner writer and return it
+    ///
+    /// The remaining bytes are padded, except in the stream modes [CTR](EncryptionMode::CTR),
+    /// [CFB](EncryptionMode::CFB) and [OFB](EncryptionMode::OFB).
+    ///
+    /// # Return value
+    /// Fails with [InvalidInput](io::ErrorKind::InvalidInput) if the remaining bytes can't be padded,
+    /// e.g. with [NoPadding] and a number of bytes that is not a multiple of `16`.
+    pub fn finish(mut self) -> io::Result<W> {
+        log::trace!("Finish encrypting writer");
+
+        let tail = if is_stream_mode(self.mode) {
+            std::mem::take(&mut self.buffer)
+        } else {
+            self.padding
+                .pad(&self.buffer)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?
+                .concat()
+        };
+
+        let ciphertext = encrypt_bytes(&tail, self.key, &NoPadding, self.mode);
+        self.writer.write_all(&ciphertext)?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+impl<const R: usize, W, K, P> Write for EncryptingWriter<'_, R, W, K, P>
+where
+    W: Write,
+    K: Key<R>,
+    P: Padding<16>,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+
+        let complete = self.buffer.len() / 16 * 16;
+        if complete == 0 {
+            return Ok(buf.len());
+        }
+
+        let plaintext: Vec<u8> = self.buffer.drain(..complete).collect();
+        let ciphertext = encrypt_bytes(&plaintext, self.key, &NoPadding, self.mode);
+
+        self.mode = next_mode(self.mode, &plaintext, &ciphertext);
+        self.writer.write_all(&ciphertext)?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // an incomplete block can't be encrypted before the rest of it is known
+        self.writer.flush()
+    }
+}
+
+/// Default number of ciphertext bytes that are requested from the inner reader at once
+const READ_SIZE: usize = 4096;
+
+/// Number of bytes at the end that may belong to the padding
+///
+/// The length of every padding is stored in (or limited by) a single byte.
+const MAX_PADDING: usize = 256;
+
+/// Reader adapter that decrypts everything read through it
+///
+/// The ciphertext is read from the inner reader in chunks and decrypted block by block,
+/// the chaining state carries over from one chunk to the next.
+/// With a padding, the last 256 bytes are held back until the end of the inner reader,
+/// because only then it is known which bytes belong to the padding.
+/// The plaintext is the same as the one of [decrypt_bytes] on the whole ciphertext,
+/// except that [ZeroPadding](crate::padding::ZeroPadding) and [BytePadding](crate::padding::BytePadding)
+/// only strip fill bytes from these last 256 bytes.
+///
+/// Reading fails with [InvalidData](io::ErrorKind::InvalidData)
+/// if the ciphertext is not a multiple of 16 bytes long
+/// (except in the stream modes [CTR](EncryptionMode::CTR), [CFB](EncryptionMode::CFB) and [OFB](EncryptionMode::OFB))
+/// or if it doesn't end with a valid padding.
+pub struct DecryptingReader<'k, const R: usize, Rd, K, P> {
+    reader: Rd,
+    key: &'k K,
+    padding: Option<P>,
+    mode: EncryptionMode,
+    read_size: usize,
+    ciphertext: Vec<u8>,
+    plaintext: Vec<u8>,
+    position: usize,
+    done: bool,
+}
+
+impl<'k, const R: usize, Rd, K, P> DecryptingReader<'k, R, Rd, K, P>
+where
+    Rd: Read,
+    K: Key<R>,
+    P: Padding<16>,
+{
+    /// Constructor that takes the inner reader and the parameters of [decrypt_bytes]
+    pub fn new(reader: Rd, key: &'k K, padding: Option<P>, mode: EncryptionMode) -> Self {
+        Self::with_capacity(READ_SIZE, reader, key, padding, mode)
+    }
+
+    /// Like [DecryptingReader::new], but requests `capacity` bytes from the inner reader at once
+    pub fn with_capacity(
+        capacity: usize,
+        reader: Rd,
+        key: &'k K,
+        padding: Option<P>,
+        mode: EncryptionMode,
+    ) -> Self {
+        assert!(capacity > 0, "The capacity must not be zero");
+
+        Self {
+            reader,
+            key,
+            padding,
+            mode,
+            read_size: capacity,
+            ciphertext: Vec::with_capacity(capacity + MAX_PADDING),
+            plaintext: Vec::new(),
+            position: 0,
+            done: false,
+        }
+    }
+
+    /// Get the inner reader back
+    pub fn into_inner(self) -> Rd {
+        self.reader
+    }
+
+    /// Read the next chunk of ciphertext and decrypt as much of it as possible
+    fn fill(&mut self) -> io::Result<()> {
+        let len = self.ciphertext.len();
+        self.ciphertext.resize(len + self.read_size, 0);
+        let n = match self.reader.read(&mut self.ciphertext[len..]) {
+            Ok(n) => n,
+            Err(err) => {
+                self.ciphertext.truncate(len);
+                return Err(err);
+            }
+        };
+        self.ciphertext.truncate(len + n);
+
+        if n == 0 {
+            return self.finish();
+        }
+
+        let held_back = if self.padding.is_some() && !is_stream_mode(self.mode) {
+            MAX_PADDING
+        } else {
+            0
+        };
+        let ready = self.ciphertext.len().saturating_sub(held_back) / 16 * 16;
+        if ready == 0 {
+            return Ok(());
+        }
+
+        let ciphertext: Vec<u8> = self.ciphertext.drain(..ready).collect();
+        let plaintext = decrypt_bytes(&ciphertext, self.key, None::<NoPadding>, self.mode)
+            .expect("Complete blocks can be decrypted");
+
+        self.mode = next_mode(self.mode, &plaintext, &ciphertext);
+        self.plaintext = plaintext;
+        self.position = 0;
+
+        Ok(())
+    }
+
+    /// Decrypt and unpad the rest of the ciphertext at the end of the inner reader
+    fn finish(&mut self) -> io::Result<()> {
+        log::trace!("Finish decrypting reader");
+
+        self.done = true;
+
+        let ciphertext = std::mem::take(&mut self.ciphertext);
+        self.plaintext = decrypt_bytes(&ciphertext, self.key, self.padding.take(), self.mode)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        self.position = 0;
+
+        Ok(())
+    }
+}
+
+impl<const R: usize, Rd, K, P> Read for DecryptingReader<'_, R, Rd, K, P>
+where
+    Rd: Read,
+    K: Key<R>,
+    P: Padding<16>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.plaintext.len() && !self.done {
+            self.fill()?;
+        }
+
+        let available = &self.plaintext[self.position..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.position += n;
+
+        Ok(n)
+    }
+}
+
+/// Whether the mode works on single bytes instead of padded blocks
+fn is_stream_mode(mode: EncryptionMode) -> bool {
+    matches!(
+        mode,
+        EncryptionMode::CTR(_) | EncryptionMode::CFB(_) | EncryptionMode::OFB(_)
+    )
+}
+
+/// Chaining state after `plaintext` (a non-empty multiple of 16 bytes) was encrypted to `ciphertext`
+///
+/// This is the same for encryption and decryption.
+fn next_mode(mode: EncryptionMode, plaintext: &[u8], ciphertext: &[u8]) -> EncryptionMode {
+    let last = |bytes: &[u8]| -> [u8; 16] { bytes[bytes.len() - 16..].try_into().unwrap() };
+    let (last_plain, last_cipher) = (last(plaintext), last(ciphertext));
+    let xor = || std::array::from_fn(|i| last_plain[i] ^ last_cipher[i]).into();
+
+    match mode {
+        EncryptionMode::ECB => EncryptionMode::ECB,
+        EncryptionMode::CBC(_) => EncryptionMode::CBC(last_cipher.into()),
+        EncryptionMode::PCBC(_) => EncryptionMode::PCBC(xor()),
+        EncryptionMode::CTR(counter) => {
+            EncryptionMode::CTR(counter.wrapping_add((plaintext.len() / 16) as u128))
+        }
+        EncryptionMode::CFB(_) => EncryptionMode::CFB(last_cipher.into()),
+        // the keystream block itself is the feedback
+        EncryptionMode::OFB(_) => EncryptionMode::OFB(xor()),
+    }
+}
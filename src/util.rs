@@ -2,6 +2,11 @@
 //!
 //! This module provides a few unrelated utility functions.
 
+#[cfg(feature = "constant-time")]
+use crate::ct_sbox;
+#[cfg(not(feature = "constant-time"))]
+use crate::lookups::sbox::{INVERSE_SBOX, SBOX};
+
 /// Transpose a 2D array by exchanging rows and columns
 pub fn transpose_array2d<const N: usize>(inp: &[[u8; N]; N]) -> [[u8; N]; N] {
     let mut out = [[0; N]; N];
@@ -30,19 +35,72 @@ pub fn rot_left<const N: usize>(mut bytes: [u8; N], shift: isize) -> [u8; N] {
     bytes
 }
 
-/// Substitute each byte of an array using a given S-box
-pub fn apply_sbox<const N: usize>(mut bytes: [u8; N], sbox: [u8; 256]) -> [u8; N] {
-    for byte in &mut bytes {
-        *byte = sbox[*byte as usize];
-    }
+/// Substitute each byte of an array using the AES [SBOX](crate::lookups::sbox::SBOX)
+///
+/// With the `constant-time` feature, the S-box is computed instead of looked up.
+pub fn sub_bytes<const N: usize>(bytes: [u8; N]) -> [u8; N] {
+    #[cfg(feature = "constant-time")]
+    return bytes.map(ct_sbox::sub_byte);
 
-    bytes
+    #[cfg(not(feature = "constant-time"))]
+    bytes.map(|byte| SBOX[byte as usize])
 }
 
-/// Interprete four bytes as an `u32`
+/// Substitute each byte of an array using the AES [INVERSE_SBOX](crate::lookups::sbox::INVERSE_SBOX)
+///
+/// With the `constant-time` feature, the S-box is computed instead of looked up.
+pub fn sub_bytes_inv<const N: usize>(bytes: [u8; N]) -> [u8; N] {
+    #[cfg(feature = "constant-time")]
+    return bytes.map(ct_sbox::sub_byte_inv);
+
+    #[cfg(not(feature = "constant-time"))]
+    bytes.map(|byte| INVERSE_SBOX[byte as usize])
+}
+
+/// Interprete four bytes as an `u32` (big-endian)
 pub fn bytes_as_u32(bytes: [u8; 4]) -> u32 {
     (bytes[3] as u32)
         | ((bytes[2] as u32) << 8)
         | ((bytes[1] as u32) << 16)
         | ((bytes[0] as u32) << 24)
 }
+
+/// Split an `u32` into four bytes (big-endian)
+///
+/// This is the inverse of [bytes_as_u32].
+pub fn u32_as_bytes(word: u32) -> [u8; 4] {
+    [
+        (word >> 24) as u8,
+        (word >> 16) as u8,
+        (word >> 8) as u8,
+        word as u8,
+    ]
+}
+
+/// Compare two byte slices in constant time
+///
+/// The running time only depends on the length of the slices, not on their contents.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let diff = a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn u32_bytes_round_trip() {
+        let bytes = [0x12, 0x34, 0x56, 0x78];
+        assert_eq!(bytes_as_u32(bytes), 0x12345678);
+        assert_eq!(u32_as_bytes(0x12345678), bytes);
+
+        for word in [0, 1, 0xff, 0x100, 0x80000000, u32::MAX, 0xdeadbeef] {
+            assert_eq!(bytes_as_u32(u32_as_bytes(word)), word);
+        }
+    }
+}
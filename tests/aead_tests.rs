@@ -0,0 +1,295 @@
+#[cfg(feature = "rand")]
+use aesculap::aead::seal;
+use aesculap::aead::{
+    decrypt_gcm, decrypt_gcm_siv, encrypt_gcm, encrypt_gcm_siv, open, GcmError, GcmSivError,
+    NONCE_SIZE,
+};
+use aesculap::hex;
+use aesculap::key::{AES128Key, AES192Key, AES256Key};
+
+#[test]
+fn gcm_siv_aes128_rfc8452() {
+    // see https://www.rfc-editor.org/rfc/rfc8452#appendix-C.1
+    let key = AES128Key::from_bytes(
+        hex::decode("01000000000000000000000000000000")
+            .unwrap()
+            .try_into()
+            .unwrap(),
+    );
+    let nonce = hex::decode("030000000000000000000000")
+        .unwrap()
+        .try_into()
+        .unwrap();
+
+    let vectors = [
+        ("", "dc20e2d83f25705bb49e439eca56de25"),
+        (
+            "0100000000000000",
+            "b5d839330ac7b786578782fff6013b815b287c22493a364c",
+        ),
+        (
+            "010000000000000000000000",
+            "7323ea61d05932260047d942a4978db357391a0bc4fdec8b0d106639",
+        ),
+        (
+            "01000000000000000000000000000000",
+            "743f7c8077ab25f8624e2e948579cf77303aaf90f6fe21199c6068577437a0c4",
+        ),
+    ];
+
+    for (plaintext, result) in vectors {
+        let ciphertext = encrypt_gcm_siv(&key, &nonce, &[], &hex::decode(plaintext).unwrap());
+        assert_eq!(ciphertext, hex::decode(result).unwrap());
+
+        let decrypted = decrypt_gcm_siv(&key, &nonce, &[], &ciphertext).unwrap();
+        assert_eq!(decrypted, hex::decode(plaintext).unwrap());
+    }
+}
+
+#[test]
+fn gcm_siv_aes256_rfc8452() {
+    // see https://www.rfc-editor.org/rfc/rfc8452#appendix-C.2
+    let key = AES256Key::from_bytes(
+        hex::decode("0100000000000000000000000000000000000000000000000000000000000000")
+            .unwrap()
+            .try_into()
+            .unwrap(),
+    );
+    let nonce = hex::decode("030000000000000000000000")
+        .unwrap()
+        .try_into()
+        .unwrap();
+
+    let ciphertext = encrypt_gcm_siv(&key, &nonce, &[], &[]);
+    assert_eq!(
+        ciphertext,
+        hex::decode("07f5f4169bbf55a8400cd47ea6fd400f").unwrap()
+    );
+
+    let decrypted = decrypt_gcm_siv(&key, &nonce, &[], &ciphertext).unwrap();
+    assert!(decrypted.is_empty());
+}
+
+#[test]
+fn gcm_siv_tampering() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let nonce = *b"abcdef012345";
+    let aad = b"header";
+    let plaintext = b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor";
+
+    let ciphertext = encrypt_gcm_siv(&key, &nonce, aad, plaintext);
+    assert_eq!(ciphertext.len(), plaintext.len() + 16);
+
+    let mut tampered = ciphertext.clone();
+    tampered[3] ^= 0x01;
+    assert_eq!(
+        decrypt_gcm_siv(&key, &nonce, aad, &tampered),
+        Err(GcmSivError::AuthenticationFailed)
+    );
+
+    let mut tampered = ciphertext.clone();
+    *tampered.last_mut().unwrap() ^= 0x80;
+    assert_eq!(
+        decrypt_gcm_siv(&key, &nonce, aad, &tampered),
+        Err(GcmSivError::AuthenticationFailed)
+    );
+
+    assert_eq!(
+        decrypt_gcm_siv(&key, &nonce, b"Header", &ciphertext),
+        Err(GcmSivError::AuthenticationFailed)
+    );
+
+    assert_eq!(
+        decrypt_gcm_siv(&key, &nonce, aad, &ciphertext[..15]),
+        Err(GcmSivError::InvalidLength)
+    );
+
+    assert_eq!(
+        decrypt_gcm_siv(&key, &nonce, aad, &ciphertext).unwrap(),
+        plaintext
+    );
+}
+
+/// Plaintext of the GCM specification test cases 4, 10 and 16
+const GCM_PLAINTEXT: &str = "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39";
+
+/// Associated data of the GCM specification test cases 4, 10 and 16
+const GCM_AAD: &str = "feedfacedeadbeeffeedfacedeadbeefabaddad2";
+
+#[test]
+fn gcm_aes128_zero_key() {
+    // see the GCM specification (McGrew, Viega), Test Cases 1 and 2
+    let key = AES128Key::from_bytes([0; 16]);
+    let nonce = [0; 12];
+
+    let ciphertext = encrypt_gcm(&key, &nonce, &[], &[]);
+    assert_eq!(
+        ciphertext,
+        hex::decode("58e2fccefa7e3061367f1d57a4e7455a").unwrap()
+    );
+
+    let ciphertext = encrypt_gcm(&key, &nonce, &[], &[0; 16]);
+    assert_eq!(
+        ciphertext,
+        hex::decode("0388dace60b6a392f328c2b971b2fe78ab6e47d42cec13bdf53a67b21257bddf").unwrap()
+    );
+    assert_eq!(
+        decrypt_gcm(&key, &nonce, &[], &ciphertext).unwrap(),
+        [0; 16]
+    );
+}
+
+#[test]
+fn gcm_with_aad() {
+    // see the GCM specification (McGrew, Viega), Test Cases 4, 10 and 16
+    let nonce = hex::decode("cafebabefacedbaddecaf888")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let plaintext = hex::decode(GCM_PLAINTEXT).unwrap();
+    let aad = hex::decode(GCM_AAD).unwrap();
+
+    let ciphertext = encrypt_gcm(
+        &AES128Key::from_bytes(
+            hex::decode("feffe9928665731c6d6a8f9467308308")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        ),
+        &nonce,
+        &aad,
+        &plaintext,
+    );
+    assert_eq!(ciphertext, hex::decode("42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e0915bc94fbc3221a5db94fae95ae7121a47").unwrap());
+
+    let key = AES192Key::from_bytes(
+        hex::decode("feffe9928665731c6d6a8f9467308308feffe9928665731c")
+            .unwrap()
+            .try_into()
+            .unwrap(),
+    );
+    let ciphertext = encrypt_gcm(&key, &nonce, &aad, &plaintext);
+    assert_eq!(ciphertext, hex::decode("3980ca0b3c00e841eb06fac4872a2757859e1ceaa6efd984628593b40ca1e19c7d773d00c144c525ac619d18c84a3f4718e2448b2fe324d9ccda27102519498e80f1478f37ba55bd6d27618c").unwrap());
+    assert_eq!(
+        decrypt_gcm(&key, &nonce, &aad, &ciphertext).unwrap(),
+        plaintext
+    );
+
+    let key = AES256Key::from_bytes(
+        hex::decode("feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308")
+            .unwrap()
+            .try_into()
+            .unwrap(),
+    );
+    let ciphertext = encrypt_gcm(&key, &nonce, &aad, &plaintext);
+    assert_eq!(ciphertext, hex::decode("522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f66276fc6ece0f4e1768cddf8853bb2d551b").unwrap());
+    assert_eq!(
+        decrypt_gcm(&key, &nonce, &aad, &ciphertext).unwrap(),
+        plaintext
+    );
+}
+
+#[test]
+fn gcm_tampering() {
+    let key = AES256Key::from_bytes(*b"0123456789abcdef0123456789abcdef");
+    let nonce = *b"abcdef012345";
+    let aad = b"header";
+    let plaintext = b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor";
+
+    let ciphertext = encrypt_gcm(&key, &nonce, aad, plaintext);
+    assert_eq!(ciphertext.len(), plaintext.len() + 16);
+
+    let mut tampered = ciphertext.clone();
+    tampered[3] ^= 0x01;
+    assert_eq!(
+        decrypt_gcm(&key, &nonce, aad, &tampered),
+        Err(GcmError::AuthenticationFailed)
+    );
+
+    let mut tampered = ciphertext.clone();
+    *tampered.last_mut().unwrap() ^= 0x80;
+    assert_eq!(
+        decrypt_gcm(&key, &nonce, aad, &tampered),
+        Err(GcmError::AuthenticationFailed)
+    );
+
+    assert_eq!(
+        decrypt_gcm(&key, &nonce, b"Header", &ciphertext),
+        Err(GcmError::AuthenticationFailed)
+    );
+    assert_eq!(
+        decrypt_gcm(&key, b"abcdef012346", aad, &ciphertext),
+        Err(GcmError::AuthenticationFailed)
+    );
+
+    assert_eq!(
+        decrypt_gcm(&key, &nonce, aad, &ciphertext[..15]),
+        Err(GcmError::InvalidLength)
+    );
+
+    assert_eq!(
+        decrypt_gcm(&key, &nonce, aad, &ciphertext).unwrap(),
+        plaintext
+    );
+}
+
+#[test]
+#[cfg(feature = "rand")]
+fn seal_round_trip() {
+    let key = AES256Key::from_bytes(*b"0123456789abcdef0123456789abcdef");
+
+    for plaintext in [&b""[..], b"I use Rust btw", &[0x42; 100]] {
+        let sealed = seal(&key, plaintext, b"header");
+        assert_eq!(sealed.len(), NONCE_SIZE + plaintext.len() + 16);
+        assert_eq!(open(&key, &sealed, b"header").unwrap(), plaintext);
+    }
+
+    // a fresh nonce for every message
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    assert_ne!(
+        seal(&key, b"I use Rust btw", &[]),
+        seal(&key, b"I use Rust btw", &[])
+    );
+}
+
+#[test]
+#[cfg(feature = "rand")]
+fn seal_tampering() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let sealed = seal(&key, b"I use Rust btw", b"header");
+
+    // nonce, ciphertext and tag
+    for i in [0, NONCE_SIZE, sealed.len() - 1] {
+        let mut tampered = sealed.clone();
+        tampered[i] ^= 0x01;
+        assert_eq!(
+            open(&key, &tampered, b"header"),
+            Err(GcmSivError::AuthenticationFailed)
+        );
+    }
+
+    assert_eq!(
+        open(&key, &sealed, b"other header"),
+        Err(GcmSivError::AuthenticationFailed)
+    );
+    assert_eq!(
+        open(
+            &AES128Key::from_bytes(*b"fedcba9876543210"),
+            &sealed,
+            b"header"
+        ),
+        Err(GcmSivError::AuthenticationFailed)
+    );
+}
+
+#[test]
+fn open_too_short() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+
+    for len in [0, NONCE_SIZE - 1, NONCE_SIZE + 15] {
+        assert_eq!(
+            open(&key, &vec![0; len], &[]),
+            Err(GcmSivError::InvalidLength)
+        );
+    }
+}
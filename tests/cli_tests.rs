@@ -0,0 +1,1724 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+fn aesculap() -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_aesculap"));
+    cmd.stdin(Stdio::null());
+    cmd
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("aesculap-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn skip_bytes_header_round_trip() {
+    let dir = temp_dir("skip_bytes_header_round_trip");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    let iv_file = dir.join("iv");
+    fs::write(&iv_file, b"abcdef0123456789").unwrap();
+
+    let header = b"\x89AES\r\n\x1a\n";
+    let plaintext = [
+        header.as_slice(),
+        b"felis eget nunc lobortis mattis aliquam",
+    ]
+    .concat();
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, &plaintext).unwrap();
+
+    let encrypted_file = dir.join("encrypted");
+    let status = aesculap()
+        .arg("encrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--cbc")
+        .arg("--iv-file")
+        .arg(&iv_file)
+        .args(["--skip-bytes", "8"])
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--output-file")
+        .arg(&encrypted_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let encrypted = fs::read(&encrypted_file).unwrap();
+    assert_eq!(&encrypted[..8], header);
+    assert_ne!(&encrypted[8..], &plaintext[8..]);
+
+    let decrypted_file = dir.join("decrypted");
+    let status = aesculap()
+        .arg("decrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--cbc")
+        .arg("--iv-file")
+        .arg(&iv_file)
+        .args(["--skip-bytes", "8"])
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--output-file")
+        .arg(&decrypted_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    assert_eq!(fs::read(&decrypted_file).unwrap(), plaintext);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn output_permissions_and_atomic_write() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = temp_dir("output_permissions_and_atomic_write");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"I use Rust btw").unwrap();
+
+    let encrypted_file = dir.join("encrypted");
+    let status = aesculap()
+        .arg("encrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--ecb")
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--output-file")
+        .arg(&encrypted_file)
+        .args(["--output-permissions", "600"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let mode = fs::metadata(&encrypted_file).unwrap().permissions().mode();
+    assert_eq!(mode & 0o7777, 0o600);
+
+    let encrypted = fs::read(&encrypted_file).unwrap();
+    assert_eq!(encrypted.len(), 16);
+
+    let mut entries: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    entries.sort();
+    assert_eq!(entries, ["encrypted", "key", "plain"]);
+
+    // a failing run must leave the existing output untouched
+    fs::write(&key_file, b"too short").unwrap();
+    let status = aesculap()
+        .arg("encrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--ecb")
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--output-file")
+        .arg(&encrypted_file)
+        .status()
+        .unwrap();
+    assert!(!status.success());
+    assert_eq!(fs::read(&encrypted_file).unwrap(), encrypted);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn in_place_round_trip() {
+    let dir = temp_dir("in_place_round_trip");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    let iv_file = dir.join("iv");
+    fs::write(&iv_file, b"abcdef0123456789").unwrap();
+
+    let plaintext = b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor";
+    let file = dir.join("file");
+    fs::write(&file, plaintext).unwrap();
+
+    for cmd in ["encrypt", "decrypt"] {
+        let status = aesculap()
+            .arg(cmd)
+            .arg("--key-file")
+            .arg(&key_file)
+            .arg("--cbc")
+            .arg("--iv-file")
+            .arg(&iv_file)
+            .arg("--input-file")
+            .arg(&file)
+            .arg("--in-place")
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        if cmd == "encrypt" {
+            let encrypted = fs::read(&file).unwrap();
+            assert_eq!(encrypted.len(), 80);
+            assert_ne!(&encrypted[..plaintext.len()], plaintext);
+        }
+    }
+
+    assert_eq!(fs::read(&file).unwrap(), plaintext);
+
+    // the plaintext is no multiple of 16 bytes, so decrypting it fails after the temporary file was created
+    let status = aesculap()
+        .arg("decrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--ecb")
+        .args(["--padding", "none"])
+        .arg("--input-file")
+        .arg(&file)
+        .arg("--in-place")
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(!status.success());
+    assert_eq!(fs::read(&file).unwrap(), plaintext);
+
+    // no temporary files are left behind
+    let mut files: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    files.sort();
+    assert_eq!(files, ["file", "iv", "key"]);
+
+    let status = aesculap()
+        .arg("encrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--ecb")
+        .arg("--stdin")
+        .arg("--in-place")
+        .status()
+        .unwrap();
+    assert!(!status.success());
+
+    let status = aesculap()
+        .arg("encrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--ecb")
+        .arg("--input-file")
+        .arg(&file)
+        .arg("--output-file")
+        .arg(dir.join("other"))
+        .arg("--in-place")
+        .status()
+        .unwrap();
+    assert!(!status.success());
+    assert!(!dir.join("other").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "bench")]
+#[test]
+fn bench_prints_throughput() {
+    let output = aesculap().args(["bench", "--size", "1"]).output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let results: Vec<&str> = stdout.lines().filter(|l| l.ends_with(" MiB/s")).collect();
+    assert_eq!(results.len(), 6);
+
+    for line in results {
+        let throughput = line.split_whitespace().rev().nth(1).unwrap();
+        assert!(throughput.parse::<f64>().unwrap() > 0.0);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupt_exits_cleanly() {
+    use std::time::Duration;
+
+    let dir = temp_dir("interrupt_exits_cleanly");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    // large enough that the encryption is still running when interrupted
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, vec![0x42; 4 * 1024 * 1024]).unwrap();
+
+    let mut child = aesculap()
+        .arg("encrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--ecb")
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--output-file")
+        .arg(dir.join("encrypted"))
+        .spawn()
+        .unwrap();
+
+    std::thread::sleep(Duration::from_millis(500));
+    // SAFETY: sending a signal to our own child process
+    unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGINT) };
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code(), Some(130));
+
+    let mut entries: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    entries.sort();
+    assert_eq!(entries, ["key", "plain"]);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn self_test_flag() {
+    let output = aesculap().arg("--self-test").output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"Self-test passed\n");
+}
+
+#[test]
+fn inline_hex_iv_matches_iv_file() {
+    let dir = temp_dir("inline_hex_iv_matches_iv_file");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    let iv_file = dir.join("iv");
+    fs::write(&iv_file, b"abcdef0123456789").unwrap();
+    let iv_hex = "61626364656630313233343536373839";
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"felis eget nunc lobortis mattis aliquam").unwrap();
+
+    let encrypt = |iv_arg: &str, iv: &std::ffi::OsStr| {
+        let output = aesculap()
+            .arg("encrypt")
+            .arg("--key-file")
+            .arg(&key_file)
+            .arg("--cbc")
+            .arg(iv_arg)
+            .arg(iv)
+            .arg("--input-file")
+            .arg(&plain_file)
+            .arg("--stdout")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        output.stdout
+    };
+
+    let from_file = encrypt("--iv-file", iv_file.as_os_str());
+    let from_hex = encrypt("--iv", iv_hex.as_ref());
+    assert_eq!(from_hex, from_file);
+
+    let encrypted_file = dir.join("encrypted");
+    fs::write(&encrypted_file, &from_hex).unwrap();
+
+    let output = aesculap()
+        .arg("decrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--cbc")
+        .args(["--iv", iv_hex])
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, fs::read(&plain_file).unwrap());
+
+    let status = aesculap()
+        .arg("encrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--ecb")
+        .args(["--iv", iv_hex])
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--stdout")
+        .status()
+        .unwrap();
+    assert!(!status.success());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+#[cfg(feature = "rand")]
+fn seed_makes_random_iv_reproducible() {
+    let dir = temp_dir("seed_makes_random_iv_reproducible");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"I use Rust btw").unwrap();
+
+    let iv_file = dir.join("iv");
+    let encrypt = |seed: &str| {
+        let output = aesculap()
+            .arg("encrypt")
+            .arg("--key-file")
+            .arg(&key_file)
+            .arg("--cbc")
+            .arg("--random-iv")
+            .arg(&iv_file)
+            .args(["--seed", seed])
+            .arg("--input-file")
+            .arg(&plain_file)
+            .arg("--stdout")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        (fs::read(&iv_file).unwrap(), output.stdout)
+    };
+
+    let first = encrypt("42");
+    assert_eq!(encrypt("42"), first);
+    assert_ne!(encrypt("43"), first);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn meta_sidecar_round_trip() {
+    let dir = temp_dir("meta_sidecar_round_trip");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    let plain_file = dir.join("plain");
+    // would lose its last byte if the padding of the sidecar was ignored
+    fs::write(&plain_file, b"I use Rust btw, I use Rust btw!\x01").unwrap();
+
+    let encrypted_file = dir.join("encrypted");
+    let status = aesculap()
+        .arg("encrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--cbc")
+        .args(["--iv", "61626364656630313233343536373839"])
+        .args(["--padding", "none"])
+        .arg("--meta")
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--output-file")
+        .arg(&encrypted_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let meta = fs::read_to_string(dir.join("encrypted.meta")).unwrap();
+    assert_eq!(
+        meta,
+        "mode=cbc\npadding=none\niv=61626364656630313233343536373839\n"
+    );
+
+    // neither mode, IV nor padding are given
+    let output = aesculap()
+        .arg("decrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, fs::read(&plain_file).unwrap());
+
+    // with the mode but no IV option, the IV is still taken from the sidecar instead of the ciphertext
+    let output = aesculap()
+        .arg("decrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--cbc")
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, fs::read(&plain_file).unwrap());
+
+    let stderr = failure(
+        aesculap()
+            .arg("decrypt")
+            .arg("--key-file")
+            .arg(&key_file)
+            .args(["--cbc", "--prepend-iv"])
+            .arg("--input-file")
+            .arg(&encrypted_file)
+            .arg("--stdout"),
+    );
+    assert!(stderr.contains("--prepend-iv"), "{stderr}");
+
+    // without a sidecar the mode is still required
+    fs::remove_file(dir.join("encrypted.meta")).unwrap();
+    let status = aesculap()
+        .arg("decrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(!status.success());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn aes_bits_must_match_key_size() {
+    let dir = temp_dir("aes_bits_must_match_key_size");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef0123456789abcdef").unwrap();
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"I use Rust btw").unwrap();
+
+    let encrypt = |bits: &str| {
+        aesculap()
+            .arg("encrypt")
+            .arg("--key-file")
+            .arg(&key_file)
+            .args(["--aes-bits", bits])
+            .arg("--ecb")
+            .arg("--input-file")
+            .arg(&plain_file)
+            .arg("--stdout")
+            .stderr(Stdio::null())
+            .output()
+            .unwrap()
+    };
+
+    assert!(!encrypt("128").status.success());
+    assert!(!encrypt("512").status.success());
+
+    let output = encrypt("256");
+    assert!(output.status.success());
+    assert_eq!(output.stdout.len(), 16);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn no_padding_keeps_trailing_zeroes() {
+    let dir = temp_dir("no_padding_keeps_trailing_zeroes");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    let mut plaintext = b"I use Rust btw".to_vec();
+    plaintext.resize(32, 0);
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, &plaintext).unwrap();
+
+    let encrypted_file = dir.join("encrypted");
+    let status = aesculap()
+        .arg("encrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--ecb")
+        .args(["--padding", "none"])
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--output-file")
+        .arg(&encrypted_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(fs::read(&encrypted_file).unwrap().len(), 32);
+
+    let output = aesculap()
+        .arg("decrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--ecb")
+        .args(["--padding", "none"])
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, plaintext);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn iv_counter_never_repeats() {
+    let dir = temp_dir("iv_counter_never_repeats");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"I use Rust btw").unwrap();
+
+    let state_file = dir.join("counter");
+    let encrypted_file = dir.join("encrypted");
+
+    let mut ivs = Vec::new();
+    for _ in 0..5 {
+        let status = aesculap()
+            .arg("encrypt")
+            .arg("--key-file")
+            .arg(&key_file)
+            .arg("--cbc")
+            .arg("--iv-counter")
+            .arg(&state_file)
+            .arg("--meta")
+            .arg("--input-file")
+            .arg(&plain_file)
+            .arg("--output-file")
+            .arg(&encrypted_file)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let meta = fs::read_to_string(dir.join("encrypted.meta")).unwrap();
+        ivs.push(
+            meta.lines()
+                .find(|l| l.starts_with("iv="))
+                .unwrap()
+                .to_owned(),
+        );
+    }
+
+    assert_eq!(fs::read_to_string(&state_file).unwrap(), "5\n");
+
+    ivs.sort();
+    ivs.dedup();
+    assert_eq!(ivs.len(), 5);
+
+    let output = aesculap()
+        .arg("decrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"I use Rust btw");
+
+    // concurrent runs wait for each other instead of reading the same counter
+    let children: Vec<_> = (0..8)
+        .map(|i| {
+            aesculap()
+                .arg("encrypt")
+                .arg("--key-file")
+                .arg(&key_file)
+                .arg("--cbc")
+                .arg("--iv-counter")
+                .arg(&state_file)
+                .arg("--meta")
+                .arg("--input-file")
+                .arg(&plain_file)
+                .arg("--output-file")
+                .arg(dir.join(format!("concurrent{i}")))
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+
+    assert_eq!(fs::read_to_string(&state_file).unwrap(), "13\n");
+
+    let mut ivs: Vec<_> = (0..8)
+        .map(|i| fs::read_to_string(dir.join(format!("concurrent{i}.meta"))).unwrap())
+        .collect();
+    ivs.sort();
+    ivs.dedup();
+    assert_eq!(ivs.len(), 8);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn info_reports_key_space() {
+    let dir = temp_dir("info_reports_key_space");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef0123456789abcdef").unwrap();
+
+    let output = aesculap()
+        .arg("info")
+        .arg("--key-file")
+        .arg(&key_file)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("256 bits (AES-256)"));
+    assert!(stdout.contains("2^256 keys"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn passphrase_round_trip() {
+    let dir = temp_dir("passphrase_round_trip");
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"I use Rust btw").unwrap();
+
+    let encrypted_file = dir.join("encrypted");
+    let status = aesculap()
+        .arg("encrypt")
+        .args(["--passphrase", "correct horse battery staple"])
+        .args(["--iterations", "1000"])
+        .args(["--salt", "000102030405060708090a0b0c0d0e0f"])
+        .arg("--cbc")
+        .args(["--iv", "61626364656630313233343536373839"])
+        .arg("--meta")
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--output-file")
+        .arg(&encrypted_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let meta = fs::read_to_string(dir.join("encrypted.meta")).unwrap();
+    assert!(meta.contains("\nsalt=000102030405060708090a0b0c0d0e0f\n"));
+    assert!(meta.ends_with("\niterations=1000\n"));
+
+    // salt and iterations come from the sidecar
+    let output = aesculap()
+        .arg("decrypt")
+        .args(["--passphrase", "correct horse battery staple"])
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"I use Rust btw");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+#[cfg(feature = "rand")]
+fn passphrase_header_round_trip() {
+    let dir = temp_dir("passphrase_header_round_trip");
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"I use Rust btw").unwrap();
+
+    // without --meta and --salt, the random salt is written in front of the ciphertext
+    let encrypted_file = dir.join("encrypted");
+    let status = aesculap()
+        .arg("encrypt")
+        .args(["--passphrase-env", "AESCULAP_TEST_PASSPHRASE"])
+        .env("AESCULAP_TEST_PASSPHRASE", "correct horse battery staple")
+        .args(["--kdf", "scrypt", "--scrypt-log-n", "4"])
+        .arg("--ecb")
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--output-file")
+        .arg(&encrypted_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(!dir.join("encrypted.meta").exists());
+
+    let encrypted = fs::read(&encrypted_file).unwrap();
+    assert!(encrypted.starts_with(b"aesculap"));
+
+    let output = aesculap()
+        .arg("decrypt")
+        .args(["--passphrase", "correct horse battery staple"])
+        .arg("--ecb")
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"I use Rust btw");
+
+    let mut child = aesculap()
+        .arg("decrypt")
+        .args(["--passphrase-env", "AESCULAP_TEST_PASSPHRASE"])
+        .env("AESCULAP_TEST_PASSPHRASE", "correct horse battery staple")
+        .arg("--ecb")
+        .arg("--stdin")
+        .arg("--stdout")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(&encrypted).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"I use Rust btw");
+
+    // a ciphertext without the header
+    let stderr = failure(
+        aesculap()
+            .arg("decrypt")
+            .args(["--passphrase", "correct horse battery staple"])
+            .arg("--ecb")
+            .arg("--input-file")
+            .arg(&plain_file)
+            .arg("--stdout"),
+    );
+    assert!(stderr.contains("Invalid key derivation header"), "{stderr}");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn crafted_kdf_header_fails_without_panic() {
+    let dir = temp_dir("crafted_kdf_header_fails_without_panic");
+
+    let header = |kdf: u8, params: [u32; 3]| {
+        let mut data = b"aesculap\x01".to_vec();
+        data.extend([kdf, 16]);
+        data.extend([0; 16]);
+        match kdf {
+            // scrypt: log_n, r and p
+            2 => {
+                data.push(params[0] as u8);
+                data.extend(params[1..].iter().flat_map(|n| n.to_be_bytes()));
+            }
+            // Argon2id: memory, iterations and parallelism
+            _ => data.extend(params.iter().flat_map(|n| n.to_be_bytes())),
+        }
+        data.extend([0; 16]);
+        data
+    };
+
+    for (name, data) in [
+        ("overflow", header(2, [4, u32::MAX, u32::MAX])),
+        ("scrypt_memory", header(2, [4, 1 << 28, 1])),
+        ("scrypt_cost", header(2, [40, 8, 1])),
+        ("argon2_memory", header(3, [u32::MAX, 1, 1])),
+    ] {
+        let encrypted_file = dir.join(name);
+        fs::write(&encrypted_file, data).unwrap();
+
+        let stderr = failure(
+            aesculap()
+                .arg("decrypt")
+                .args(["--passphrase", "correct horse battery staple"])
+                .arg("--ecb")
+                .arg("--input-file")
+                .arg(&encrypted_file)
+                .arg("--stdout"),
+        );
+        assert!(
+            stderr.contains("Invalid cost parameters"),
+            "{name}: {stderr}"
+        );
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn passphrase_derives_pbkdf2_key() {
+    let dir = temp_dir("passphrase_derives_pbkdf2_key");
+
+    // PBKDF2-HMAC-SHA256 of the passphrase with salt "NaCl" and 1000 iterations
+    let key_file = dir.join("key");
+    fs::write(
+        &key_file,
+        [
+            0xba, 0xec, 0x2a, 0x72, 0x03, 0x25, 0x33, 0xbc, 0xee, 0x30, 0x95, 0xa4, 0x47, 0x01,
+            0x30, 0xc8,
+        ],
+    )
+    .unwrap();
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"I use Rust btw").unwrap();
+
+    let encrypt = |key_args: &[&str]| {
+        let output = aesculap()
+            .arg("encrypt")
+            .args(key_args)
+            .arg("--ecb")
+            .arg("--input-file")
+            .arg(&plain_file)
+            .arg("--stdout")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        output.stdout
+    };
+
+    assert_eq!(
+        encrypt(&[
+            "--passphrase",
+            "correct horse battery staple",
+            "--salt",
+            "4e61436c",
+            "--iterations",
+            "1000",
+            "--aes-bits",
+            "128",
+        ]),
+        encrypt(&["--key-file", key_file.to_str().unwrap()])
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn passphrase_derives_scrypt_key() {
+    let dir = temp_dir("passphrase_derives_scrypt_key");
+
+    // scrypt of the passphrase with salt "NaCl", N = 16, r = 8 and p = 1
+    let key_file = dir.join("key");
+    fs::write(
+        &key_file,
+        [
+            0xd7, 0x56, 0x91, 0x8d, 0x08, 0x5f, 0x0b, 0x39, 0x6e, 0xf0, 0xc8, 0x18, 0x0e, 0x06,
+            0x34, 0x0b,
+        ],
+    )
+    .unwrap();
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"I use Rust btw").unwrap();
+
+    let encrypted_file = dir.join("encrypted");
+    let status = aesculap()
+        .arg("encrypt")
+        .args(["--passphrase", "correct horse battery staple"])
+        .args(["--salt", "4e61436c"])
+        .args(["--kdf", "scrypt", "--scrypt-log-n", "4"])
+        .args(["--aes-bits", "128"])
+        .arg("--ecb")
+        .arg("--meta")
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--output-file")
+        .arg(&encrypted_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let meta = fs::read_to_string(dir.join("encrypted.meta")).unwrap();
+    assert!(meta.ends_with("\nkdf=scrypt\nscrypt-log-n=4\nscrypt-r=8\nscrypt-p=1\n"));
+
+    let output = aesculap()
+        .arg("decrypt")
+        .args(["--key-file", key_file.to_str().unwrap()])
+        .arg("--ecb")
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"I use Rust btw");
+
+    // the key derivation function and its parameters come from the sidecar
+    let output = aesculap()
+        .arg("decrypt")
+        .args(["--passphrase", "correct horse battery staple"])
+        .args(["--aes-bits", "128"])
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"I use Rust btw");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn passphrase_derives_argon2id_key() {
+    let dir = temp_dir("passphrase_derives_argon2id_key");
+
+    // Argon2id of the passphrase with salt "NaCl and pepper", 64 KiB, 1 iteration and 2 lanes
+    let key_file = dir.join("key");
+    fs::write(
+        &key_file,
+        [
+            0xae, 0x8c, 0x16, 0xea, 0x45, 0xac, 0x51, 0xee, 0x7c, 0x6a, 0x39, 0x57, 0xaf, 0xdb,
+            0x42, 0x8f,
+        ],
+    )
+    .unwrap();
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"I use Rust btw").unwrap();
+
+    let encrypted_file = dir.join("encrypted");
+    let status = aesculap()
+        .arg("encrypt")
+        .args(["--passphrase", "correct horse battery staple"])
+        .args(["--salt", "4e61436c20616e6420706570706572"])
+        .args(["--kdf", "argon2id", "--argon2-memory", "64"])
+        .args(["--argon2-iterations", "1", "--argon2-parallelism", "2"])
+        .args(["--aes-bits", "128"])
+        .arg("--ecb")
+        .arg("--meta")
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--output-file")
+        .arg(&encrypted_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let meta = fs::read_to_string(dir.join("encrypted.meta")).unwrap();
+    assert!(meta.ends_with(
+        "\nkdf=argon2id\nargon2-memory=64\nargon2-iterations=1\nargon2-parallelism=2\n"
+    ));
+
+    let output = aesculap()
+        .arg("decrypt")
+        .args(["--key-file", key_file.to_str().unwrap()])
+        .arg("--ecb")
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"I use Rust btw");
+
+    // the parameters come from the sidecar
+    let output = aesculap()
+        .arg("decrypt")
+        .args(["--passphrase", "correct horse battery staple"])
+        .args(["--aes-bits", "128"])
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"I use Rust btw");
+
+    // cost parameters of another function are rejected
+    let status = aesculap()
+        .arg("decrypt")
+        .args(["--passphrase", "correct horse battery staple"])
+        .args(["--scrypt-log-n", "4"])
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(!status.success());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn key_hex_matches_key_file() {
+    let dir = temp_dir("key_hex_matches_key_file");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"I use Rust btw").unwrap();
+
+    let encrypt = |key_args: &[&str]| {
+        aesculap()
+            .arg("encrypt")
+            .args(key_args)
+            .arg("--ecb")
+            .arg("--input-file")
+            .arg(&plain_file)
+            .arg("--stdout")
+            .stderr(Stdio::null())
+            .output()
+            .unwrap()
+    };
+
+    let from_file = encrypt(&["--key-file", key_file.to_str().unwrap()]);
+    let from_hex = encrypt(&["--key-hex", "30313233343536373839616263646566"]);
+    assert!(from_hex.status.success());
+    assert_eq!(from_hex.stdout, from_file.stdout);
+
+    for key_args in [
+        &["--key-hex", "303132333435363738396162636465"][..],
+        &["--key-hex", "3031323334353637383961626364656"],
+        &["--key-hex", "3031323334353637383961626364656g"],
+        &[
+            "--key-hex",
+            "30313233343536373839616263646566",
+            "--key-file",
+            key_file.to_str().unwrap(),
+        ],
+    ] {
+        assert!(!encrypt(key_args).status.success());
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn key_and_iv_from_base64() {
+    let dir = temp_dir("key_and_iv_from_base64");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    let iv_file = dir.join("iv");
+    fs::write(&iv_file, b"abcdef0123456789").unwrap();
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"I use Rust btw").unwrap();
+
+    let encrypt = |args: &[&str]| {
+        aesculap()
+            .arg("encrypt")
+            .args(args)
+            .arg("--cbc")
+            .arg("--input-file")
+            .arg(&plain_file)
+            .arg("--stdout")
+            .stderr(Stdio::null())
+            .output()
+            .unwrap()
+    };
+
+    let from_files = encrypt(&[
+        "--key-file",
+        key_file.to_str().unwrap(),
+        "--iv-file",
+        iv_file.to_str().unwrap(),
+    ]);
+    let from_base64 = encrypt(&[
+        "--key-base64",
+        "MDEyMzQ1Njc4OWFiY2RlZg==",
+        "--iv-base64",
+        "YWJjZGVmMDEyMzQ1Njc4OQ==",
+    ]);
+    assert!(from_base64.status.success());
+    assert_eq!(from_base64.stdout, from_files.stdout);
+
+    let encrypted_file = dir.join("encrypted");
+    fs::write(&encrypted_file, &from_base64.stdout).unwrap();
+    let output = aesculap()
+        .arg("decrypt")
+        .args(["--key-base64", "MDEyMzQ1Njc4OWFiY2RlZg=="])
+        .args(["--cbc", "--iv-base64", "YWJjZGVmMDEyMzQ1Njc4OQ=="])
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"I use Rust btw");
+
+    for args in [
+        // 15 bytes
+        &[
+            "--key-base64",
+            "MDEyMzQ1Njc4OWFiY2Rl",
+            "--iv",
+            "61626364656630313233343536373839",
+        ][..],
+        &[
+            "--key-base64",
+            "MDEyMzQ1Njc4OWFiY2RlZg",
+            "--iv",
+            "61626364656630313233343536373839",
+        ],
+        &[
+            "--key-base64",
+            "MDEyMzQ1Njc4OWFiY2RlZg==",
+            "--iv-base64",
+            "YWJjZGVmMDEyMzQ1Njc4",
+        ],
+        &[
+            "--key-base64",
+            "MDEyMzQ1Njc4OWFiY2RlZg==",
+            "--iv-base64",
+            "YWJjZGVmMDEyMzQ1Njc4OQ=!",
+        ],
+    ] {
+        assert!(!encrypt(args).status.success());
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn padding_must_match_sidecar() {
+    let dir = temp_dir("padding_must_match_sidecar");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"I use Rust btw").unwrap();
+
+    let encrypted_file = dir.join("encrypted");
+    let status = aesculap()
+        .arg("encrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--ecb")
+        .arg("--meta")
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--output-file")
+        .arg(&encrypted_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    for mode in [None, Some("--ecb")] {
+        let output = aesculap()
+            .arg("decrypt")
+            .arg("--key-file")
+            .arg(&key_file)
+            .args(mode)
+            .args(["--padding", "zero"])
+            .arg("--input-file")
+            .arg(&encrypted_file)
+            .arg("--stdout")
+            .env("RUST_LOG", "error")
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        assert!(output.stdout.is_empty());
+
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("padded with pkcs7, not zero"), "{stderr}");
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn corrupted_ciphertext_fails_without_panic() {
+    let dir = temp_dir("corrupted_ciphertext_fails_without_panic");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    // a single block that doesn't decrypt to valid padding
+    let encrypted_file = dir.join("encrypted");
+    fs::write(&encrypted_file, [0xff; 16]).unwrap();
+
+    let output = aesculap()
+        .arg("decrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--ecb")
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    // errors are logged even without RUST_LOG
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("[ERROR aesculap::decryption]"), "{stderr}");
+    assert!(stderr.contains("the padding is invalid"), "{stderr}");
+    assert!(!stderr.contains("panicked"), "{stderr}");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+/// Run the binary and return its stderr, asserting a clean failure
+fn failure(cmd: &mut Command) -> String {
+    let output = cmd.output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    // the library may log the error before the CLI reports it
+    assert!(
+        stderr.lines().any(|line| line.starts_with("Error: ")),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("panicked"), "{stderr}");
+    stderr
+}
+
+#[test]
+fn missing_files_are_reported() {
+    let dir = temp_dir("missing_files_are_reported");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+    let missing = dir.join("missing");
+
+    let stderr = failure(
+        aesculap()
+            .arg("encrypt")
+            .arg("--key-file")
+            .arg(&missing)
+            .arg("--ecb")
+            .arg("--input-file")
+            .arg(&key_file)
+            .arg("--stdout"),
+    );
+    assert!(
+        stderr.contains(&format!("Cannot read {}", missing.display())),
+        "{stderr}"
+    );
+
+    let stderr = failure(
+        aesculap()
+            .arg("decrypt")
+            .arg("--key-file")
+            .arg(&key_file)
+            .arg("--ecb")
+            .arg("--input-file")
+            .arg(&missing)
+            .arg("--stdout"),
+    );
+    assert!(
+        stderr.contains(&format!("Cannot read {}", missing.display())),
+        "{stderr}"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn malformed_keys_are_reported() {
+    let dir = temp_dir("malformed_keys_are_reported");
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"I use Rust btw").unwrap();
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"too short").unwrap();
+
+    let stderr = failure(
+        aesculap()
+            .arg("encrypt")
+            .arg("--key-file")
+            .arg(&key_file)
+            .arg("--ecb")
+            .arg("--input-file")
+            .arg(&plain_file)
+            .arg("--stdout"),
+    );
+    assert!(stderr.contains("not 9 bytes"), "{stderr}");
+
+    let stderr = failure(
+        aesculap()
+            .arg("encrypt")
+            .args(["--key-hex", "00112233445566778899aabbccddeeff00"])
+            .arg("--ecb")
+            .arg("--input-file")
+            .arg(&plain_file)
+            .arg("--stdout"),
+    );
+    assert!(stderr.contains("not 17 bytes"), "{stderr}");
+
+    // the argument parser rejects non-hex digits
+    let output = aesculap()
+        .arg("encrypt")
+        .args(["--key-hex", "00112233445566778899aabbccddeefg"])
+        .arg("--ecb")
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("hex digits only"), "{stderr}");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn armored_round_trip() {
+    let dir = temp_dir("armored_round_trip");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    let plaintext: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, &plaintext).unwrap();
+
+    let armored_file = dir.join("armored");
+    let status = aesculap()
+        .arg("encrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .args(["--cbc", "--iv", "61626364656630313233343536373839"])
+        .arg("--armor")
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--output-file")
+        .arg(&armored_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let armored = fs::read_to_string(&armored_file).unwrap();
+    assert!(armored.lines().all(|line| line.len() <= 64));
+    assert_eq!(armored.lines().next().unwrap().len(), 64);
+
+    let output = aesculap()
+        .arg("decrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .args(["--cbc", "--iv", "61626364656630313233343536373839"])
+        .arg("--base64")
+        .arg("--input-file")
+        .arg(&armored_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, plaintext);
+
+    fs::write(&armored_file, armored.replace('A', "*")).unwrap();
+    let stderr = failure(
+        aesculap()
+            .arg("decrypt")
+            .arg("--key-file")
+            .arg(&key_file)
+            .args(["--cbc", "--iv", "61626364656630313233343536373839"])
+            .arg("--armor")
+            .arg("--input-file")
+            .arg(&armored_file)
+            .arg("--stdout"),
+    );
+    assert!(stderr.contains("Invalid Base64 character"), "{stderr}");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn prepended_iv_round_trip() {
+    let dir = temp_dir("prepended_iv_round_trip");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"I use Rust btw").unwrap();
+
+    let encrypted_file = dir.join("encrypted");
+    let status = aesculap()
+        .arg("encrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .args(["--cbc", "--iv", "61626364656630313233343536373839"])
+        .arg("--prepend-iv")
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--output-file")
+        .arg(&encrypted_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let encrypted = fs::read(&encrypted_file).unwrap();
+    assert_eq!(encrypted.len(), 32);
+    assert_eq!(&encrypted[..16], b"abcdef0123456789");
+
+    // the IV is taken from the ciphertext
+    for args in [&["--cbc"][..], &["--cbc", "--prepend-iv"]] {
+        let output = aesculap()
+            .arg("decrypt")
+            .arg("--key-file")
+            .arg(&key_file)
+            .args(args)
+            .arg("--input-file")
+            .arg(&encrypted_file)
+            .arg("--stdout")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"I use Rust btw");
+    }
+
+    fs::write(&encrypted_file, &encrypted[..10]).unwrap();
+    let stderr = failure(
+        aesculap()
+            .arg("decrypt")
+            .arg("--key-file")
+            .arg(&key_file)
+            .arg("--cbc")
+            .arg("--input-file")
+            .arg(&encrypted_file)
+            .arg("--stdout"),
+    );
+    assert!(stderr.contains("only 10 bytes"), "{stderr}");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn authenticated_round_trip() {
+    let dir = temp_dir("authenticated_round_trip");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"HEADI use Rust btw").unwrap();
+
+    let encrypted_file = dir.join("encrypted");
+    let status = aesculap()
+        .arg("encrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .args(["--cbc", "--iv", "61626364656630313233343536373839"])
+        .args(["--skip-bytes", "4", "--authenticate"])
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--output-file")
+        .arg(&encrypted_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // header, one block and the tag
+    let encrypted = fs::read(&encrypted_file).unwrap();
+    assert_eq!(encrypted.len(), 4 + 16 + 32);
+
+    let decrypt = |file: &PathBuf| {
+        let mut cmd = aesculap();
+        cmd.arg("decrypt")
+            .arg("--key-file")
+            .arg(&key_file)
+            .args(["--cbc", "--iv", "61626364656630313233343536373839"])
+            .args(["--skip-bytes", "4", "--authenticate"])
+            .arg("--input-file")
+            .arg(file)
+            .arg("--stdout");
+        cmd
+    };
+
+    let output = decrypt(&encrypted_file).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"HEADI use Rust btw");
+
+    // a modified ciphertext or header is rejected before anything is decrypted
+    for i in [0, 10, encrypted.len() - 1] {
+        let mut tampered = encrypted.clone();
+        tampered[i] ^= 1;
+        fs::write(&encrypted_file, &tampered).unwrap();
+
+        let stderr = failure(&mut decrypt(&encrypted_file));
+        assert!(
+            stderr.contains("authentication tag does not match"),
+            "{stderr}"
+        );
+    }
+
+    fs::write(&encrypted_file, &encrypted[..20]).unwrap();
+    let stderr = failure(&mut decrypt(&encrypted_file));
+    assert!(stderr.contains("too short"), "{stderr}");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn keygen_writes_key_file() {
+    let dir = temp_dir("keygen_writes_key_file");
+
+    let key_file = dir.join("key");
+    let status = aesculap()
+        .arg("keygen")
+        .args(["--bits", "256"])
+        .arg("--output-file")
+        .arg(&key_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let key = fs::read(&key_file).unwrap();
+    assert_eq!(key.len(), 32);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&key_file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    let stderr = failure(aesculap().arg("keygen").arg("--output-file").arg(&key_file));
+    assert!(stderr.contains("already exists"), "{stderr}");
+    assert_eq!(fs::read(&key_file).unwrap(), key);
+
+    let status = aesculap()
+        .arg("keygen")
+        .args(["--bits", "128"])
+        .arg("--output-file")
+        .arg(&key_file)
+        .arg("--force")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(fs::read(&key_file).unwrap().len(), 16);
+
+    let output = aesculap()
+        .arg("keygen")
+        .args(["--format", "hex", "--stdout"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout.len(), 65);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "openssl-compat")]
+#[test]
+fn openssl_compat_round_trip() {
+    let dir = temp_dir("openssl_compat_round_trip");
+
+    // printf 'I use Rust btw, and OpenSSL too.\n' | openssl enc -aes-128-cbc -md md5 -a -pass pass:secret
+    let encrypted_file = dir.join("encrypted");
+    fs::write(
+        &encrypted_file,
+        "U2FsdGVkX1/JjMAb8FgIwvA/tdOZ7F+Isr4tTHBZhYwGfsL3l0kuu1/AJF3RjkPY\n\
+         CpJqLSNH2PL42odDsBkh5A==\n",
+    )
+    .unwrap();
+
+    let output = aesculap()
+        .arg("decrypt")
+        .args(["--passphrase", "secret", "--aes-bits", "128"])
+        .args(["--openssl-compat", "--cbc", "--armor"])
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"I use Rust btw, and OpenSSL too.\n");
+
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, b"I use Rust btw").unwrap();
+
+    let status = aesculap()
+        .arg("encrypt")
+        .args(["--passphrase", "secret"])
+        .args(["--openssl-compat", "--cbc"])
+        .arg("--input-file")
+        .arg(&plain_file)
+        .arg("--output-file")
+        .arg(&encrypted_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let encrypted = fs::read(&encrypted_file).unwrap();
+    assert!(encrypted.starts_with(b"Salted__"));
+    assert_eq!(encrypted.len(), 32);
+
+    let output = aesculap()
+        .arg("decrypt")
+        .args(["--passphrase", "secret"])
+        .args(["--openssl-compat", "--cbc"])
+        .arg("--input-file")
+        .arg(&encrypted_file)
+        .arg("--stdout")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"I use Rust btw");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn large_file_round_trip_in_chunks() {
+    let dir = temp_dir("large_file_round_trip_in_chunks");
+
+    let key_file = dir.join("key");
+    fs::write(&key_file, b"0123456789abcdef").unwrap();
+
+    let iv_file = dir.join("iv");
+    fs::write(&iv_file, b"abcdef0123456789").unwrap();
+
+    let plaintext: Vec<u8> = (0..5 * 1024 * 1024 + 7).map(|i| (i % 251) as u8).collect();
+    let plain_file = dir.join("plain");
+    fs::write(&plain_file, &plaintext).unwrap();
+
+    let encrypt = |chunk_size: Option<&str>, encrypted_file: &PathBuf| {
+        let mut cmd = aesculap();
+        cmd.arg("encrypt")
+            .arg("--key-file")
+            .arg(&key_file)
+            .arg("--cbc")
+            .arg("--iv-file")
+            .arg(&iv_file)
+            .arg("--input-file")
+            .arg(&plain_file)
+            .arg("--output-file")
+            .arg(encrypted_file);
+        if let Some(chunk_size) = chunk_size {
+            cmd.args(["--chunk-size", chunk_size]);
+        }
+        assert!(cmd.status().unwrap().success());
+        fs::read(encrypted_file).unwrap()
+    };
+
+    let encrypted_file = dir.join("encrypted");
+    let encrypted = encrypt(None, &encrypted_file);
+    assert_eq!(encrypted.len(), (plaintext.len() / 16 + 1) * 16);
+    assert_eq!(encrypt(Some("1"), &dir.join("encrypted-1k")), encrypted);
+
+    let output = aesculap()
+        .arg("decrypt")
+        .arg("--key-file")
+        .arg(&key_file)
+        .arg("--cbc")
+        .arg("--iv-file")
+        .arg(&iv_file)
+        .args(["--chunk-size", "3"])
+        .arg("--stdin")
+        .arg("--stdout")
+        .stdin(fs::File::open(&encrypted_file).unwrap())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout == plaintext);
+
+    fs::remove_dir_all(dir).unwrap();
+}
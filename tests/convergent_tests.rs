@@ -0,0 +1,33 @@
+use aesculap::convergent::{decrypt_convergent, encrypt_convergent};
+use aesculap::key::AES128Key;
+
+const PLAINTEXT: &[u8] = b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor";
+
+#[test]
+fn identical_inputs_encrypt_identically() {
+    let master_key = AES128Key::from_bytes(*b"0123456789abcdef");
+
+    let (tag, ciphertext) = encrypt_convergent(PLAINTEXT, &master_key);
+    assert_eq!(
+        encrypt_convergent(PLAINTEXT, &master_key),
+        (tag, ciphertext.clone())
+    );
+
+    let (other_tag, other_ciphertext) = encrypt_convergent(b"I use Rust btw", &master_key);
+    assert_ne!(other_tag, tag);
+    assert_ne!(other_ciphertext[..16], ciphertext[..16]);
+}
+
+#[test]
+fn round_trip_and_tampering() {
+    let master_key = AES128Key::from_bytes(*b"0123456789abcdef");
+
+    let (tag, mut ciphertext) = encrypt_convergent(PLAINTEXT, &master_key);
+    assert_eq!(
+        decrypt_convergent(&tag, &ciphertext, &master_key).unwrap(),
+        PLAINTEXT
+    );
+
+    ciphertext[0] ^= 1;
+    assert!(decrypt_convergent(&tag, &ciphertext, &master_key).is_err());
+}
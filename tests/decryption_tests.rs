@@ -1,9 +1,17 @@
 use aesculap::block::Block;
-use aesculap::decryption::{decrypt_block, decrypt_bytes};
-use aesculap::key::{AES128Key, AES192Key, AES256Key};
-use aesculap::padding::{BytePadding, Padding, Pkcs7Padding, ZeroPadding};
+use aesculap::decryption::{
+    decrypt_block, decrypt_bytes, decrypt_bytes_cbc, decrypt_bytes_cts, decrypt_records,
+    DecryptError,
+};
+use aesculap::encryption::{
+    encrypt_block, encrypt_bytes, encrypt_bytes_cts, encrypt_records, CtsError,
+};
+use aesculap::key::{AES128Key, AES192Key, AES256Key, Key};
+use aesculap::padding::{BytePadding, NoPadding, Padding, Pkcs7Padding, ZeroPadding};
 use aesculap::EncryptionMode;
 use aesculap::InitializationVector;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 #[test]
 fn single_block_aes128_pkcs() {
@@ -18,7 +26,7 @@ fn single_block_aes128_pkcs() {
     let key = AES128Key::from_bytes(*key_text);
 
     decrypt_block(&mut block, &key);
-    let bytes = Pkcs7Padding.unpad(&[block.dump_bytes()]);
+    let bytes = Pkcs7Padding.unpad(&[block.dump_bytes()]).unwrap();
 
     let decrypted_text = b"I use Rust btw";
 
@@ -38,7 +46,7 @@ fn single_block_aes128_byte_padding() {
     let key = AES128Key::from_bytes(*key_text);
 
     decrypt_block(&mut block, &key);
-    let bytes = BytePadding(0x69).unpad(&[block.dump_bytes()]);
+    let bytes = BytePadding(0x69).unpad(&[block.dump_bytes()]).unwrap();
 
     let decrypted_text = b"I use Rust btw";
 
@@ -58,7 +66,7 @@ fn single_block_aes128_zero_padding() {
     let key = AES128Key::from_bytes(*key_text);
 
     decrypt_block(&mut block, &key);
-    let bytes = ZeroPadding.unpad(&[block.dump_bytes()]);
+    let bytes = ZeroPadding.unpad(&[block.dump_bytes()]).unwrap();
 
     let decrypted_text = b"I use Rust btw";
 
@@ -78,7 +86,7 @@ fn single_block_aes192_pkcs() {
     let key = AES192Key::from_bytes(*key_text);
 
     decrypt_block(&mut block, &key);
-    let bytes = Pkcs7Padding.unpad(&[block.dump_bytes()]);
+    let bytes = Pkcs7Padding.unpad(&[block.dump_bytes()]).unwrap();
 
     let decrypted_text = b"I use Rust btw";
 
@@ -98,7 +106,7 @@ fn single_block_aes256_pkcs() {
     let key = AES256Key::from_bytes(*key_text);
 
     decrypt_block(&mut block, &key);
-    let bytes = Pkcs7Padding.unpad(&[block.dump_bytes()]);
+    let bytes = Pkcs7Padding.unpad(&[block.dump_bytes()]).unwrap();
 
     let decrypted_text = b"I use Rust btw";
 
@@ -130,7 +138,9 @@ fn multiple_blocks_aes128_pkcs() {
         decrypt_block(block, &key);
     }
 
-    let bytes = Pkcs7Padding.unpad(&blocks.iter().map(|b| b.dump_bytes()).collect::<Vec<_>>());
+    let bytes = Pkcs7Padding
+        .unpad(&blocks.iter().map(|b| b.dump_bytes()).collect::<Vec<_>>())
+        .unwrap();
 
     let decrypted_text = b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor nec feugiat nisl pretium fusce";
 
@@ -197,3 +207,320 @@ fn raw_bytes_aes128_pkcs_cbc() {
 
     assert_eq!(decrypted_bytes, encryption_text.to_vec());
 }
+
+#[test]
+fn records_round_trip() {
+    let key = AES256Key::from_bytes(*b"0123456789abcdef0123456789abcdef");
+    let records: [&[u8]; 3] = [b"felis eget", b"nunc lobortis mattis aliquam", b""];
+
+    let encrypted = encrypt_records(&records, &key, u128::MAX);
+    let encrypted: Vec<&[u8]> = encrypted.iter().map(Vec::as_slice).collect();
+
+    let decrypted = decrypt_records(&encrypted, &key, u128::MAX).unwrap();
+    assert_eq!(decrypted, records);
+}
+
+#[test]
+fn cbc_with_direct_iv() {
+    let key = AES192Key::from_bytes(*b"0123456789abcdef01234567");
+    let encrypted = [0x42; 48];
+
+    for i in 0..3 {
+        let iv = InitializationVector::from(i as u128);
+
+        assert_eq!(
+            decrypt_bytes_cbc(&encrypted, &key, None::<Pkcs7Padding>, iv),
+            decrypt_bytes(
+                &encrypted,
+                &key,
+                None::<Pkcs7Padding>,
+                EncryptionMode::CBC(iv)
+            )
+        );
+    }
+}
+
+#[test]
+fn ctr_round_trip() {
+    let key = AES256Key::from_bytes(*b"0123456789abcdef0123456789abcdef");
+    let text = b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor";
+
+    for len in [0, 1, 15, 16, 17, text.len()] {
+        let counter = u128::MAX - 1;
+        let encrypted = encrypt_bytes(
+            &text[..len],
+            &key,
+            &Pkcs7Padding,
+            EncryptionMode::CTR(counter),
+        );
+        assert_eq!(encrypted.len(), len);
+
+        let decrypted = decrypt_bytes(
+            &encrypted,
+            &key,
+            Some(Pkcs7Padding),
+            EncryptionMode::CTR(counter),
+        )
+        .unwrap();
+        assert_eq!(decrypted, text[..len]);
+    }
+}
+
+#[test]
+fn cfb_nist_vector() {
+    // see NIST SP 800-38A, F.3.14
+    let key = AES128Key::from_bytes(0x2b7e151628aed2a6abf7158809cf4f3c_u128.to_be_bytes());
+    let iv = InitializationVector::from(0x000102030405060708090a0b0c0d0e0f);
+
+    let encrypted: Vec<u8> = [
+        0x3b3fd92eb72dad20333449f8e83cfb4a_u128,
+        0xc8a64537a0b3a93fcde3cdad9f1ce58b,
+        0x26751f67a3cbb140b1808cf187a4f4df,
+        0xc04b05357c5d1c0eeac4c66f9ff7f2e6,
+    ]
+    .iter()
+    .flat_map(|b| b.to_be_bytes())
+    .collect();
+    let expected: Vec<u8> = [
+        0x6bc1bee22e409f96e93d7e117393172a_u128,
+        0xae2d8a571e03ac9c9eb76fac45af8e51,
+        0x30c81c46a35ce411e5fbc1191a0a52ef,
+        0xf69f2445df4f9b17ad2b417be66c3710,
+    ]
+    .iter()
+    .flat_map(|b| b.to_be_bytes())
+    .collect();
+
+    let decrypted = decrypt_bytes(
+        &encrypted,
+        &key,
+        None::<Pkcs7Padding>,
+        EncryptionMode::CFB(iv),
+    );
+    assert_eq!(decrypted.unwrap(), expected);
+
+    let decrypted = decrypt_bytes(
+        &encrypted[..37],
+        &key,
+        Some(Pkcs7Padding),
+        EncryptionMode::CFB(iv),
+    );
+    assert_eq!(decrypted.unwrap(), expected[..37]);
+}
+
+#[test]
+fn cfb_round_trip() {
+    let key = AES192Key::from_bytes(*b"0123456789abcdef01234567");
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+    let text = b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor";
+
+    for len in [0, 1, 15, 16, 17, text.len()] {
+        let encrypted = encrypt_bytes(&text[..len], &key, &Pkcs7Padding, EncryptionMode::CFB(iv));
+        assert_eq!(encrypted.len(), len);
+
+        let decrypted = decrypt_bytes(
+            &encrypted,
+            &key,
+            Some(Pkcs7Padding),
+            EncryptionMode::CFB(iv),
+        )
+        .unwrap();
+        assert_eq!(decrypted, text[..len]);
+    }
+}
+
+#[test]
+fn ofb_round_trip() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+    let text = b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor";
+
+    for len in [0, 1, 15, 16, 17, 31, 32, 33, text.len()] {
+        let encrypted = encrypt_bytes(&text[..len], &key, &Pkcs7Padding, EncryptionMode::OFB(iv));
+        assert_eq!(encrypted.len(), len);
+
+        let decrypted = decrypt_bytes(
+            &encrypted,
+            &key,
+            Some(Pkcs7Padding),
+            EncryptionMode::OFB(iv),
+        )
+        .unwrap();
+        assert_eq!(decrypted, text[..len]);
+    }
+}
+
+#[test]
+fn pcbc_round_trip() {
+    let key = AES256Key::from_bytes(*b"0123456789abcdef0123456789abcdef");
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+    let text = b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor";
+
+    for len in [0, 1, 15, 16, 17, text.len()] {
+        let encrypted = encrypt_bytes(&text[..len], &key, &Pkcs7Padding, EncryptionMode::PCBC(iv));
+
+        let decrypted = decrypt_bytes(
+            &encrypted,
+            &key,
+            Some(Pkcs7Padding),
+            EncryptionMode::PCBC(iv),
+        )
+        .unwrap();
+        assert_eq!(decrypted, text[..len]);
+    }
+}
+
+#[test]
+fn pcbc_corruption_propagates() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+    let text = [0x42; 64];
+
+    let mut encrypted = encrypt_bytes(&text, &key, &Pkcs7Padding, EncryptionMode::PCBC(iv));
+    encrypted[20] ^= 0x01;
+
+    let decrypted = decrypt_bytes(
+        &encrypted,
+        &key,
+        None::<Pkcs7Padding>,
+        EncryptionMode::PCBC(iv),
+    )
+    .unwrap();
+
+    // the first block is intact, every following block is garbled
+    assert_eq!(decrypted[..16], text[..16]);
+    for block in decrypted[16..64].chunks(16) {
+        assert_ne!(block, &text[..16]);
+    }
+
+    // unlike CBC, where only the corrupted and the next block are affected
+    let mut encrypted = encrypt_bytes(&text, &key, &Pkcs7Padding, EncryptionMode::CBC(iv));
+    encrypted[20] ^= 0x01;
+
+    let decrypted = decrypt_bytes(
+        &encrypted,
+        &key,
+        None::<Pkcs7Padding>,
+        EncryptionMode::CBC(iv),
+    )
+    .unwrap();
+    assert_eq!(decrypted[48..64], text[48..64]);
+}
+
+#[test]
+fn block_round_trip_random() {
+    fn round_trip<const R: usize, K: Key<R>>(block: Block, key: &K) {
+        let mut encrypted = block;
+        encrypt_block(&mut encrypted, key);
+        assert_ne!(encrypted, block);
+
+        decrypt_block(&mut encrypted, key);
+        assert_eq!(encrypted, block);
+    }
+
+    let mut rng = StdRng::seed_from_u64(256);
+
+    for _ in 0..100 {
+        let block = Block::from_bytes(rng.gen());
+
+        round_trip(block, &AES128Key::from_bytes(rng.gen()));
+        round_trip(block, &AES192Key::from_bytes(rng.gen()));
+        round_trip(block, &AES256Key::from_bytes(rng.gen()));
+    }
+}
+
+#[test]
+fn cts_round_trip() {
+    let key = AES192Key::from_bytes(*b"0123456789abcdef01234567");
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+    let text = b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor";
+
+    for len in [17, 31, 32, 33, 47, 48, 64, text.len()] {
+        let encrypted = encrypt_bytes_cts(&text[..len], &key, iv).unwrap();
+        assert_eq!(encrypted.len(), len);
+
+        let decrypted = decrypt_bytes_cts(&encrypted, &key, iv).unwrap();
+        assert_eq!(decrypted, text[..len]);
+    }
+
+    assert_eq!(
+        decrypt_bytes_cts(&text[..16], &key, iv),
+        Err(CtsError::TooShort)
+    );
+}
+
+#[test]
+fn no_padding_keeps_trailing_zeroes() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+
+    let mut text = b"I use Rust btw".to_vec();
+    text.resize(32, 0);
+
+    let encrypted = encrypt_bytes(&text, &key, &NoPadding, EncryptionMode::CBC(iv));
+    assert_eq!(encrypted.len(), 32);
+
+    let decrypted = decrypt_bytes(&encrypted, &key, Some(NoPadding), EncryptionMode::CBC(iv));
+    assert_eq!(decrypted.unwrap(), text);
+}
+
+#[test]
+fn bad_padding_is_an_error() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+
+    // ends with a zero byte, which is never valid PKCS #7 padding
+    let mut text = b"I use Rust btw".to_vec();
+    text.resize(16, 0);
+
+    let encrypted = encrypt_bytes(&text, &key, &NoPadding, EncryptionMode::CBC(iv));
+    assert!(decrypt_bytes(
+        &encrypted,
+        &key,
+        Some(Pkcs7Padding),
+        EncryptionMode::CBC(iv)
+    )
+    .is_err());
+}
+
+#[test]
+fn corrupted_ciphertext_is_invalid_padding() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+
+    let mut encrypted = encrypt_bytes(b"I use Rust btw", &key, &Pkcs7Padding, EncryptionMode::ECB);
+    let last = encrypted.len() - 1;
+    encrypted[last] ^= 0xff;
+
+    let err = decrypt_bytes(&encrypted, &key, Some(Pkcs7Padding), EncryptionMode::ECB).unwrap_err();
+    assert!(matches!(err, DecryptError::InvalidPadding(_)), "{err:?}");
+
+    let err = decrypt_bytes(
+        &encrypted[..last],
+        &key,
+        Some(Pkcs7Padding),
+        EncryptionMode::ECB,
+    )
+    .unwrap_err();
+    assert_eq!(err, DecryptError::NotBlockAligned);
+}
+
+#[test]
+fn large_ecb_input_matches_single_blocks() {
+    // large enough to be split across threads with the `parallel` feature
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let ciphertext: Vec<u8> = (0..16 * 20_000).map(|i| (i % 251) as u8).collect();
+
+    let expected: Vec<u8> = ciphertext
+        .chunks_exact(16)
+        .flat_map(|chunk| {
+            let mut block = Block::from_bytes(chunk.try_into().unwrap());
+            decrypt_block(&mut block, &key);
+            block.dump_bytes()
+        })
+        .collect();
+
+    assert_eq!(
+        decrypt_bytes(&ciphertext, &key, None::<NoPadding>, EncryptionMode::ECB).unwrap(),
+        expected
+    );
+}
@@ -1,14 +1,21 @@
 use aesculap::block::Block;
-use aesculap::encryption::{encrypt_block, encrypt_bytes};
-use aesculap::key::{AES128Key, AES192Key, AES256Key};
-use aesculap::padding::{BytePadding, Pkcs7Padding, ZeroPadding};
+use aesculap::cipher::Cipher;
+use aesculap::encryption::{
+    encrypt_block, encrypt_bytes, encrypt_bytes_cbc, encrypt_bytes_cts, encrypt_records,
+    keystream_with, output_len, try_encrypt_bytes, CtrKeystream, CtsError,
+};
+use std::cell::Cell;
+
+use aesculap::decryption::{decrypt_block, decrypt_bytes};
+use aesculap::key::{AES128Key, AES192Key, AES256Key, RoundKeyProvider, Subkey};
+use aesculap::padding::{BytePadding, NoPadding, Padding, PaddingError, Pkcs7Padding, ZeroPadding};
 use aesculap::EncryptionMode;
 use aesculap::InitializationVector;
 
 #[test]
 fn single_block_aes128_pkcs() {
     let encryption_text = b"I use Rust btw";
-    let mut blocks = Block::load(encryption_text, &Pkcs7Padding);
+    let mut blocks = Block::load(encryption_text, &Pkcs7Padding).unwrap();
     assert_eq!(blocks.len(), 1);
 
     let key_text = b"0123456789abcdef";
@@ -27,7 +34,7 @@ fn single_block_aes128_pkcs() {
 #[test]
 fn single_block_aes128_byte_padding() {
     let encryption_text = b"I use Rust btw";
-    let mut blocks = Block::load(encryption_text, &BytePadding(0x69));
+    let mut blocks = Block::load(encryption_text, &BytePadding(0x69)).unwrap();
     assert_eq!(blocks.len(), 1);
 
     let key_text = b"0123456789abcdef";
@@ -46,7 +53,7 @@ fn single_block_aes128_byte_padding() {
 #[test]
 fn single_block_aes128_zero_padding() {
     let encryption_text = b"I use Rust btw";
-    let mut blocks = Block::load(encryption_text, &ZeroPadding);
+    let mut blocks = Block::load(encryption_text, &ZeroPadding).unwrap();
     assert_eq!(blocks.len(), 1);
 
     let key_text = b"0123456789abcdef";
@@ -65,7 +72,7 @@ fn single_block_aes128_zero_padding() {
 #[test]
 fn single_block_aes192_pkcs() {
     let encryption_text = b"I use Rust btw";
-    let mut blocks = Block::load(encryption_text, &Pkcs7Padding);
+    let mut blocks = Block::load(encryption_text, &Pkcs7Padding).unwrap();
     assert_eq!(blocks.len(), 1);
 
     let key_text = b"0123456789abcdef01234567";
@@ -84,7 +91,7 @@ fn single_block_aes192_pkcs() {
 #[test]
 fn single_block_aes256_pkcs() {
     let encryption_text = b"I use Rust btw";
-    let mut blocks = Block::load(encryption_text, &Pkcs7Padding);
+    let mut blocks = Block::load(encryption_text, &Pkcs7Padding).unwrap();
     assert_eq!(blocks.len(), 1);
 
     let key_text = b"0123456789abcdef0123456789abcdef";
@@ -103,7 +110,7 @@ fn single_block_aes256_pkcs() {
 #[test]
 fn multiple_blocks_aes128_pkcs() {
     let encryption_text = b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor nec feugiat nisl pretium fusce";
-    let mut blocks = Block::load(encryption_text, &Pkcs7Padding);
+    let mut blocks = Block::load(encryption_text, &Pkcs7Padding).unwrap();
 
     let key_text = b"0123456789abcdef";
     let key = AES128Key::from_bytes(*key_text);
@@ -181,3 +188,547 @@ fn raw_bytes_aes128_pkcs_cbc() {
 
     assert_eq!(encrypted_bytes, expected_bytes);
 }
+
+#[test]
+fn output_len_of_modes_and_paddings() {
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+
+    for mode in [
+        EncryptionMode::ECB,
+        EncryptionMode::CBC(iv),
+        EncryptionMode::PCBC(iv),
+    ] {
+        assert_eq!(output_len(0, &mode, &Pkcs7Padding), 16);
+        assert_eq!(output_len(14, &mode, &Pkcs7Padding), 16);
+        assert_eq!(output_len(16, &mode, &Pkcs7Padding), 32);
+        assert_eq!(output_len(100, &mode, &Pkcs7Padding), 112);
+
+        assert_eq!(output_len(0, &mode, &ZeroPadding), 0);
+        assert_eq!(output_len(14, &mode, &ZeroPadding), 16);
+        assert_eq!(output_len(16, &mode, &ZeroPadding), 16);
+
+        assert_eq!(output_len(14, &mode, &BytePadding(0x69)), 16);
+        assert_eq!(output_len(32, &mode, &BytePadding(0x69)), 32);
+
+        assert_eq!(output_len(0, &mode, &NoPadding), 0);
+        assert_eq!(output_len(32, &mode, &NoPadding), 32);
+    }
+}
+
+#[test]
+fn output_len_of_stream_modes() {
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+
+    for mode in [
+        EncryptionMode::CTR(0),
+        EncryptionMode::CFB(iv),
+        EncryptionMode::OFB(iv),
+    ] {
+        assert_eq!(output_len(0, &mode, &Pkcs7Padding), 0);
+        assert_eq!(output_len(14, &mode, &Pkcs7Padding), 14);
+        assert_eq!(output_len(16, &mode, &ZeroPadding), 16);
+        assert_eq!(output_len(100, &mode, &NoPadding), 100);
+    }
+}
+
+#[test]
+fn misaligned_input_without_padding() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+
+    assert_eq!(
+        try_encrypt_bytes(b"I use Rust btw", &key, &NoPadding, EncryptionMode::CBC(iv)),
+        Err(PaddingError::Misaligned)
+    );
+
+    // the stream modes don't pad at all
+    assert_eq!(
+        try_encrypt_bytes(b"I use Rust btw", &key, &NoPadding, EncryptionMode::OFB(iv)).unwrap(),
+        encrypt_bytes(b"I use Rust btw", &key, &NoPadding, EncryptionMode::OFB(iv))
+    );
+}
+
+#[test]
+fn output_len_matches_encryption() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+    let text = b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor nec feugiat nisl pretium fusce";
+
+    for len in [0, 1, 14, 15, 16, 17, 32, 48, text.len()] {
+        let bytes = &text[..len];
+
+        fn check<P: Padding<16>>(bytes: &[u8], key: &AES128Key, mode: EncryptionMode, padding: &P) {
+            let expected = output_len(bytes.len(), &mode, padding);
+            assert_eq!(encrypt_bytes(bytes, key, padding, mode).len(), expected);
+        }
+
+        check(bytes, &key, EncryptionMode::ECB, &Pkcs7Padding);
+        check(bytes, &key, EncryptionMode::CBC(iv), &Pkcs7Padding);
+        check(bytes, &key, EncryptionMode::CBC(iv), &BytePadding(0x69));
+        check(bytes, &key, EncryptionMode::CBC(iv), &ZeroPadding);
+
+        if len % 16 == 0 {
+            check(bytes, &key, EncryptionMode::CBC(iv), &NoPadding);
+        }
+    }
+}
+
+/// Stands in for an external key store that only hands out precomputed round keys
+struct MockProvider {
+    round_keys: [Subkey; 11],
+    requests: Cell<usize>,
+}
+
+impl RoundKeyProvider<11> for MockProvider {
+    fn round_keys(&self) -> [Subkey; 11] {
+        self.requests.set(self.requests.get() + 1);
+        self.round_keys
+    }
+}
+
+#[test]
+fn round_key_provider_known_answer() {
+    // key schedule of b"0123456789abcdef"
+    let provider = MockProvider {
+        round_keys: [
+            0x30313233343536373839616263646566,
+            0x727c01c8464937ff7e70569d1d1433fb,
+            0x8abf0e6cccf63993b2866f0eaf925cf5,
+            0xc1f5e8150d03d186bf85be881017e27d,
+            0x396d17df346ec6598beb78d19bfc9aac,
+            0x99d586cbadbb409226503843bdaca2ef,
+            0x28ef59b185541923a30421601ea8838f,
+            0xaa032ac32f5733e08c53128092fb910f,
+            0x25825c8c0ad56f6c86867dec147dece3,
+            0xc14c4d76cb99221a4d1f5ff65962b315,
+            0x5d2114bd96b836a7dba7695182c5da44,
+        ],
+        requests: Cell::new(0),
+    };
+
+    let plaintext = *b"felis eget nunc!";
+    let mut block = Block::from_bytes(plaintext);
+
+    encrypt_block(&mut block, &provider);
+    assert_eq!(
+        block.dump_bytes(),
+        0x4d7c347ebc4aeeec89a54b6f63f4d694_u128.to_be_bytes()
+    );
+
+    decrypt_block(&mut block, &provider);
+    assert_eq!(block.dump_bytes(), plaintext);
+
+    assert_eq!(provider.requests.get(), 2);
+}
+
+#[test]
+fn identical_records_differ() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let records: [&[u8]; 3] = [b"Rust", b"Rust", b"Rust"];
+
+    let encrypted = encrypt_records(&records, &key, 42);
+    assert_eq!(encrypted.len(), 3);
+    assert!(encrypted.iter().all(|r| r.len() == 16));
+    assert_ne!(encrypted[0], encrypted[1]);
+    assert_ne!(encrypted[1], encrypted[2]);
+    assert_ne!(encrypted[0], encrypted[2]);
+
+    // deterministic for the same tweaks
+    assert_eq!(encrypt_records(&records, &key, 42), encrypted);
+    assert_eq!(encrypt_records(&records[1..], &key, 43), encrypted[1..]);
+}
+
+#[test]
+fn cbc_with_direct_iv() {
+    let key = AES192Key::from_bytes(*b"0123456789abcdef01234567");
+    let messages: [&[u8]; 3] = [
+        b"",
+        b"I use Rust btw",
+        b"felis eget nunc lobortis mattis aliquam",
+    ];
+
+    for (i, message) in messages.into_iter().enumerate() {
+        let iv = InitializationVector::from(i as u128);
+
+        assert_eq!(
+            encrypt_bytes_cbc(message, &key, &Pkcs7Padding, iv),
+            encrypt_bytes(message, &key, &Pkcs7Padding, EncryptionMode::CBC(iv))
+        );
+    }
+}
+
+#[test]
+fn ctr_nist_vector() {
+    // see NIST SP 800-38A, F.5.1
+    let key = AES128Key::from_bytes(0x2b7e151628aed2a6abf7158809cf4f3c_u128.to_be_bytes());
+    let counter = 0xf0f1f2f3f4f5f6f7f8f9fafbfcfdfeff;
+
+    let plaintext: Vec<u8> = [
+        0x6bc1bee22e409f96e93d7e117393172a_u128,
+        0xae2d8a571e03ac9c9eb76fac45af8e51,
+        0x30c81c46a35ce411e5fbc1191a0a52ef,
+        0xf69f2445df4f9b17ad2b417be66c3710,
+    ]
+    .iter()
+    .flat_map(|b| b.to_be_bytes())
+    .collect();
+    let expected: Vec<u8> = [
+        0x874d6191b620e3261bef6864990db6ce_u128,
+        0x9806f66b7970fdff8617187bb9fffdff,
+        0x5ae4df3edbd5d35e5b4f09020db03eab,
+        0x1e031dda2fbe03d1792170a0f3009cee,
+    ]
+    .iter()
+    .flat_map(|b| b.to_be_bytes())
+    .collect();
+
+    let encrypted = encrypt_bytes(&plaintext, &key, &NoPadding, EncryptionMode::CTR(counter));
+    assert_eq!(encrypted, expected);
+
+    // partial blocks use a truncated keystream and are not padded
+    let encrypted = encrypt_bytes(
+        &plaintext[..37],
+        &key,
+        &Pkcs7Padding,
+        EncryptionMode::CTR(counter),
+    );
+    assert_eq!(encrypted, expected[..37]);
+}
+
+#[test]
+fn ctr_counter_wraps_around() {
+    let key = AES128Key::from_bytes(0x2b7e151628aed2a6abf7158809cf4f3c_u128.to_be_bytes());
+
+    let keystream = encrypt_bytes(&[0; 32], &key, &NoPadding, EncryptionMode::CTR(u128::MAX));
+
+    let mut block = Block::from(0);
+    encrypt_block(&mut block, &key);
+    assert_eq!(keystream[16..], block.dump_bytes());
+    assert_eq!(
+        keystream[..16],
+        0x8af2860142f786f409307c1a3f7eaaac_u128.to_be_bytes()
+    );
+}
+
+#[test]
+fn cfb_nist_vector() {
+    // see NIST SP 800-38A, F.3.13
+    let key = AES128Key::from_bytes(0x2b7e151628aed2a6abf7158809cf4f3c_u128.to_be_bytes());
+    let iv = InitializationVector::from(0x000102030405060708090a0b0c0d0e0f);
+
+    let plaintext: Vec<u8> = [
+        0x6bc1bee22e409f96e93d7e117393172a_u128,
+        0xae2d8a571e03ac9c9eb76fac45af8e51,
+        0x30c81c46a35ce411e5fbc1191a0a52ef,
+        0xf69f2445df4f9b17ad2b417be66c3710,
+    ]
+    .iter()
+    .flat_map(|b| b.to_be_bytes())
+    .collect();
+    let expected: Vec<u8> = [
+        0x3b3fd92eb72dad20333449f8e83cfb4a_u128,
+        0xc8a64537a0b3a93fcde3cdad9f1ce58b,
+        0x26751f67a3cbb140b1808cf187a4f4df,
+        0xc04b05357c5d1c0eeac4c66f9ff7f2e6,
+    ]
+    .iter()
+    .flat_map(|b| b.to_be_bytes())
+    .collect();
+
+    let encrypted = encrypt_bytes(&plaintext, &key, &NoPadding, EncryptionMode::CFB(iv));
+    assert_eq!(encrypted, expected);
+
+    let encrypted = encrypt_bytes(
+        &plaintext[..37],
+        &key,
+        &Pkcs7Padding,
+        EncryptionMode::CFB(iv),
+    );
+    assert_eq!(encrypted, expected[..37]);
+}
+
+#[test]
+fn keystream_with_custom_counter_blocks() {
+    // see RFC 3686, Test Vector #2: nonce || IV || 32 bit block counter starting at 1
+    let key = AES128Key::from_bytes(0x7e24067817fae0d743d6ce1f32539163_u128.to_be_bytes());
+    let counter_blocks = (1u32..).map(|i| {
+        let mut block = [0; 16];
+        block[..4].copy_from_slice(&[0x00, 0x6c, 0xb6, 0xdb]);
+        block[4..12].copy_from_slice(&0xc0543b59da48d90b_u64.to_be_bytes());
+        block[12..].copy_from_slice(&i.to_be_bytes());
+        block
+    });
+
+    let plaintext: Vec<u8> = (0..32).collect();
+    let expected: Vec<u8> = [
+        0x5104a106168a72d9790d41ee8edad388_u128,
+        0xeb2e1efc46da57c8fce630df9141be28,
+    ]
+    .iter()
+    .flat_map(|b| b.to_be_bytes())
+    .collect();
+
+    let encrypted = keystream_with(&plaintext, &key, counter_blocks.clone());
+    assert_eq!(encrypted, expected);
+    assert_eq!(keystream_with(&encrypted, &key, counter_blocks), plaintext);
+}
+
+#[test]
+fn keystream_with_matches_ctr() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let text = b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor";
+
+    let counter_blocks = (42u128..).map(u128::to_be_bytes);
+    assert_eq!(
+        keystream_with(text, &key, counter_blocks),
+        encrypt_bytes(text, &key, &NoPadding, EncryptionMode::CTR(42))
+    );
+}
+
+#[test]
+#[should_panic(expected = "Ran out of counter blocks")]
+fn keystream_with_too_few_counter_blocks() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    keystream_with(&[0; 17], &key, [[0; 16]]);
+}
+
+#[test]
+fn ofb_nist_vector() {
+    // see NIST SP 800-38A, F.4.1
+    let key = AES128Key::from_bytes(0x2b7e151628aed2a6abf7158809cf4f3c_u128.to_be_bytes());
+    let iv = InitializationVector::from(0x000102030405060708090a0b0c0d0e0f);
+
+    let plaintext: Vec<u8> = [
+        0x6bc1bee22e409f96e93d7e117393172a_u128,
+        0xae2d8a571e03ac9c9eb76fac45af8e51,
+        0x30c81c46a35ce411e5fbc1191a0a52ef,
+        0xf69f2445df4f9b17ad2b417be66c3710,
+    ]
+    .iter()
+    .flat_map(|b| b.to_be_bytes())
+    .collect();
+    let expected: Vec<u8> = [
+        0x3b3fd92eb72dad20333449f8e83cfb4a_u128,
+        0x7789508d16918f03f53c52dac54ed825,
+        0x9740051e9c5fecf64344f7a82260edcc,
+        0x304c6528f659c77866a510d9c1d6ae5e,
+    ]
+    .iter()
+    .flat_map(|b| b.to_be_bytes())
+    .collect();
+
+    let encrypted = encrypt_bytes(&plaintext, &key, &NoPadding, EncryptionMode::OFB(iv));
+    assert_eq!(encrypted, expected);
+}
+
+#[test]
+fn pcbc_known_answer() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+
+    let encrypted = encrypt_bytes(
+        b"felis eget nunc lobortis mattis aliquam",
+        &key,
+        &Pkcs7Padding,
+        EncryptionMode::PCBC(iv),
+    );
+
+    let expected: Vec<u8> = [
+        0x2cb2105e276cc531b8d6066aca8fcb73_u128,
+        0x94e0654ae85f34585520060c50cfd091,
+        0xc1986c3e9c2a080a6d74b0df644236ac,
+    ]
+    .iter()
+    .flat_map(|b| b.to_be_bytes())
+    .collect();
+
+    assert_eq!(encrypted, expected);
+}
+
+#[test]
+fn cts_rfc3962() {
+    // see https://www.rfc-editor.org/rfc/rfc3962#appendix-B
+    let key = AES128Key::from_bytes(*b"chicken teriyaki");
+    let iv = InitializationVector::from(0);
+    let text = b"I would like the General Gau's Chicken, please, ";
+
+    let vectors: [(usize, &[u128]); 4] = [
+        (17, &[0xc6353568f2bf8cb4d8a580362da7ff7f, 0x97 << 120]),
+        (
+            31,
+            &[
+                0xfc00783e0efdb2c1d445d4c8eff7ed22,
+                0x97687268d6ecccc0c07b25e25ecfe5 << 8,
+            ],
+        ),
+        (
+            32,
+            &[
+                0x39312523a78662d5be7fcbcc98ebf5a8,
+                0x97687268d6ecccc0c07b25e25ecfe584,
+            ],
+        ),
+        (
+            47,
+            &[
+                0x97687268d6ecccc0c07b25e25ecfe584,
+                0xb3fffd940c16a18c1b5549d2f838029e,
+                0x39312523a78662d5be7fcbcc98ebf5 << 8,
+            ],
+        ),
+    ];
+
+    for (len, expected) in vectors {
+        let expected: Vec<u8> = expected.iter().flat_map(|b| b.to_be_bytes()).collect();
+
+        let encrypted = encrypt_bytes_cts(&text[..len], &key, iv).unwrap();
+        assert_eq!(encrypted, expected[..len]);
+    }
+}
+
+#[test]
+fn cts_needs_more_than_one_block() {
+    let key = AES128Key::from_bytes(*b"chicken teriyaki");
+    let iv = InitializationVector::from(0);
+
+    for text in [&b""[..], b"I use Rust btw", b"0123456789abcdef"] {
+        assert_eq!(encrypt_bytes_cts(text, &key, iv), Err(CtsError::TooShort));
+    }
+}
+
+#[test]
+fn large_ecb_input_matches_single_blocks() {
+    // large enough to be split across threads with the `parallel` feature
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let plaintext: Vec<u8> = (0..16 * 20_000).map(|i| (i % 251) as u8).collect();
+
+    let expected: Vec<u8> = plaintext
+        .chunks_exact(16)
+        .flat_map(|chunk| {
+            let mut block = Block::from_bytes(chunk.try_into().unwrap());
+            encrypt_block(&mut block, &key);
+            block.dump_bytes()
+        })
+        .collect();
+
+    assert_eq!(
+        encrypt_bytes(&plaintext, &key, &NoPadding, EncryptionMode::ECB),
+        expected
+    );
+}
+
+#[test]
+fn large_ctr_input_matches_keystream() {
+    // large enough to be split across threads with the `parallel` feature
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let plaintext: Vec<u8> = (0..16 * 20_000 + 7).map(|i| (i % 251) as u8).collect();
+
+    // the counter wraps around in the middle
+    let counter = u128::MAX - 10_000;
+    let counter_blocks =
+        || std::iter::successors(Some(counter), |c| Some(c.wrapping_add(1))).map(u128::to_be_bytes);
+
+    for len in [16 * 1024 - 1, 16 * 1024, 16 * 5_000 + 9, plaintext.len()] {
+        assert_eq!(
+            encrypt_bytes(
+                &plaintext[..len],
+                &key,
+                &NoPadding,
+                EncryptionMode::CTR(counter)
+            ),
+            keystream_with(&plaintext[..len], &key, counter_blocks()),
+            "{len} bytes"
+        );
+    }
+}
+
+#[test]
+fn cipher_matches_key() {
+    let key = AES192Key::from_bytes(*b"0123456789abcdef01234567");
+    let cipher = Cipher::new(&key);
+
+    let mut block = Block::from_bytes(*b"felis eget nunc!");
+    let mut expected = block;
+
+    cipher.encrypt_block(&mut block);
+    encrypt_block(&mut expected, &key);
+    assert_eq!(block, expected);
+
+    cipher.decrypt_block(&mut block);
+    assert_eq!(block.dump_bytes(), *b"felis eget nunc!");
+
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+    let plaintext = b"felis eget nunc lobortis mattis aliquam";
+    assert_eq!(
+        encrypt_bytes(plaintext, &cipher, &Pkcs7Padding, EncryptionMode::CBC(iv)),
+        encrypt_bytes(plaintext, &key, &Pkcs7Padding, EncryptionMode::CBC(iv))
+    );
+}
+
+#[test]
+fn key_schedule_runs_once_per_call() {
+    let provider = MockProvider {
+        round_keys: AES128Key::from_bytes(*b"0123456789abcdef").round_keys(),
+        requests: Cell::new(0),
+    };
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+
+    let encrypted = encrypt_bytes(
+        &[0x42; 100],
+        &provider,
+        &Pkcs7Padding,
+        EncryptionMode::CBC(iv),
+    );
+    assert_eq!(provider.requests.get(), 1);
+
+    decrypt_bytes(
+        &encrypted,
+        &provider,
+        Some(Pkcs7Padding),
+        EncryptionMode::CBC(iv),
+    )
+    .unwrap();
+    assert_eq!(provider.requests.get(), 2);
+}
+
+#[test]
+fn ctr_keystream_twice_is_identity() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let counter = u128::MAX - 1;
+    let plaintext = b"felis eget nunc lobortis mattis aliquam";
+
+    let encrypted: Vec<u8> = plaintext
+        .iter()
+        .zip(CtrKeystream::new(&key, counter))
+        .map(|(b, k)| b ^ k)
+        .collect();
+    assert_eq!(
+        encrypted,
+        encrypt_bytes(plaintext, &key, &NoPadding, EncryptionMode::CTR(counter))
+    );
+
+    let decrypted: Vec<u8> = encrypted
+        .iter()
+        .zip(CtrKeystream::new(&key, counter))
+        .map(|(b, k)| b ^ k)
+        .collect();
+    assert_eq!(decrypted, plaintext);
+}
+
+#[test]
+fn ctr_keystream_seek() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let keystream: Vec<u8> = CtrKeystream::new(&key, 7).take(64).collect();
+
+    let mut seeking = CtrKeystream::new(&key, 7);
+    assert_eq!(seeking.nth(5), Some(keystream[5]));
+
+    seeking.seek(9);
+    assert!(seeking
+        .by_ref()
+        .take(32)
+        .eq(keystream[32..].iter().copied()));
+
+    seeking.seek(7);
+    assert!(seeking.take(16).eq(keystream[..16].iter().copied()));
+}
@@ -0,0 +1,70 @@
+use aesculap::etm::{encrypt_then_mac, verify_then_decrypt, EtmError, TAG_SIZE};
+use aesculap::key::{AES128Key, AES256Key};
+use aesculap::padding::{NoPadding, PaddingError, Pkcs7Padding};
+use aesculap::{EncryptionMode, InitializationVector};
+
+const PLAINTEXT: &[u8] = b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor";
+
+const MAC_KEY: &[u8] = b"an independent key for the hmac";
+
+#[test]
+fn round_trip() {
+    let key = AES256Key::from_bytes(*b"0123456789abcdef0123456789abcdef");
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+
+    for mode in [
+        EncryptionMode::ECB,
+        EncryptionMode::CBC(iv),
+        EncryptionMode::CTR(0x1234),
+        EncryptionMode::CFB(iv),
+        EncryptionMode::OFB(iv),
+        EncryptionMode::PCBC(iv),
+    ] {
+        let data =
+            encrypt_then_mac(PLAINTEXT, &key, MAC_KEY, &Pkcs7Padding, mode, b"header").unwrap();
+        assert_eq!(
+            verify_then_decrypt(&data, &key, MAC_KEY, Some(Pkcs7Padding), mode, b"header").unwrap(),
+            PLAINTEXT
+        );
+    }
+}
+
+#[test]
+fn tampered_ciphertext_is_rejected() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let mode = EncryptionMode::CBC(InitializationVector::from_bytes(*b"abcdef0123456789"));
+
+    let data = encrypt_then_mac(PLAINTEXT, &key, MAC_KEY, &Pkcs7Padding, mode, &[]).unwrap();
+
+    for i in [0, data.len() - TAG_SIZE - 1, data.len() - 1] {
+        let mut tampered = data.clone();
+        tampered[i] ^= 1;
+        assert_eq!(
+            verify_then_decrypt(&tampered, &key, MAC_KEY, Some(Pkcs7Padding), mode, &[]),
+            Err(EtmError::AuthenticationFailed)
+        );
+    }
+
+    let truncated = &data[..data.len() - 16];
+    assert_eq!(
+        verify_then_decrypt(truncated, &key, MAC_KEY, Some(Pkcs7Padding), mode, &[]),
+        Err(EtmError::AuthenticationFailed)
+    );
+}
+
+#[test]
+fn misaligned_input_without_padding_fails() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+
+    assert_eq!(
+        encrypt_then_mac(
+            PLAINTEXT,
+            &key,
+            MAC_KEY,
+            &NoPadding,
+            EncryptionMode::ECB,
+            &[]
+        ),
+        Err(PaddingError::Misaligned)
+    );
+}
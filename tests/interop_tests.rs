@@ -0,0 +1,41 @@
+#![cfg(feature = "openssl-compat")]
+
+use aesculap::hex;
+use aesculap::interop::{decrypt_salted, encrypt_salted, split_salted_header};
+
+const PLAINTEXT: &[u8] = b"I use Rust btw, and OpenSSL too.\n";
+
+#[test]
+fn decrypt_openssl_output() {
+    // printf 'I use Rust btw, and OpenSSL too.\n' | openssl enc -aes-256-cbc -md md5 -pass pass:correct-horse
+    let data = hex::decode(
+        "53616c7465645f5fbdbb49bbcc4e04aa8cf64c420b2236fdc96f66ec8d3d8653\
+         225956873cae2afc4745db1b190dd2fd8397a9672224ddafca0d8f96a880b5d4",
+    )
+    .unwrap();
+    assert_eq!(
+        decrypt_salted(&data, b"correct-horse", 256).unwrap(),
+        PLAINTEXT
+    );
+    assert!(decrypt_salted(&data, b"wrong horse", 256).is_err());
+
+    // printf 'I use Rust btw, and OpenSSL too.\n' | openssl enc -aes-128-cbc -md md5 -pass pass:secret
+    let data = hex::decode(
+        "53616c7465645f5fc98cc01bf05808c2f03fb5d399ec5f88b2be2d4c7059858c\
+         067ec2f797492ebb5fc0245dd18e43d80a926a2d2347d8f2f8da8743b01921e4",
+    )
+    .unwrap();
+    assert_eq!(decrypt_salted(&data, b"secret", 128).unwrap(), PLAINTEXT);
+}
+
+#[test]
+fn encrypt_like_openssl() {
+    let data = hex::decode(
+        "53616c7465645f5fbdbb49bbcc4e04aa8cf64c420b2236fdc96f66ec8d3d8653\
+         225956873cae2afc4745db1b190dd2fd8397a9672224ddafca0d8f96a880b5d4",
+    )
+    .unwrap();
+    let (salt, _) = split_salted_header(&data).unwrap();
+
+    assert_eq!(encrypt_salted(PLAINTEXT, b"correct-horse", salt, 256), data);
+}
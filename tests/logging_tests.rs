@@ -0,0 +1,47 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use aesculap::encryption::encrypt_bytes;
+use aesculap::key::AES128Key;
+use aesculap::padding::Pkcs7Padding;
+use aesculap::{EncryptionMode, InitializationVector, PER_BLOCK_LOG_TARGET};
+
+struct CountingLogger {
+    per_block: AtomicUsize,
+    debug: AtomicUsize,
+}
+
+impl log::Log for CountingLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        if record.target() == PER_BLOCK_LOG_TARGET {
+            self.per_block.fetch_add(1, Ordering::SeqCst);
+        } else if record.level() <= log::Level::Debug {
+            self.debug.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CountingLogger = CountingLogger {
+    per_block: AtomicUsize::new(0),
+    debug: AtomicUsize::new(0),
+};
+
+#[test]
+fn per_block_messages_use_separate_target() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+    let plaintext = vec![0x42; 16 * 10_000];
+
+    encrypt_bytes(&plaintext, &key, &Pkcs7Padding, EncryptionMode::CBC(iv));
+
+    assert_eq!(LOGGER.per_block.load(Ordering::SeqCst), 10_001);
+    assert_eq!(LOGGER.debug.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,105 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+
+use aesculap::key::AES128Key;
+use aesculap::multi::{decrypt_stream_multi, encrypt_message};
+use aesculap::InitializationVector;
+
+#[test]
+fn three_messages_through_one_file() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let messages: [&[u8]; 3] = [
+        b"I use Rust btw",
+        b"",
+        b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor",
+    ];
+
+    let path = std::env::temp_dir().join(format!("aesculap-{}-multi", std::process::id()));
+
+    let mut file = File::create(&path).unwrap();
+    for (i, message) in messages.iter().enumerate() {
+        let iv = InitializationVector::from(i as u128);
+        file.write_all(&encrypt_message(message, &key, iv)).unwrap();
+    }
+    drop(file);
+
+    let reader = BufReader::new(File::open(&path).unwrap());
+    let decrypted: Vec<Vec<u8>> = decrypt_stream_multi(reader, &key)
+        .collect::<io::Result<_>>()
+        .unwrap();
+
+    assert_eq!(decrypted, messages);
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn truncated_record() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+
+    let mut stream = encrypt_message(b"I use Rust btw", &key, InitializationVector::from(1));
+    stream.extend(encrypt_message(
+        b"felis eget",
+        &key,
+        InitializationVector::from(2),
+    ));
+    stream.truncate(stream.len() - 1);
+
+    let mut reader = decrypt_stream_multi(stream.as_slice(), &key);
+
+    assert_eq!(reader.next().unwrap().unwrap(), b"I use Rust btw");
+    assert_eq!(
+        reader.next().unwrap().unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+    assert!(reader.next().is_none());
+}
+
+/// Reader that hands out a single byte per call and is interrupted in between
+struct Trickle<'a> {
+    bytes: &'a [u8],
+    interrupt: bool,
+}
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.interrupt = !self.interrupt;
+        if self.interrupt {
+            return Err(io::ErrorKind::Interrupted.into());
+        }
+
+        let n = self.bytes.len().min(buf.len()).min(1);
+        buf[..n].copy_from_slice(&self.bytes[..n]);
+        self.bytes = &self.bytes[n..];
+
+        Ok(n)
+    }
+}
+
+#[test]
+fn short_reads() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let messages: [&[u8]; 2] = [
+        b"felis eget nunc lobortis mattis aliquam faucibus purus in massa tempor",
+        b"I use Rust btw",
+    ];
+
+    let mut stream = Vec::new();
+    for (i, message) in messages.iter().enumerate() {
+        stream.extend(encrypt_message(
+            message,
+            &key,
+            InitializationVector::from(i as u128),
+        ));
+    }
+
+    let reader = Trickle {
+        bytes: &stream,
+        interrupt: false,
+    };
+    let decrypted: Vec<Vec<u8>> = decrypt_stream_multi(reader, &key)
+        .collect::<io::Result<_>>()
+        .unwrap();
+
+    assert_eq!(decrypted, messages);
+}
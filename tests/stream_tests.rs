@@ -0,0 +1,158 @@
+use std::io::{self, Read, Write};
+
+use aesculap::decryption::decrypt_bytes;
+use aesculap::encryption::encrypt_bytes;
+use aesculap::key::AES128Key;
+use aesculap::padding::{NoPadding, Pkcs7Padding, ZeroPadding};
+use aesculap::stream::{DecryptingReader, EncryptingWriter};
+use aesculap::{EncryptionMode, InitializationVector};
+
+fn modes() -> [EncryptionMode; 6] {
+    let iv = InitializationVector::from_bytes(*b"abcdef0123456789");
+
+    [
+        EncryptionMode::ECB,
+        EncryptionMode::CBC(iv),
+        EncryptionMode::PCBC(iv),
+        EncryptionMode::CTR(u128::MAX - 1),
+        EncryptionMode::CFB(iv),
+        EncryptionMode::OFB(iv),
+    ]
+}
+
+#[test]
+fn odd_chunks_match_one_shot() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let plaintext: Vec<u8> = (0..100).collect();
+
+    for mode in modes() {
+        for len in [0, 1, 15, 16, 17, 48, 100] {
+            let expected = encrypt_bytes(&plaintext[..len], &key, &Pkcs7Padding, mode);
+
+            for chunk_size in [1, 3, 7, 16, 17, 33] {
+                let mut writer = EncryptingWriter::new(Vec::new(), &key, Pkcs7Padding, mode);
+                for chunk in plaintext[..len].chunks(chunk_size) {
+                    writer.write_all(chunk).unwrap();
+                }
+
+                assert_eq!(writer.finish().unwrap(), expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn padding_of_the_last_block() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let plaintext = b"felis eget nunc lobortis mattis aliquam";
+
+    let mut writer = EncryptingWriter::new(Vec::new(), &key, ZeroPadding, EncryptionMode::ECB);
+    writer.write_all(plaintext).unwrap();
+    assert_eq!(
+        writer.finish().unwrap(),
+        encrypt_bytes(plaintext, &key, &ZeroPadding, EncryptionMode::ECB)
+    );
+
+    let mut writer = EncryptingWriter::new(Vec::new(), &key, NoPadding, EncryptionMode::ECB);
+    writer.write_all(plaintext).unwrap();
+    assert_eq!(
+        writer.finish().unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+fn complete_blocks_are_written_immediately() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let mut output = Vec::new();
+
+    let mut writer = EncryptingWriter::new(&mut output, &key, Pkcs7Padding, EncryptionMode::ECB);
+    writer.write_all(&[0x69; 20]).unwrap();
+    writer.flush().unwrap();
+    drop(writer);
+
+    assert_eq!(output.len(), 16);
+}
+
+/// Read everything in increments of `step` bytes
+fn read_in_steps(mut reader: impl Read, step: usize) -> io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut buf = vec![0; step];
+
+    loop {
+        match reader.read(&mut buf)? {
+            0 => return Ok(output),
+            n => output.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+#[test]
+fn reading_in_increments_reconstructs_plaintext() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let plaintext: Vec<u8> = (0..10000).map(|i| (i * 7 % 251) as u8).collect();
+
+    for mode in modes() {
+        for len in [0, 1, 15, 16, 17, 255, 256, 257, 4095, 4096, 10000] {
+            let ciphertext = encrypt_bytes(&plaintext[..len], &key, &Pkcs7Padding, mode);
+
+            for step in [1, 7, 16] {
+                let reader = DecryptingReader::new(&ciphertext[..], &key, Some(Pkcs7Padding), mode);
+                assert_eq!(read_in_steps(reader, step).unwrap(), &plaintext[..len]);
+            }
+        }
+    }
+}
+
+#[test]
+fn reading_without_padding_matches_one_shot() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let ciphertext: Vec<u8> = (0..5008).map(|i| (i % 256) as u8).collect();
+
+    for mode in modes() {
+        let expected = decrypt_bytes(&ciphertext, &key, None::<NoPadding>, mode).unwrap();
+
+        let reader = DecryptingReader::new(&ciphertext[..], &key, None::<NoPadding>, mode);
+        assert_eq!(read_in_steps(reader, 7).unwrap(), expected);
+    }
+}
+
+#[test]
+fn misaligned_ciphertext_is_rejected() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let ciphertext = [0x42; 4097];
+
+    let reader = DecryptingReader::new(
+        &ciphertext[..],
+        &key,
+        None::<NoPadding>,
+        EncryptionMode::ECB,
+    );
+    assert_eq!(
+        read_in_steps(reader, 16).unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+
+    let reader = DecryptingReader::new(&ciphertext[..], &key, Some(Pkcs7Padding), modes()[1]);
+    assert_eq!(
+        read_in_steps(reader, 16).unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+}
+
+#[test]
+fn invalid_padding_is_rejected() {
+    let key = AES128Key::from_bytes(*b"0123456789abcdef");
+    let ciphertext = encrypt_bytes(&[0; 32], &key, &NoPadding, EncryptionMode::ECB);
+
+    let reader = DecryptingReader::new(
+        &ciphertext[..],
+        &key,
+        Some(Pkcs7Padding),
+        EncryptionMode::ECB,
+    );
+    assert_eq!(
+        read_in_steps(reader, 1).unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+}
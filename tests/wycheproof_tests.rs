@@ -0,0 +1,428 @@
+//! Test vectors in the format of [Wycheproof](https://github.com/google/wycheproof)
+//!
+//! Every file in `tests/vectors/wycheproof/` is parsed and each test case is run
+//! according to its `result`: `valid` cases must round trip, `invalid` cases must be rejected
+//! and `acceptable` cases may go either way but must not panic.
+//! Files of algorithms that are not implemented yet are skipped.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use aesculap::aead::{decrypt_gcm, encrypt_gcm, GcmError};
+use aesculap::decryption::{decrypt_bytes, DecryptError};
+use aesculap::encryption::encrypt_bytes;
+use aesculap::hex;
+use aesculap::key::{AES128Key, AES192Key, AES256Key, Key};
+use aesculap::mac::cmac;
+use aesculap::padding::Pkcs7Padding;
+use aesculap::{EncryptionMode, InitializationVector};
+
+const VECTOR_DIR: &str = "tests/vectors/wycheproof";
+
+/// Minimal JSON value, just enough for the Wycheproof files
+#[derive(Debug)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+impl Json {
+    fn parse(input: &str) -> Json {
+        let mut parser = Parser {
+            bytes: input.as_bytes(),
+            pos: 0,
+        };
+        let value = parser.value();
+        parser.whitespace();
+        assert_eq!(parser.pos, input.len(), "Trailing characters in JSON");
+        value
+    }
+
+    fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(map) => map.get(key).unwrap_or(&Json::Null),
+            _ => panic!("Expected JSON object, got {self:?}"),
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            Json::String(s) => s,
+            _ => panic!("Expected JSON string, got {self:?}"),
+        }
+    }
+
+    fn as_array(&self) -> &[Json] {
+        match self {
+            Json::Array(a) => a,
+            _ => panic!("Expected JSON array, got {self:?}"),
+        }
+    }
+
+    fn as_u64(&self) -> u64 {
+        match self {
+            Json::Number(n) => *n as u64,
+            _ => panic!("Expected JSON number, got {self:?}"),
+        }
+    }
+
+    fn hex(&self) -> Vec<u8> {
+        hex::decode(self.as_str()).unwrap()
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn whitespace(&mut self) {
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: u8) {
+        self.whitespace();
+        assert_eq!(
+            self.bytes[self.pos], c,
+            "Unexpected character at {}",
+            self.pos
+        );
+        self.pos += 1;
+    }
+
+    fn literal(&mut self, literal: &str, value: Json) -> Json {
+        assert!(self.bytes[self.pos..].starts_with(literal.as_bytes()));
+        self.pos += literal.len();
+        value
+    }
+
+    fn value(&mut self) -> Json {
+        self.whitespace();
+        match self.bytes[self.pos] {
+            b'{' => self.object(),
+            b'[' => self.array(),
+            b'"' => Json::String(self.string()),
+            b't' => self.literal("true", Json::Bool(true)),
+            b'f' => self.literal("false", Json::Bool(false)),
+            b'n' => self.literal("null", Json::Null),
+            _ => self.number(),
+        }
+    }
+
+    fn object(&mut self) -> Json {
+        self.expect(b'{');
+        let mut map = BTreeMap::new();
+        self.whitespace();
+        if self.bytes[self.pos] == b'}' {
+            self.pos += 1;
+            return Json::Object(map);
+        }
+        loop {
+            self.whitespace();
+            let key = self.string();
+            self.expect(b':');
+            map.insert(key, self.value());
+            self.whitespace();
+            self.pos += 1;
+            match self.bytes[self.pos - 1] {
+                b',' => continue,
+                b'}' => return Json::Object(map),
+                c => panic!("Unexpected character {:?} in object", c as char),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Json {
+        self.expect(b'[');
+        let mut items = Vec::new();
+        self.whitespace();
+        if self.bytes[self.pos] == b']' {
+            self.pos += 1;
+            return Json::Array(items);
+        }
+        loop {
+            items.push(self.value());
+            self.whitespace();
+            self.pos += 1;
+            match self.bytes[self.pos - 1] {
+                b',' => continue,
+                b']' => return Json::Array(items),
+                c => panic!("Unexpected character {:?} in array", c as char),
+            }
+        }
+    }
+
+    fn string(&mut self) -> String {
+        self.expect(b'"');
+        let mut s = String::new();
+        loop {
+            let c = self.bytes[self.pos];
+            self.pos += 1;
+            match c {
+                b'"' => return s,
+                b'\\' => {
+                    let escaped = self.bytes[self.pos];
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => s.push('\n'),
+                        b't' => s.push('\t'),
+                        b'u' => {
+                            let code =
+                                std::str::from_utf8(&self.bytes[self.pos..self.pos + 4]).unwrap();
+                            s.push(char::from_u32(u32::from_str_radix(code, 16).unwrap()).unwrap());
+                            self.pos += 4;
+                        }
+                        other => s.push(other as char),
+                    }
+                }
+                _ => {
+                    // copy a whole (possibly multi-byte) UTF-8 sequence
+                    let start = self.pos - 1;
+                    let mut end = self.pos;
+                    while end < self.bytes.len() && (self.bytes[end] & 0xc0) == 0x80 {
+                        end += 1;
+                    }
+                    s.push_str(std::str::from_utf8(&self.bytes[start..end]).unwrap());
+                    self.pos = end;
+                }
+            }
+        }
+    }
+
+    fn number(&mut self) -> Json {
+        let start = self.pos;
+        while self.pos < self.bytes.len()
+            && matches!(
+                self.bytes[self.pos],
+                b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'
+            )
+        {
+            self.pos += 1;
+        }
+        let number = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap();
+        Json::Number(number.parse().unwrap())
+    }
+}
+
+/// Expected outcome of a test case
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Expected {
+    Valid,
+    Invalid,
+    Acceptable,
+}
+
+impl Expected {
+    fn of(test: &Json) -> Expected {
+        match test.get("result").as_str() {
+            "valid" => Expected::Valid,
+            "invalid" => Expected::Invalid,
+            "acceptable" => Expected::Acceptable,
+            other => panic!("Unknown result {other:?}"),
+        }
+    }
+}
+
+/// Run all test cases of a file, returning the number of test cases that were run
+fn run_file(path: &Path) -> usize {
+    let doc = Json::parse(&fs::read_to_string(path).unwrap());
+    let algorithm = doc.get("algorithm").as_str();
+
+    let run_test: fn(&Json) -> bool = match algorithm {
+        "AES-CBC-PKCS5" => cbc_pkcs5,
+        "AES-GCM" => gcm,
+        "AES-CMAC" => aes_cmac,
+        _ => {
+            eprintln!("Skip {}: {algorithm} is not implemented", path.display());
+            return 0;
+        }
+    };
+
+    let mut count = 0;
+    for group in doc.get("testGroups").as_array() {
+        for test in group.get("tests").as_array() {
+            if run_test(test) {
+                count += 1;
+            }
+        }
+    }
+
+    assert_eq!(
+        doc.get("numberOfTests").as_u64() as usize,
+        doc.get("testGroups")
+            .as_array()
+            .iter()
+            .map(|g| g.get("tests").as_array().len())
+            .sum::<usize>(),
+        "Wrong numberOfTests in {}",
+        path.display()
+    );
+
+    count
+}
+
+/// Call `f` with the AES key that matches the length of `bytes`
+macro_rules! with_key {
+    ($bytes:expr, $f:ident($($arg:expr),*)) => {{
+        let bytes = $bytes;
+        match bytes.len() {
+            16 => $f(&AES128Key::from_bytes(bytes.try_into().unwrap()), $($arg),*),
+            24 => $f(&AES192Key::from_bytes(bytes.try_into().unwrap()), $($arg),*),
+            32 => $f(&AES256Key::from_bytes(bytes.try_into().unwrap()), $($arg),*),
+            len => panic!("Unsupported key size {len}"),
+        }
+    }};
+}
+
+/// Run an `IndCpaTest` case of AES-CBC with PKCS #7 padding
+fn cbc_pkcs5(test: &Json) -> bool {
+    let id = test.get("tcId").as_u64();
+    let expected = Expected::of(test);
+
+    let iv = InitializationVector::from_bytes(test.get("iv").hex().try_into().unwrap());
+    let msg = test.get("msg").hex();
+    let ct = test.get("ct").hex();
+
+    fn run<const R: usize, K: Key<R>>(
+        key: &K,
+        iv: InitializationVector,
+        msg: &[u8],
+        ct: &[u8],
+    ) -> (Vec<u8>, Result<Vec<u8>, DecryptError>) {
+        (
+            encrypt_bytes(msg, key, &Pkcs7Padding, EncryptionMode::CBC(iv)),
+            decrypt_bytes(ct, key, Some(Pkcs7Padding), EncryptionMode::CBC(iv)),
+        )
+    }
+
+    let (encrypted, decrypted) = with_key!(test.get("key").hex(), run(iv, &msg, &ct));
+
+    match expected {
+        Expected::Valid => {
+            assert_eq!(encrypted, ct, "tcId {id}: wrong ciphertext");
+            assert_eq!(decrypted, Ok(msg), "tcId {id}: wrong plaintext");
+        }
+        Expected::Invalid => assert!(decrypted.is_err(), "tcId {id}: accepted invalid input"),
+        Expected::Acceptable => (),
+    }
+
+    true
+}
+
+/// Run an `AeadTest` case of AES-GCM
+///
+/// Returns `false` if the test case was skipped.
+fn gcm(test: &Json) -> bool {
+    let id = test.get("tcId").as_u64();
+    let expected = Expected::of(test);
+
+    // only 96 bit nonces and full-size tags are supported
+    let Ok(iv) = <[u8; 12]>::try_from(test.get("iv").hex()) else {
+        return false;
+    };
+    let tag = test.get("tag").hex();
+    if tag.len() != 16 {
+        return false;
+    }
+
+    let aad = test.get("aad").hex();
+    let msg = test.get("msg").hex();
+    let ct = [test.get("ct").hex(), tag].concat();
+
+    fn run<const R: usize, K: Key<R>>(
+        key: &K,
+        iv: &[u8; 12],
+        aad: &[u8],
+        msg: &[u8],
+        ct: &[u8],
+    ) -> (Vec<u8>, Result<Vec<u8>, GcmError>) {
+        (
+            encrypt_gcm(key, iv, aad, msg),
+            decrypt_gcm(key, iv, aad, ct),
+        )
+    }
+
+    let (encrypted, decrypted) = with_key!(test.get("key").hex(), run(&iv, &aad, &msg, &ct));
+
+    match expected {
+        Expected::Valid => {
+            assert_eq!(encrypted, ct, "tcId {id}: wrong ciphertext");
+            assert_eq!(decrypted, Ok(msg), "tcId {id}: wrong plaintext");
+        }
+        Expected::Invalid => assert!(decrypted.is_err(), "tcId {id}: accepted invalid input"),
+        Expected::Acceptable => (),
+    }
+
+    true
+}
+
+/// Run a `MacTest` case of AES-CMAC
+///
+/// Returns `false` if the test case was skipped.
+fn aes_cmac(test: &Json) -> bool {
+    let id = test.get("tcId").as_u64();
+    let expected = Expected::of(test);
+
+    // only keys of the AES variants are supported
+    let key = test.get("key").hex();
+    if ![16, 24, 32].contains(&key.len()) {
+        return false;
+    }
+
+    let msg = test.get("msg").hex();
+    let tag = test.get("tag").hex();
+
+    fn run<const R: usize, K: Key<R>>(key: &K, msg: &[u8]) -> [u8; 16] {
+        cmac(key, msg)
+    }
+
+    let computed = with_key!(key, run(&msg));
+
+    match expected {
+        Expected::Valid => assert_eq!(computed[..], tag, "tcId {id}: wrong tag"),
+        Expected::Invalid => assert_ne!(computed[..], tag, "tcId {id}: accepted invalid tag"),
+        Expected::Acceptable => (),
+    }
+
+    true
+}
+
+#[test]
+fn wycheproof_vectors() {
+    let mut paths: Vec<_> = fs::read_dir(VECTOR_DIR)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    let count: usize = paths.iter().map(|path| run_file(path)).sum();
+    assert!(count > 0, "No test vectors were run");
+}
+
+#[test]
+fn json_parser() {
+    let doc = Json::parse(
+        r#" { "a": [1, 2.5e1, -3], "b": "x\"Aé", "c": {}, "d": [], "e": null, "f": true } "#,
+    );
+
+    let numbers: Vec<u64> = doc.get("a").as_array()[..2]
+        .iter()
+        .map(Json::as_u64)
+        .collect();
+    assert_eq!(numbers, [1, 25]);
+    assert_eq!(doc.get("b").as_str(), "x\"Aé");
+    assert!(matches!(doc.get("c"), Json::Object(map) if map.is_empty()));
+    assert!(doc.get("d").as_array().is_empty());
+    assert!(matches!(doc.get("e"), Json::Null));
+    assert!(matches!(doc.get("f"), Json::Bool(true)));
+    assert!(matches!(doc.get("missing"), Json::Null));
+}